@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Int,
@@ -8,16 +10,17 @@ pub enum Type {
     Any, // 用于尚未确定类型的表达式
 }
 
-impl Type {
-    pub fn to_string(&self) -> String {
-        match self {
-            Type::Int => "int".to_string(),
-            Type::Str => "str".to_string(),
-            Type::Bool => "bool".to_string(),
-            Type::Float => "float".to_string(),
-            Type::Double => "double".to_string(),
-            Type::Any => "any".to_string(),
-        }
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Str => "str",
+            Type::Bool => "bool",
+            Type::Float => "float",
+            Type::Double => "double",
+            Type::Any => "any",
+        };
+        write!(f, "{}", name)
     }
 }
 
@@ -33,42 +36,83 @@ impl Position {
     }
 }
 
+/// 结构体字段或枚举成员携带的值：名称和声明的类型，没有类型时可以保存任意值
+pub type Field = (String, Option<Type>);
+
+/// 函数参数：参数名和声明的类型，没有类型的参数可以接收任意值
+pub type Param = (String, Option<Type>);
+
+/// 枚举成员：成员名和它携带的字段
+pub type Variant = (String, Vec<Field>);
+
+/// 变量名在解析（resolve）之后绑定到的位置。局部变量按（向外经过的作用域层数，槽位）访问，
+/// 全局变量按全局槽位访问。宿主程序、REPL 和 import 都可能在运行时定义全局变量，
+/// 所以全局槽位中的变量可能还不存在，这时按名称查找同名的具名函数或报告未定义的变量。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Binding {
+    #[default]
+    Unresolved,
+    Local { depth: usize, slot: usize },
+    Global { slot: usize },
+}
+
 #[derive(Debug, Clone)]
 pub enum ASTNode {
     Print(Box<ASTNode>),
     Println(Box<ASTNode>),
     // 变量声明：<type>name = value 或 var <type>name = value
-    Var(String, Box<ASTNode>), // name, value
-    TypedVar(String, Type, Box<ASTNode>), // name, type, value
-    // 数组声明：var <int>name[size] = {init_values}
-    ArrayDecl(String, Type, usize, Vec<ASTNode>), // name, type, size, init_values
+    Var(String, Binding, Box<ASTNode>, Position), // name, binding, value, position
+    TypedVar(String, Binding, Type, Box<ASTNode>, Position), // name, binding, type, value, position
+    // 数组声明：var <int>name[size] = {init_values}，多维数组 var <int>name[rows][columns] = {{...}, ...}
+    ArrayDecl(String, Binding, Type, Vec<usize>, Vec<ASTNode>, Position), // name, binding, type, sizes from outer to inner, init_values, position
     // 列表声明：var name = []
-    ListDecl(String, Vec<ASTNode>), // name, init_values
-    // 数组/列表索引访问：name[index]
+    ListDecl(String, Binding, Vec<ASTNode>, Position), // name, binding, init_values, position
+    // 常量声明：const <type>NAME = value，类型可以省略
+    ConstDecl(String, Binding, Option<Type>, Box<ASTNode>, Position), // name, binding, type, value, position of the name
+    // 列表字面量：{value, ...} 或 []
+    ListLiteral(Vec<ASTNode>), // elements
+    // 映射字面量：{key: value, ...}，空映射写作 {:}
+    MapLiteral(Vec<(ASTNode, ASTNode)>, Position), // entries, position
+    // 数组/列表/映射索引访问：name[index]
     IndexAccess(Box<ASTNode>, Box<ASTNode>, Position), // array/list expression, index expression, position
-    // 数组/列表索引赋值：name[index] = value
-    IndexAssign(Box<ASTNode>, Box<ASTNode>, Box<ASTNode>, Position), // array/list expression, index expression, value expression, position
+    // 给变量中的元素或字段赋值：name[i][j] = value、name.a.b = value 或 name.items[0].x = value
+    PathAssign(String, Binding, Vec<Accessor>, Box<ASTNode>, Position), // name, binding, accessors from outer to inner, value, position
     // 新的for循环格式：for i in range(start, end) { body }
-    For(String, Box<ASTNode>, Vec<ASTNode>),
+    // 也可以遍历映射的键：for k in map { body }
+    // 循环变量总是每次迭代的作用域中的第一个槽位
+    For(String, Box<ASTNode>, Vec<ASTNode>, Position), // loop variable, range, body, position of the range
     // while循环：while (condition) { body }
     While(Box<ASTNode>, Vec<ASTNode>),
+    // 跳出/继续最内层的循环：break; continue;
+    Break(Position),
+    Continue(Position),
+    // 异常处理：try { body } catch (e) { handler } finally { cleanup }，catch 和 finally 至少有一个
+    Try(Vec<ASTNode>, Option<(String, Vec<ASTNode>)>, Option<Vec<ASTNode>>, Position), // body, catch variable and body, finally body, position
+    // 抛出错误：throw value
+    Throw(Box<ASTNode>, Position), // value, position
     If(Box<ASTNode>, Vec<ASTNode>, Option<Vec<ASTNode>>),
     // input语句：input prompt, variable
-    Input(Box<ASTNode>, String),
-    Assign(String, Box<ASTNode>),
-    Identifier(String, Position),
-    Number(f64),
+    Input(Box<ASTNode>, String, Binding, Position), // prompt, variable, binding, position
+    Assign(String, Binding, Box<ASTNode>, Position), // name, binding, value, position
+    Identifier(String, Binding, Position),
+    Integer(i64),   // 整数字面量，例如 42
+    Number(f64),    // 浮点数字面量，例如 3.14
     String(String),
     Bool(bool),
     BinaryOp(Box<ASTNode>, String, Box<ASTNode>, Position), // left operand, operator, right operand, position
-    // 函数定义：func name(param1, param2) { body }
-    Function(String, Vec<String>, Vec<ASTNode>),
+    UnaryOp(String, Box<ASTNode>, Position),  // 一元运算，目前只有逻辑非 !
+    // 函数定义：func name(param1, <double>param2) -> double { body }，参数和返回值的类型都可以省略
+    Function(String, Vec<Param>, Option<Type>, Vec<ASTNode>, Position), // name, params, return type, body, position of the name
     // 表达式函数定义：expr name(l a, r b) { body }
     Expr(String, Vec<(String, String)>, Vec<ASTNode>),
     // 函数调用：name(arg1, arg2)
-    FunctionCall(String, Vec<ASTNode>),
+    FunctionCall(String, Vec<ASTNode>, Position), // name, args, position
+    // 调用表达式的值：f(1)(2)、fs[0](3)，以及调用保存在局部变量中的函数
+    Call(Box<ASTNode>, Vec<ASTNode>, Position), // callee, args, position
+    // 匿名函数：func (a, b) { body } 或 func (a, b) => expr，可以使用定义它的位置可见的局部变量
+    Lambda(Vec<Param>, Option<Type>, Vec<ASTNode>, Position), // params, return type, body, position
     // 返回语句：return value
-    Return(Box<ASTNode>),
+    Return(Box<ASTNode>, Position), // value, position
     // if表达式：if (condition) then_expr else else_expr
 
     IfExpr(Box<ASTNode>, Box<ASTNode>, Box<ASTNode>),
@@ -78,4 +122,37 @@ pub enum ASTNode {
     TypeConversion(Type, Box<ASTNode>, Position),
     // import语句：import "filename"
     Import(String, Position),
+    // 结构体声明：struct Point { <double>x, <double>y }，没有类型的字段可以保存任意值
+    StructDecl(String, Vec<(String, Option<Type>)>, Position), // name, fields, position
+    // 结构体字段访问：value.field
+    FieldAccess(Box<ASTNode>, String, Position), // struct expression, field, position
+    // 枚举声明：enum Shape { Circle(<double>r), Rect(<double>w, <double>h), Empty }
+    EnumDecl(String, Vec<Variant>, Position), // name, variants and their payload fields, position
+    // 枚举值：Shape::Circle(2.0) 或 Shape::Empty
+    EnumVariant(String, String, Vec<ASTNode>, Position), // enum, variant, payload, position
+    // match语句：match (value) { pattern => { body } ... }
+    // 每个分支使用新的块作用域，模式中绑定的变量依次占用前几个槽位
+    Match(Box<ASTNode>, Vec<(Pattern, Vec<ASTNode>)>, Position), // subject, arms, position
+    // match表达式：match (value) { pattern => expr, ... }
+    MatchExpr(Box<ASTNode>, Vec<(Pattern, ASTNode)>, Position), // subject, arms, position
+}
+
+/// 赋值目标中变量之后的一层访问：数组、列表或映射的下标，或者结构体的字段
+#[derive(Debug, Clone)]
+pub enum Accessor {
+    Index(ASTNode),
+    Field(String),
+}
+
+/// match 分支的模式
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // 通配符 _，匹配任意值
+    Wildcard,
+    // 字面量：整数、浮点数、字符串或布尔值
+    Literal(ASTNode),
+    // 范围 start..end，与 for 循环一样不包含终点
+    Range(ASTNode, ASTNode),
+    // 枚举成员：Shape::Circle(r)，绑定的名称为 _ 时忽略该值
+    Variant(String, String, Vec<(String, Binding)>, Position), // enum, variant, payload bindings, position
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+use std::rc::Rc;
+use crate::ast::{Binding, Pattern, Position, Type};
+use crate::interpreter::{BinaryOp, EnumType, Function, StructType, Value};
+
+/// 虚拟机指令。操作数中的 `usize` 是代码块常量表、名称表或函数表中的下标，
+/// 跳转指令的操作数是目标指令的下标。变量指令同时带有变量名和解析器给出的 [`Binding`]，
+/// 局部变量按槽位访问，名称只用于全局变量和诊断信息。
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpCode {
+    /// 压入常量
+    Constant(usize),
+    Pop,
+
+    /// 读取变量并压栈
+    GetVar(usize, Binding),
+    /// 弹出值并在当前作用域中声明无类型变量
+    DefineVar(usize, Binding),
+    /// 弹出值，转换为声明的类型后在当前作用域中声明变量
+    DefineTyped(usize, Binding, Type),
+    /// 弹出值并在当前作用域中声明常量，指定了类型时先进行转换
+    DefineConst(usize, Binding, Option<Type>),
+    /// 弹出值并赋给已有变量，变量不存在时在当前作用域中创建
+    Assign(usize, Binding),
+    /// 弹出 `count` 个初始值，声明数组（名称、绑定、元素类型、由外向内每一维的长度、初始值个数）
+    DefineArray(usize, Binding, Type, Vec<usize>, usize),
+    /// 弹出 `count` 个元素，声明列表
+    DefineList(usize, Binding, usize),
+    /// 弹出 `count` 个元素，压入列表
+    MakeList(usize),
+    /// 弹出 `count` 对依次排列的键和值，压入映射
+    MakeMap(usize),
+    /// 弹出下标和数组，压入对应的元素
+    Index,
+    /// 弹出下标，压入变量中对应的元素，变量不需要先压栈（变量名、绑定）
+    IndexVar(usize, Binding),
+    /// 弹出值和路径中由外向内的下标，修改变量中路径上的元素或字段（变量名、绑定、路径）
+    SetPath(usize, Binding, Vec<PathOperand>),
+    /// 弹出结构体，压入名称表中对应名称的字段
+    GetField(usize),
+
+    /// 弹出两个操作数，压入二元运算的结果
+    Binary(BinaryOp),
+    /// 弹出操作数，压入一元运算的结果
+    Unary(String),
+    /// 弹出值，压入它作为条件时的真假
+    Truthy,
+    /// 弹出值，压入转换为指定类型后的值
+    Convert(Type),
+
+    Jump(usize),
+    /// 弹出条件，为假时跳转
+    JumpIfFalse(usize),
+    /// 弹出条件，为真时跳转
+    JumpIfTrue(usize),
+
+    /// 进入一个新的块作用域
+    EnterScope,
+    /// 离开当前的块作用域
+    ExitScope,
+
+    /// 弹出范围的起点和终点，开始遍历其中的整数
+    ForRange,
+    /// 弹出值，开始遍历数组或列表的元素、映射的键；其他值视为从 0 开始的范围的终点
+    ForEach,
+    /// 还有下一个值时进入循环体的作用域并在第一个槽位中声明循环变量，否则跳转到循环结束
+    ForNext(usize),
+    /// 结束最内层的 for 循环
+    ForEnd,
+
+    /// 以名称表中的名称声明函数表中的函数（名称、函数）
+    DefineFunction(usize, usize),
+    /// 声明结构体表中的结构体类型
+    DefineStruct(usize),
+    /// 声明枚举表中的枚举类型
+    DefineEnum(usize),
+    /// 弹出成员携带的值，压入枚举值（枚举名、成员名、值的个数）
+    MakeVariant(usize, usize, usize),
+    /// 栈顶的值匹配模式表中的模式时弹出它，进入分支的块作用域并声明模式绑定的变量；否则跳转到下一个分支
+    MatchArm(usize, usize),
+    /// 弹出没有匹配任何分支的值并报告错误
+    NoMatch,
+    /// 检查函数是否存在，在计算参数之前报告未定义的函数
+    CheckFunction(usize),
+    /// 弹出参数并调用函数（函数名、参数个数），压入返回值
+    Call(usize, usize),
+    /// 弹出除第一个以外的参数，调用修改变量的内置函数并写回（函数名、变量名、绑定、弹出的参数个数），压入返回值
+    CallInPlace(usize, usize, Binding, usize),
+    /// 弹出参数和被调用的函数值并调用它（参数个数），压入返回值
+    CallValue(usize),
+    /// 以当前作用域为外层作用域，压入函数表中函数的 lambda 值
+    MakeClosure(usize),
+    /// 弹出返回值并立即从函数返回
+    Return,
+
+    /// 进入 try 语句，之后的指令出错时跳转到目标位置
+    PushHandler(usize),
+    /// 离开 try 语句
+    PopHandler,
+    /// 进入 catch 的块作用域，在第一个槽位中声明捕获的错误值
+    EnterCatch,
+    /// finally 执行完之后重新抛出捕获的错误
+    Rethrow,
+    /// 丢弃等待重新抛出的错误，用于从出错路径上的 finally 中 break/continue
+    DiscardError,
+    /// 弹出值并抛出错误
+    Throw,
+
+    Print,
+    Println,
+    /// 弹出提示信息并读取一行输入，存入变量
+    Input(usize, Binding),
+    /// 导入并执行另一个文件
+    Import(usize),
+}
+
+/// [`OpCode::SetPath`] 路径中的一层：下标的值在栈上，字段名在名称表中
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathOperand {
+    Index,
+    Field(usize),
+}
+
+/// 编译后的代码块：顶层程序或一个函数体
+#[derive(Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    pub positions: Vec<Position>,  // 每条指令对应的源代码位置，用于诊断信息
+    pub constants: Vec<Value>,
+    pub names: Vec<Rc<str>>,
+    pub functions: Vec<Rc<Function>>,
+    pub structs: Vec<Rc<StructType>>,
+    pub enums: Vec<Rc<EnumType>>,
+    pub patterns: Vec<Pattern>,
+}
+
+impl Chunk {
+    /// 追加一条指令，返回它的下标
+    pub fn write(&mut self, op: OpCode, pos: Position) -> usize {
+        self.code.push(op);
+        self.positions.push(pos);
+        self.code.len() - 1
+    }
+
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// 名称表中的名称只保存一份
+    pub fn add_name(&mut self, name: &str) -> usize {
+        if let Some(index) = self.names.iter().position(|existing| **existing == *name) {
+            return index;
+        }
+        self.names.push(name.into());
+        self.names.len() - 1
+    }
+}
@@ -0,0 +1,715 @@
+// 与解析器一样，检查错误以 CompilerError 返回
+#![allow(clippy::result_large_err)]
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::ast::{ASTNode, Accessor, Param, Pattern, Position, Type};
+use crate::environment::Variable;
+use crate::error::{error_messages, CompilerError};
+use crate::interpreter::{Function, Value};
+use crate::resolver::nested_blocks;
+
+/// 检查时推断出的表达式类型。`Scalar(Type::Any)` 表示类型只能在运行时确定
+#[derive(Debug, Clone, PartialEq)]
+enum Inferred {
+    Scalar(Type),
+    // 数组、列表、映射、枚举和函数值，保存与 Value::get_type_name 相同的类型名
+    Value(String),
+}
+
+const ANY: Inferred = Inferred::Scalar(Type::Any);
+
+impl Inferred {
+    fn value(name: &str) -> Self {
+        Inferred::Value(name.to_string())
+    }
+
+    fn is_known(&self) -> bool {
+        *self != ANY
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, Inferred::Scalar(Type::Int | Type::Float | Type::Double))
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Inferred::Scalar(scalar) => scalar.to_string(),
+            Inferred::Value(name) => name.clone(),
+        }
+    }
+}
+
+/// 变量声明时指定的类型，决定读取变量得到的类型和赋值时的转换目标
+#[derive(Debug, Clone, PartialEq)]
+enum Declared {
+    Untyped,
+    Typed(Type),         // <int>x
+    Array(Type, Vec<usize>), // <int>xs[3] 或 <int>grid[3][4]，保存元素的类型和由外向内每一维的长度
+    Constant(Inferred),  // 常量的值不会改变，类型就是初始值的类型
+}
+
+impl Declared {
+    fn of_param(param_type: &Option<Type>) -> Self {
+        match param_type {
+            Some(param_type) => Declared::Typed(param_type.clone()),
+            None => Declared::Untyped,
+        }
+    }
+
+    fn read(&self) -> Inferred {
+        match self {
+            Declared::Untyped => ANY,
+            Declared::Typed(var_type) => Inferred::Scalar(var_type.clone()),
+            Declared::Array(..) => Inferred::value("array"),
+            Declared::Constant(inferred) => inferred.clone(),
+        }
+    }
+
+    /// 赋值时值被转换成的类型，数组变量按元素类型转换
+    fn target(&self) -> Option<&Type> {
+        match self {
+            Declared::Typed(var_type) | Declared::Array(var_type, _) => Some(var_type),
+            Declared::Untyped | Declared::Constant(_) => None,
+        }
+    }
+
+    /// 执行之前已经存在的变量，例如宿主程序设置的变量或 REPL 中之前定义的变量
+    fn of_variable(variable: &Variable) -> Self {
+        match (&variable.var_type, &variable.value) {
+            (_, value) if variable.constant => Declared::Constant(value_type(value)),
+            (Some(element_type), Value::Array(_)) => Declared::Array(element_type.clone(), array_sizes(&variable.value)),
+            (Some(var_type), _) => Declared::Typed(var_type.clone()),
+            (None, _) => Declared::Untyped,
+        }
+    }
+}
+
+/// 具名函数的参数和返回类型，以及用于提示的声明位置
+#[derive(Debug, Clone)]
+struct FunctionType {
+    params: Vec<Param>,
+    return_type: Option<Type>,
+    origin: String,
+}
+
+/// 在执行之前推断表达式的类型，报告一定会失败的赋值和运算。
+///
+/// 没有类型的变量、函数的参数和调用结果的类型只能在运行时确定（`Type::Any`），
+/// 只有两边的类型都已知时才报告错误，所以检查通过的程序仍然可能在运行时出现类型错误。
+/// 与解析器不同，检查会继续进行，最后一次报告所有错误。
+pub struct Checker<'a> {
+    file_path: &'a str,
+    source_lines: &'a [String],
+    scopes: Vec<HashMap<String, Declared>>, // 当前函数或顶层代码中由外向内的局部作用域
+    in_function: bool,
+    globals: HashMap<String, Declared>,         // 执行到当前位置时的全局变量
+    program_globals: HashMap<String, Declared>, // 函数体中使用的全局变量，程序中声明的类型不一致时没有类型
+    functions: HashMap<String, Option<FunctionType>>, // 具名函数的类型，同名函数的声明不一致时为 None
+    returns: Option<(String, Type)>,                  // 当前函数的名称和声明的返回类型
+    errors: Vec<CompilerError>,
+}
+
+impl<'a> Checker<'a> {
+    pub fn new(file_path: &'a str, source_lines: &'a [String]) -> Self {
+        Self {
+            file_path,
+            source_lines,
+            scopes: Vec::new(),
+            in_function: false,
+            globals: HashMap::new(),
+            program_globals: HashMap::new(),
+            functions: HashMap::new(),
+            returns: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// 执行之前已经定义的具名函数，例如 REPL 中之前定义的函数
+    pub fn with_functions<'f>(mut self, functions: impl IntoIterator<Item = (&'f String, &'f Rc<Function>)>) -> Self {
+        for (name, function) in functions {
+            let signature = &function.signature;
+            let params = function.params.iter().enumerate()
+                .map(|(index, param)| (param.clone(), signature.param_types.get(index).cloned().flatten()))
+                .collect();
+            let function_type = FunctionType { params, return_type: signature.return_type.clone(), origin: signature.origin.clone() };
+            self.functions.insert(name.clone(), Some(function_type));
+        }
+        self
+    }
+
+    /// 执行之前已经存在的全局变量和它们的类型
+    pub fn with_globals<'v>(mut self, globals: impl IntoIterator<Item = (&'v String, &'v Variable)>) -> Self {
+        for (name, variable) in globals {
+            self.globals.insert(name.clone(), Declared::of_variable(variable));
+        }
+        self
+    }
+
+    /// 检查一段已经解析过的顶层程序，按源代码中的顺序返回所有错误
+    pub fn check(mut self, program: &[ASTNode]) -> Result<(), Vec<CompilerError>> {
+        self.program_globals = self.globals.clone();
+        let mut declared: HashMap<&str, Declared> = HashMap::new();
+        for node in program {
+            if let Some((name, declaration)) = declaration(node) {
+                let merged = match declared.get(name) {
+                    Some(existing) if *existing != declaration => Declared::Untyped,
+                    _ => declaration,
+                };
+                declared.insert(name, merged);
+            }
+        }
+        for (name, declaration) in declared {
+            let merged = match self.program_globals.get(name) {
+                Some(existing) if *existing != declaration => Declared::Untyped,
+                _ => declaration,
+            };
+            self.program_globals.insert(name.to_string(), merged);
+        }
+
+        let mut defined = HashMap::new();
+        self.collect_functions(program, &mut defined);
+        self.functions.extend(defined);
+
+        self.check_statements(program);
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn check_statement(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Print(expr) | ASTNode::Println(expr) | ASTNode::Throw(expr, _) => {
+                self.infer(expr);
+            }
+            ASTNode::Return(expr, pos) => {
+                let value_type = self.infer(expr);
+                if let Some((name, return_type)) = self.returns.clone()
+                    && let Some(reason) = conversion_failure(&return_type, expr, &value_type)
+                {
+                    let message = error_messages::return_type_error(&name, &return_type.to_string(), &reason);
+                    let error = self.error_at(message, pos).with_help(error_messages::help_return_type(&return_type.to_string()));
+                    self.errors.push(error);
+                }
+            }
+            ASTNode::Var(name, _, expr, _) => {
+                self.infer(expr);
+                self.declare(name, Declared::Untyped);
+            }
+            ASTNode::TypedVar(name, _, var_type, expr, pos) => {
+                let value_type = self.infer(expr);
+                self.check_conversion(name, var_type, expr, &value_type, pos);
+                self.declare(name, Declared::Typed(var_type.clone()));
+            }
+            ASTNode::ArrayDecl(name, _, element_type, sizes, init_values, pos) => {
+                self.check_array_values(name, element_type, init_values, sizes, pos);
+                self.declare(name, Declared::Array(element_type.clone(), sizes.clone()));
+            }
+            ASTNode::ListDecl(name, _, init_values, _) => {
+                for init in init_values {
+                    self.infer(init);
+                }
+                self.declare(name, Declared::Untyped);
+            }
+            ASTNode::ConstDecl(name, _, const_type, expr, pos) => {
+                let value_type = self.infer(expr);
+                let declaration = match const_type {
+                    Some(const_type) => {
+                        self.check_conversion(name, const_type, expr, &value_type, pos);
+                        Declared::Constant(Inferred::Scalar(const_type.clone()))
+                    }
+                    None => Declared::Constant(value_type),
+                };
+                self.declare(name, declaration);
+            }
+            ASTNode::Assign(name, _, expr, pos) => {
+                let value_type = self.infer(expr);
+                match self.lookup(name) {
+                    Some(declaration) => {
+                        if let Some(target) = declaration.target() {
+                            self.check_conversion(name, target, expr, &value_type, pos);
+                        }
+                    }
+                    // 给不存在的变量赋值会声明没有类型的变量
+                    None => self.declare(name, Declared::Untyped),
+                }
+            }
+            ASTNode::PathAssign(name, _, path, value_expr, pos) => {
+                for accessor in path {
+                    if let Accessor::Index(index_expr) = accessor {
+                        self.infer(index_expr);
+                    }
+                }
+                let value_type = self.infer(value_expr);
+                // 数组的元素类型只适用于只有下标的路径
+                if path.iter().all(|accessor| matches!(accessor, Accessor::Index(_)))
+                    && let Some(Declared::Array(element_type, sizes)) = self.lookup(name)
+                    && let Some(row_sizes) = sizes.get(path.len()..)
+                {
+                    // 下标个数少于维数时替换的是多维数组的一行，只能是数组或列表
+                    let incompatible = if row_sizes.is_empty() {
+                        conversion_failure(&element_type, value_expr, &value_type).is_some()
+                    } else {
+                        value_type != ANY && !is_sequence(&value_type)
+                    };
+                    if incompatible {
+                        let slot_type = row_sizes.iter().fold(element_type.to_string(), |name, size| format!("{}[{}]", name, size));
+                        let message = error_messages::array_type_error(&slot_type, &value_type.name());
+                        let error = self.error_at(message, pos)
+                            .with_help(error_messages::help_array_assignment())
+                            .with_example(error_messages::example_array_assignment());
+                        self.errors.push(error);
+                    }
+                }
+            }
+            ASTNode::Input(prompt, name, ..) => {
+                self.infer(prompt);
+                if self.lookup(name).is_none() {
+                    self.declare(name, Declared::Untyped);
+                }
+            }
+            ASTNode::For(var, range_expr, body, pos) => {
+                // start..end 只能作为 for 循环的范围，起点和终点分别推断
+                let bounds = match range_expr.as_ref() {
+                    ASTNode::BinaryOp(left, op, right, _) if op == ".." => vec![self.infer(left), self.infer(right)],
+                    _ => vec![self.infer(range_expr)],
+                };
+                // 数组、列表和映射可以遍历，标量只有 int 可以作为范围
+                let not_int = |bound: &&Inferred| matches!(bound, Inferred::Scalar(scalar) if !matches!(scalar, Type::Int | Type::Any));
+                if let Some(bound) = bounds.iter().find(not_int) {
+                    let error = self.error_at(error_messages::range_bound_type(&bound.name()), pos)
+                        .with_help(error_messages::help_range_bound_type());
+                    self.errors.push(error);
+                }
+                self.scopes.push(HashMap::new());
+                self.declare(var, Declared::Untyped);
+                self.check_statements(body);
+                self.scopes.pop();
+            }
+            ASTNode::While(condition, body) => {
+                self.infer(condition);
+                self.check_block(body);
+            }
+            ASTNode::Try(body, catch, finally, _) => {
+                self.check_block(body);
+                if let Some((name, handler)) = catch {
+                    self.scopes.push(HashMap::new());
+                    self.declare(name, Declared::Untyped);
+                    self.check_statements(handler);
+                    self.scopes.pop();
+                }
+                if let Some(cleanup) = finally {
+                    self.check_block(cleanup);
+                }
+            }
+            ASTNode::If(condition, then_branch, else_branch) => {
+                self.infer(condition);
+                self.check_block(then_branch);
+                if let Some(else_stmts) = else_branch {
+                    self.check_block(else_stmts);
+                }
+            }
+            ASTNode::Function(name, params, return_type, body, _) => {
+                let function_scope = params.iter().map(|(param, param_type)| (param.clone(), Declared::of_param(param_type))).collect();
+                self.check_function(name, function_scope, return_type, body);
+            }
+            ASTNode::Expr(name, typed_params, body) => {
+                let function_scope = typed_params.iter().map(|(_, param)| (param.clone(), Declared::Untyped)).collect();
+                self.check_function(name, function_scope, &None, body);
+            }
+            ASTNode::FunctionCall(..) | ASTNode::Call(..) => {
+                self.infer(node);
+            }
+            ASTNode::Match(subject, arms, _) => {
+                self.infer(subject);
+                for (pattern, body) in arms {
+                    self.scopes.push(HashMap::new());
+                    self.declare_pattern(pattern);
+                    self.check_statements(body);
+                    self.scopes.pop();
+                }
+            }
+            // 其他表达式作为语句时不会执行
+            _ => {}
+        }
+    }
+
+    fn check_statements(&mut self, statements: &[ASTNode]) {
+        for stmt in statements {
+            self.check_statement(stmt);
+        }
+    }
+
+    fn check_block(&mut self, statements: &[ASTNode]) {
+        self.scopes.push(HashMap::new());
+        self.check_statements(statements);
+        self.scopes.pop();
+    }
+
+    /// 函数体从一个只包含参数的新作用域开始
+    fn check_function(&mut self, name: &str, function_scope: HashMap<String, Declared>, return_type: &Option<Type>, body: &[ASTNode]) {
+        let saved_scopes = std::mem::replace(&mut self.scopes, vec![function_scope]);
+        let saved_in_function = std::mem::replace(&mut self.in_function, true);
+        let saved_returns = std::mem::replace(&mut self.returns, returns(name, return_type));
+        self.check_statements(body);
+        self.scopes = saved_scopes;
+        self.in_function = saved_in_function;
+        self.returns = saved_returns;
+    }
+
+    /// 推断表达式的类型，同时检查其中的运算和嵌套的 lambda
+    fn infer(&mut self, node: &ASTNode) -> Inferred {
+        match node {
+            ASTNode::Integer(_) => Inferred::Scalar(Type::Int),
+            ASTNode::Number(_) => Inferred::Scalar(Type::Double),
+            ASTNode::String(_) => Inferred::Scalar(Type::Str),
+            ASTNode::Bool(_) => Inferred::Scalar(Type::Bool),
+            ASTNode::Identifier(name, _, _) => self.lookup(name).map_or(ANY, |declaration| declaration.read()),
+            ASTNode::ListLiteral(elements) => {
+                for element in elements {
+                    self.infer(element);
+                }
+                Inferred::value("list")
+            }
+            ASTNode::MapLiteral(entries, _) => {
+                for (key, value) in entries {
+                    self.infer(key);
+                    self.infer(value);
+                }
+                Inferred::value("map")
+            }
+            ASTNode::IndexAccess(target, index_expr, _) => {
+                let target_type = self.infer(target);
+                self.infer(index_expr);
+                // 数组变量经过与维数相同个数的下标得到元素，更少的下标得到内层的数组
+                let (root, depth) = index_root(node);
+                if let ASTNode::Identifier(name, _, _) = root
+                    && let Some(Declared::Array(element_type, sizes)) = self.lookup(name)
+                    && depth <= sizes.len()
+                {
+                    return if depth == sizes.len() { Inferred::Scalar(element_type) } else { Inferred::value("array") };
+                }
+                match target_type {
+                    // 字符串的索引得到单个字符的字符串
+                    Inferred::Scalar(Type::Str) => Inferred::Scalar(Type::Str),
+                    _ => ANY,
+                }
+            }
+            ASTNode::BinaryOp(left, op, right, pos) => {
+                let left_type = self.infer(left);
+                let right_type = self.infer(right);
+                if op == ".." {
+                    let error = self.error_at(error_messages::range_outside_for(), pos)
+                        .with_help(error_messages::help_range_outside_for());
+                    self.errors.push(error);
+                    return ANY;
+                }
+                self.binary_op_type(op, &left_type, &right_type, pos)
+            }
+            ASTNode::UnaryOp(_, expr, _) => {
+                self.infer(expr);
+                Inferred::Scalar(Type::Bool)
+            }
+            ASTNode::TypeConversion(target_type, expr, pos) => {
+                // 字符串的内容只能在运行时检查，这样转换失败可以被 catch
+                if let Inferred::Value(actual) = self.infer(expr) && !matches!(target_type, Type::Str) {
+                    let message = error_messages::impossible_conversion(&actual, &target_type.to_string());
+                    let error = self.error_at(message, pos).with_help(error_messages::help_impossible_conversion());
+                    self.errors.push(error);
+                }
+                Inferred::Scalar(target_type.clone())
+            }
+            ASTNode::IfExpr(condition, then_expr, else_expr) => {
+                self.infer(condition);
+                let then_type = self.infer(then_expr);
+                let else_type = self.infer(else_expr);
+                if then_type == else_type { then_type } else { ANY }
+            }
+            ASTNode::FunctionCall(name, args, pos) => {
+                let arg_types: Vec<Inferred> = args.iter().map(|arg| self.infer(arg)).collect();
+                let Some(Some(signature)) = self.functions.get(name).cloned() else {
+                    return ANY;
+                };
+                for (index, (arg, arg_type)) in args.iter().zip(&arg_types).enumerate() {
+                    if let Some((param, Some(param_type))) = signature.params.get(index)
+                        && let Some(reason) = conversion_failure(param_type, arg, arg_type)
+                    {
+                        let message = error_messages::argument_type_error(name, param, &param_type.to_string(), &reason);
+                        let error = self.error_at(message, pos)
+                            .with_help(error_messages::help_argument_type(&param_type.to_string()))
+                            .with_note(error_messages::function_declared_at(name, &signature.origin));
+                        self.errors.push(error);
+                    }
+                }
+                signature.return_type.map_or(ANY, Inferred::Scalar)
+            }
+            ASTNode::Call(callee, args, _) => {
+                self.infer(callee);
+                for arg in args {
+                    self.infer(arg);
+                }
+                ANY
+            }
+            ASTNode::Lambda(params, return_type, body, _) => {
+                // lambda 的函数作用域位于定义它的位置的作用域之内
+                self.scopes.push(params.iter().map(|(param, param_type)| (param.clone(), Declared::of_param(param_type))).collect());
+                let saved_in_function = std::mem::replace(&mut self.in_function, true);
+                let saved_returns = std::mem::replace(&mut self.returns, returns("<lambda>", return_type));
+                self.check_statements(body);
+                self.in_function = saved_in_function;
+                self.returns = saved_returns;
+                self.scopes.pop();
+                Inferred::value("function")
+            }
+            ASTNode::FieldAccess(expr, _, _) => {
+                self.infer(expr);
+                ANY
+            }
+            ASTNode::EnumVariant(enum_name, _, args, _) => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                Inferred::Value(enum_name.clone())
+            }
+            ASTNode::MatchExpr(subject, arms, _) => {
+                self.infer(subject);
+                for (pattern, arm_expr) in arms {
+                    self.scopes.push(HashMap::new());
+                    self.declare_pattern(pattern);
+                    self.infer(arm_expr);
+                    self.scopes.pop();
+                }
+                ANY
+            }
+            _ => ANY,
+        }
+    }
+
+    /// 二元运算的结果类型，两边的类型都已知且运行时一定会失败时报告错误。
+    /// 规则与解释器的 apply_binary_op 相同
+    fn binary_op_type(&mut self, op: &str, left: &Inferred, right: &Inferred, pos: &Position) -> Inferred {
+        let allowed = match op {
+            "&&" | "||" => return Inferred::Scalar(Type::Bool),
+            "+" => {
+                let strings = Inferred::Scalar(Type::Str);
+                (*left == strings && *right == strings) || (left.is_numeric() && right.is_numeric())
+            }
+            "==" | "!=" => {
+                (left.is_numeric() && right.is_numeric()) || left == right
+            }
+            "-" | "*" | "/" | "%" | "~/" | "**" | "<" | ">" | "<=" | ">=" => left.is_numeric() && right.is_numeric(),
+            _ => return ANY,
+        };
+        if !allowed && left.is_known() && right.is_known() {
+            let message = error_messages::operation_type_error(op, &left.name(), &right.name());
+            let error = self.error_at(message, pos)
+                .with_help(error_messages::help_operation_type())
+                .with_example(error_messages::example_operation_type());
+            self.errors.push(error);
+            return ANY;
+        }
+
+        match op {
+            "==" | "!=" | "<" | ">" | "<=" | ">=" => Inferred::Scalar(Type::Bool),
+            _ if left.is_numeric() && right.is_numeric() => numeric_result(op, left, right),
+            "+" if allowed => Inferred::Scalar(Type::Str),
+            _ => ANY,
+        }
+    }
+
+    /// 数组的初始值：每一层的值不能多于这一维的长度，多维数组的列表字面量逐层检查，一行也可以用数组或列表的值初始化
+    fn check_array_values(&mut self, name: &str, element_type: &Type, values: &[ASTNode], sizes: &[usize], pos: &Position) {
+        let (&size, inner_sizes) = sizes.split_first().expect("an array has at least one dimension");
+        if values.len() > size.max(1) {
+            let error = self.error_at(error_messages::too_many_array_values(size, values.len()), pos)
+                .with_help(error_messages::help_array_values());
+            self.errors.push(error);
+        }
+        for value in values {
+            match value {
+                ASTNode::ListLiteral(elements) if !inner_sizes.is_empty() => {
+                    self.check_array_values(name, element_type, elements, inner_sizes, pos);
+                }
+                _ => {
+                    let value_type = self.infer(value);
+                    if inner_sizes.is_empty() || !is_sequence(&value_type) {
+                        self.check_conversion(name, element_type, value, &value_type, pos);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 检查赋给带类型的变量、常量或数组元素的值能否转换为声明的类型
+    fn check_conversion(&mut self, name: &str, target: &Type, expr: &ASTNode, value_type: &Inferred, pos: &Position) {
+        if let Some(reason) = conversion_failure(target, expr, value_type) {
+            let message = error_messages::incompatible_assignment(name, &target.to_string(), &reason);
+            let error = self.error_at(message, pos).with_help(error_messages::help_incompatible_assignment(&target.to_string()));
+            self.errors.push(error);
+        }
+    }
+
+    /// 程序中定义的所有具名函数，包括函数体和语句块中的。表达式函数的参数没有类型
+    fn collect_functions(&self, nodes: &[ASTNode], defined: &mut HashMap<String, Option<FunctionType>>) {
+        for node in nodes {
+            let function_type = match node {
+                ASTNode::Function(name, params, return_type, _, pos) => {
+                    let origin = format!("{}:{}:{}", self.file_path, pos.line, pos.column);
+                    Some((name, FunctionType { params: params.clone(), return_type: return_type.clone(), origin }))
+                }
+                ASTNode::Expr(name, typed_params, _) => {
+                    let params = typed_params.iter().map(|(_, param)| (param.clone(), None)).collect();
+                    Some((name, FunctionType { params, return_type: None, origin: String::new() }))
+                }
+                _ => None,
+            };
+            if let Some((name, function_type)) = function_type {
+                // 同一个函数名可能被重新定义，参数和返回类型不一致时无法确定调用的是哪一个
+                let consistent = match defined.get(name) {
+                    None => true,
+                    Some(Some(existing)) => existing.params == function_type.params && existing.return_type == function_type.return_type,
+                    Some(None) => false,
+                };
+                defined.insert(name.clone(), consistent.then_some(function_type));
+            }
+            for block in nested_blocks(node) {
+                self.collect_functions(block, defined);
+            }
+        }
+    }
+
+    /// 由内向外查找变量的声明，函数体中使用程序中所有全局变量共同的类型
+    fn lookup(&self, name: &str) -> Option<Declared> {
+        if let Some(declaration) = self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            return Some(declaration.clone());
+        }
+        if self.in_function {
+            self.program_globals.get(name).cloned()
+        } else {
+            self.globals.get(name).cloned()
+        }
+    }
+
+    /// 在当前作用域中声明变量，顶层代码中声明的是全局变量
+    fn declare(&mut self, name: &str, declaration: Declared) {
+        match self.scopes.last_mut() {
+            Some(scope) => scope.insert(name.to_string(), declaration),
+            None => self.globals.insert(name.to_string(), declaration),
+        };
+    }
+
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        if let Pattern::Variant(_, _, bindings, _) = pattern {
+            for (name, _) in bindings.iter().filter(|(name, _)| name != "_") {
+                self.declare(name, Declared::Untyped);
+            }
+        }
+    }
+
+    fn error_at(&self, message: String, pos: &Position) -> CompilerError {
+        let source_line = pos.line.checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .cloned()
+            .unwrap_or_default();
+        CompilerError::new(message, pos.line, pos.column, self.file_path.to_string(), source_line)
+    }
+}
+
+/// 两个数值运算的结果类型：按 int -> float -> double 的顺序提升，
+/// int 之间的 ** 在指数为负数时得到 double
+fn numeric_result(op: &str, left: &Inferred, right: &Inferred) -> Inferred {
+    let double = Inferred::Scalar(Type::Double);
+    let float = Inferred::Scalar(Type::Float);
+    if *left == double || *right == double {
+        double
+    } else if *left == float || *right == float {
+        float
+    } else {
+        match op {
+            "**" => ANY,
+            _ => Inferred::Scalar(Type::Int),
+        }
+    }
+}
+
+/// 值一定无法转换为 `target` 时返回原因：数组、列表等值只能转换为 str，字面量直接尝试转换
+fn conversion_failure(target: &Type, expr: &ASTNode, value_type: &Inferred) -> Option<String> {
+    match (literal_value(expr), value_type) {
+        (Some(value), _) => value.convert_to(target).err(),
+        (None, Inferred::Value(actual)) if !matches!(target, Type::Str) => {
+            Some(error_messages::incompatible_value(actual, &target.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// 声明了返回类型的函数中 return 的值要转换为该类型
+fn returns(name: &str, return_type: &Option<Type>) -> Option<(String, Type)> {
+    return_type.as_ref().map(|return_type| (name.to_string(), return_type.clone()))
+}
+
+/// 字面量表达式的值
+fn literal_value(expr: &ASTNode) -> Option<Value> {
+    match expr {
+        ASTNode::Integer(n) => Some(Value::Int(*n)),
+        ASTNode::Number(n) => Some(Value::Double(*n)),
+        ASTNode::String(s) => Some(Value::Str(s.clone())),
+        ASTNode::Bool(b) => Some(Value::Bool(*b)),
+        _ => None,
+    }
+}
+
+/// 已有的值的类型
+fn value_type(value: &Value) -> Inferred {
+    match value.get_type() {
+        Type::Any => Inferred::Value(value.get_type_name()),
+        scalar => Inferred::Scalar(scalar),
+    }
+}
+
+fn is_sequence(inferred: &Inferred) -> bool {
+    matches!(inferred, Inferred::Value(name) if name == "array" || name == "list")
+}
+
+/// 数组由外向内每一维的长度，内层的长度取第一个元素的长度
+fn array_sizes(value: &Value) -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut current = value;
+    while let Value::Array(items) = current {
+        sizes.push(items.len());
+        match items.first() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+    sizes
+}
+
+/// 连续索引访问的变量和下标个数，例如 `grid[i][j]` 得到 `(grid, 2)`
+fn index_root(node: &ASTNode) -> (&ASTNode, usize) {
+    match node {
+        ASTNode::IndexAccess(target, _, _) => {
+            let (root, depth) = index_root(target);
+            (root, depth + 1)
+        }
+        _ => (node, 0),
+    }
+}
+
+/// 顶层语句声明的全局变量和它的类型，常量的初始值只有是字面量时才能在这里确定类型
+fn declaration(node: &ASTNode) -> Option<(&str, Declared)> {
+    let declared = match node {
+        ASTNode::Var(name, ..) | ASTNode::ListDecl(name, ..) => (name, Declared::Untyped),
+        ASTNode::TypedVar(name, _, var_type, ..) => (name, Declared::Typed(var_type.clone())),
+        ASTNode::ArrayDecl(name, _, element_type, sizes, ..) => (name, Declared::Array(element_type.clone(), sizes.clone())),
+        ASTNode::ConstDecl(name, _, Some(const_type), ..) => (name, Declared::Constant(Inferred::Scalar(const_type.clone()))),
+        ASTNode::ConstDecl(name, _, None, expr, _) => {
+            let inferred = literal_value(expr).map_or(ANY, |value| value_type(&value));
+            (name, Declared::Constant(inferred))
+        }
+        _ => return None,
+    };
+    Some((declared.0.as_str(), declared.1))
+}
@@ -0,0 +1,601 @@
+use std::rc::Rc;
+use crate::ast::{ASTNode, Accessor, Param, Pattern, Position, Type};
+use crate::bytecode::{Chunk, OpCode, PathOperand};
+use crate::interpreter::builtins::Builtin;
+use crate::interpreter::{may_call, BinaryOp, EnumType, Function, FunctionBody, Signature, SourceFile, StructType, Value};
+
+/// 正在编译的循环：break/continue 需要先离开循环体内的作用域，跳转目标在循环编译完之后才知道
+struct LoopContext {
+    body_depth: usize,     // 循环体所在的作用域深度
+    breaks: Vec<usize>,    // 跳转到循环结束的指令
+    continues: Vec<usize>, // 跳转到循环体末尾的指令
+}
+
+/// 正在编译的 try 语句：break/continue/return 离开 try 之前要先移除它的错误处理并执行 finally
+struct TryContext {
+    loop_count: usize,             // try 语句外层的循环个数
+    scope_depth: usize,            // try 语句所在的作用域深度
+    has_handler: bool,             // 当前位置出错时是否由这个 try 处理
+    finally: Option<Vec<ASTNode>>,
+}
+
+/// 将语法树编译为字节码。语义与 `Interpreter::evaluate` 一一对应：
+/// 每个语句块使用新的块作用域，return 立即结束整个函数体。
+pub struct Compiler {
+    source: Rc<SourceFile>, // 正在编译的文件，函数的声明位置和函数体中的错误引用它
+    chunk: Chunk,
+    scope_depth: usize,
+    loops: Vec<LoopContext>,
+    trys: Vec<TryContext>,
+    pending_errors: Vec<usize>, // 正在编译的出错路径上的 finally 外层的循环个数，finally 之后要重新抛出这些错误
+}
+
+/// 不会产生诊断信息的指令使用的位置
+fn no_position() -> Position {
+    Position::new(0, 0)
+}
+
+impl Compiler {
+    fn new(source: &Rc<SourceFile>) -> Self {
+        Self {
+            source: Rc::clone(source),
+            chunk: Chunk::default(),
+            scope_depth: 0,
+            loops: Vec::new(),
+            trys: Vec::new(),
+            pending_errors: Vec::new(),
+        }
+    }
+
+    /// 编译文件 `source` 中的一段顶层程序
+    pub fn compile_program(ast: &[ASTNode], source: &Rc<SourceFile>) -> Chunk {
+        let mut compiler = Self::new(source);
+        for node in ast {
+            compiler.compile_statement(node);
+        }
+        compiler.chunk
+    }
+
+    /// 编译函数体，函数体执行完而没有遇到 return 时返回默认值
+    fn compile_function(&self, params: Vec<String>, body: &[ASTNode], signature: Signature) -> Function {
+        let mut compiler = Self::new(&self.source);
+        compiler.compile_block(body);
+        Function {
+            params,
+            body: FunctionBody::Bytecode(Rc::new(compiler.chunk)),
+            signature,
+            source: Rc::clone(&self.source),
+        }
+    }
+    
+    /// 带有参数和返回类型的具名函数或 lambda
+    fn compile_typed_function(&self, params: &[Param], return_type: &Option<Type>, body: &[ASTNode], pos: &Position) -> Function {
+        let origin = format!("{}:{}:{}", self.source.path, pos.line, pos.column);
+        let signature = Signature::new(params, return_type.clone(), origin);
+        let names = params.iter().map(|(param, _)| param.clone()).collect();
+        self.compile_function(names, body, signature)
+    }
+
+    fn emit(&mut self, op: OpCode) -> usize {
+        self.chunk.write(op, no_position())
+    }
+
+    fn emit_at(&mut self, op: OpCode, pos: &Position) -> usize {
+        self.chunk.write(op, pos.clone())
+    }
+
+    fn emit_constant(&mut self, value: Value) {
+        let index = self.chunk.add_constant(value);
+        self.emit(OpCode::Constant(index));
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        self.chunk.add_name(name)
+    }
+
+    /// 当前位置，用作向后跳转的目标
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    /// 将已发出的跳转指令的目标改为当前位置
+    fn patch_jump(&mut self, at: usize) {
+        let target = self.here();
+        match &mut self.chunk.code[at] {
+            OpCode::Jump(offset) | OpCode::JumpIfFalse(offset) | OpCode::JumpIfTrue(offset)
+            | OpCode::ForNext(offset) | OpCode::MatchArm(_, offset) | OpCode::PushHandler(offset) => *offset = target,
+            _ => {}
+        }
+    }
+
+    /// 在新的块作用域中编译语句块
+    fn compile_scoped_block(&mut self, statements: &[ASTNode]) {
+        self.emit(OpCode::EnterScope);
+        self.scope_depth += 1;
+        self.compile_block(statements);
+        self.scope_depth -= 1;
+        self.emit(OpCode::ExitScope);
+    }
+
+    fn compile_block(&mut self, statements: &[ASTNode]) {
+        for stmt in statements {
+            self.compile_statement(stmt);
+        }
+    }
+
+    /// 离开循环体内比 `depth` 更深的作用域
+    fn exit_scopes_to(&mut self, depth: usize) {
+        for _ in depth..self.scope_depth {
+            self.emit(OpCode::ExitScope);
+        }
+    }
+
+    /// 由内向外离开最内层的 `count` 个 try 语句：移除错误处理并执行 finally。
+    /// 结束时 `scope_depth` 是最外层的那个 try 语句所在的深度，由调用方恢复
+    fn exit_trys(&mut self, count: usize) {
+        let mut exited = Vec::with_capacity(count);
+        for _ in 0..count {
+            let Some(context) = self.trys.pop() else {
+                break;
+            };
+            self.exit_scopes_to(context.scope_depth);
+            self.scope_depth = context.scope_depth;
+            if context.has_handler {
+                self.emit(OpCode::PopHandler);
+            }
+            // finally 中的语句由外层的 try 处理
+            if let Some(cleanup) = &context.finally {
+                self.compile_scoped_block(cleanup);
+            }
+            exited.push(context);
+        }
+        self.trys.extend(exited.into_iter().rev());
+    }
+
+    /// 回填循环中 break 和 continue 的跳转目标
+    fn finish_loop(&mut self, context: LoopContext, body_end: usize, exit: usize) {
+        for at in context.continues {
+            self.chunk.code[at] = OpCode::Jump(body_end);
+        }
+        for at in context.breaks {
+            self.chunk.code[at] = OpCode::Jump(exit);
+        }
+    }
+
+    fn compile_statement(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Print(expr) => {
+                self.compile_expression(expr);
+                self.emit(OpCode::Print);
+            }
+            ASTNode::Println(expr) => {
+                self.compile_expression(expr);
+                self.emit(OpCode::Println);
+            }
+            ASTNode::Var(name, binding, expr, _) => {
+                self.compile_expression(expr);
+                let name = self.name(name);
+                self.emit(OpCode::DefineVar(name, *binding));
+            }
+            ASTNode::TypedVar(name, binding, var_type, expr, pos) => {
+                self.compile_expression(expr);
+                let name = self.name(name);
+                self.emit_at(OpCode::DefineTyped(name, *binding, var_type.clone()), pos);
+            }
+            ASTNode::ArrayDecl(name, binding, element_type, sizes, init_values, pos) => {
+                // 只有一个初始值时用它填充整个数组，否则最多使用最外层长度个初始值
+                let count = if init_values.len() == 1 { 1 } else { init_values.len().min(sizes[0]) };
+                for init in init_values.iter().take(count) {
+                    self.compile_expression(init);
+                }
+                let name = self.name(name);
+                self.emit_at(OpCode::DefineArray(name, *binding, element_type.clone(), sizes.clone(), count), pos);
+            }
+            ASTNode::ConstDecl(name, binding, const_type, expr, pos) => {
+                self.compile_expression(expr);
+                let name = self.name(name);
+                self.emit_at(OpCode::DefineConst(name, *binding, const_type.clone()), pos);
+            }
+            ASTNode::ListDecl(name, binding, init_values, _) => {
+                for init in init_values {
+                    self.compile_expression(init);
+                }
+                let name = self.name(name);
+                self.emit(OpCode::DefineList(name, *binding, init_values.len()));
+            }
+            ASTNode::Assign(name, binding, expr, pos) => {
+                self.compile_expression(expr);
+                let name = self.name(name);
+                self.emit_at(OpCode::Assign(name, *binding), pos);
+            }
+            ASTNode::PathAssign(name, binding, path, value_expr, pos) => {
+                // 下标由外向内依次压栈，最后压入赋给元素或字段的值
+                let mut operands = Vec::with_capacity(path.len());
+                for accessor in path {
+                    operands.push(match accessor {
+                        Accessor::Index(index_expr) => {
+                            self.compile_expression(index_expr);
+                            PathOperand::Index
+                        }
+                        Accessor::Field(field) => PathOperand::Field(self.name(field)),
+                    });
+                }
+                self.compile_expression(value_expr);
+                let name = self.name(name);
+                self.emit_at(OpCode::SetPath(name, *binding, operands), pos);
+            }
+            ASTNode::For(_, range_expr, body, pos) => self.compile_for(range_expr, body, pos),
+            ASTNode::While(condition, body) => self.compile_while(condition, body),
+            ASTNode::If(condition, then_branch, else_branch) => {
+                self.compile_expression(condition);
+                let to_else = self.emit(OpCode::JumpIfFalse(0));
+                self.compile_scoped_block(then_branch);
+                match else_branch {
+                    Some(else_stmts) => {
+                        let to_end = self.emit(OpCode::Jump(0));
+                        self.patch_jump(to_else);
+                        self.compile_scoped_block(else_stmts);
+                        self.patch_jump(to_end);
+                    }
+                    None => self.patch_jump(to_else),
+                }
+            }
+            ASTNode::Break(_) => {
+                // 语法分析已经保证 break/continue 只出现在循环中
+                if let Some(body_depth) = self.loops.last().map(|context| context.body_depth) {
+                    let depth = self.scope_depth;
+                    self.exit_trys(self.trys_in_loop());
+                    self.discard_pending_errors();
+                    self.exit_scopes_to(body_depth - 1);
+                    let at = self.emit(OpCode::Jump(0));
+                    self.scope_depth = depth;
+                    if let Some(context) = self.loops.last_mut() {
+                        context.breaks.push(at);
+                    }
+                }
+            }
+            ASTNode::Continue(_) => {
+                if let Some(body_depth) = self.loops.last().map(|context| context.body_depth) {
+                    let depth = self.scope_depth;
+                    self.exit_trys(self.trys_in_loop());
+                    self.discard_pending_errors();
+                    self.exit_scopes_to(body_depth);
+                    let at = self.emit(OpCode::Jump(0));
+                    self.scope_depth = depth;
+                    if let Some(context) = self.loops.last_mut() {
+                        context.continues.push(at);
+                    }
+                }
+            }
+            ASTNode::Try(body, catch, finally, pos) => self.compile_try(body, catch.as_ref(), finally.as_deref(), pos),
+            ASTNode::Throw(expr, pos) => {
+                self.compile_expression(expr);
+                self.emit_at(OpCode::Throw, pos);
+            }
+            ASTNode::Input(prompt, var_name, binding, pos) => {
+                self.compile_expression(prompt);
+                let name = self.name(var_name);
+                self.emit_at(OpCode::Input(name, *binding), pos);
+            }
+            ASTNode::Function(name, params, return_type, body, pos) => {
+                let function = self.compile_typed_function(params, return_type, body, pos);
+                self.define_function(name, function);
+            }
+            ASTNode::Expr(name, typed_params, body) => {
+                let params = typed_params.iter().map(|(_, name)| name.clone()).collect();
+                let function = self.compile_function(params, body, Signature::default());
+                self.define_function(name, function);
+            }
+            ASTNode::StructDecl(name, fields, _) => {
+                let struct_type = StructType { name: name.clone(), fields: fields.clone(), caught: None };
+                self.chunk.structs.push(Rc::new(struct_type));
+                self.emit(OpCode::DefineStruct(self.chunk.structs.len() - 1));
+            }
+            ASTNode::EnumDecl(name, variants, _) => {
+                let enum_type = EnumType { name: name.clone(), variants: variants.clone() };
+                self.chunk.enums.push(Rc::new(enum_type));
+                self.emit(OpCode::DefineEnum(self.chunk.enums.len() - 1));
+            }
+            ASTNode::Match(subject, arms, pos) => {
+                self.compile_match(subject, arms, pos, |compiler, body| compiler.compile_block(body));
+            }
+            ASTNode::FunctionCall(..) | ASTNode::Call(..) => {
+                self.compile_expression(node);
+                self.emit(OpCode::Pop);
+            }
+            ASTNode::Return(expr, _) => {
+                // 语法分析已经保证 return 只出现在函数体中；虚拟机返回时直接丢弃循环状态、错误处理和块作用域，
+                // 只需要先执行 finally
+                self.compile_expression(expr);
+                let depth = self.scope_depth;
+                self.exit_trys(self.trys.len());
+                self.scope_depth = depth;
+                self.emit(OpCode::Return);
+            }
+            ASTNode::Import(filename, pos) => {
+                let name = self.name(filename);
+                self.emit_at(OpCode::Import(name), pos);
+            }
+            // 其他表达式作为语句时不执行
+            _ => {}
+        }
+    }
+
+    fn define_function(&mut self, name: &str, function: Function) {
+        self.chunk.functions.push(Rc::new(function));
+        let index = self.chunk.functions.len() - 1;
+        let name = self.name(name);
+        self.emit(OpCode::DefineFunction(name, index));
+    }
+
+    /// for 循环正在遍历的值由虚拟机单独保存，每次迭代使用新的作用域保存循环变量
+    fn compile_for(&mut self, range_expr: &ASTNode, body: &[ASTNode], pos: &Position) {
+        // 支持 start..end 格式；其他表达式的值在运行时才知道是映射还是范围的终点
+        match range_expr {
+            ASTNode::BinaryOp(left, op, right, _) if op == ".." => {
+                self.compile_expression(left);
+                self.compile_expression(right);
+                self.emit_at(OpCode::ForRange, pos);
+            }
+            _ => {
+                self.compile_expression(range_expr);
+                self.emit_at(OpCode::ForEach, pos);
+            }
+        }
+
+        let loop_start = self.here();
+        let to_exit = self.emit(OpCode::ForNext(0));
+
+        self.scope_depth += 1;
+        self.loops.push(LoopContext { body_depth: self.scope_depth, breaks: Vec::new(), continues: Vec::new() });
+        self.compile_block(body);
+        let context = self.loops.pop();
+        self.scope_depth -= 1;
+
+        let body_end = self.emit(OpCode::ExitScope);
+        self.emit(OpCode::Jump(loop_start));
+
+        let exit = self.here();
+        self.patch_jump(to_exit);
+        self.emit(OpCode::ForEnd);
+
+        if let Some(context) = context {
+            self.finish_loop(context, body_end, exit);
+        }
+    }
+
+    /// 最内层的循环中开始的 try 语句的个数，break/continue 需要离开这些 try
+    fn trys_in_loop(&self) -> usize {
+        self.trys.iter().rev().take_while(|context| context.loop_count == self.loops.len()).count()
+    }
+
+    /// 从出错路径上的 finally 中 break/continue 时不再重新抛出错误，丢弃最内层的循环中等待重新抛出的错误
+    fn discard_pending_errors(&mut self) {
+        let count = self.pending_errors.iter().rev().take_while(|&&loop_count| loop_count == self.loops.len()).count();
+        for _ in 0..count {
+            self.emit(OpCode::DiscardError);
+        }
+    }
+
+    /// try 语句体和 catch 出错时跳转到错误处理的位置：有 catch 时进入 catch，
+    /// 否则执行 finally 之后重新抛出错误。有 finally 时 catch 中的错误同样先执行 finally
+    fn compile_try(&mut self, body: &[ASTNode], catch: Option<&(String, Vec<ASTNode>)>, finally: Option<&[ASTNode]>, pos: &Position) {
+        let to_handler = self.emit(OpCode::PushHandler(0));
+        self.trys.push(TryContext {
+            loop_count: self.loops.len(),
+            scope_depth: self.scope_depth,
+            has_handler: true,
+            finally: finally.map(<[ASTNode]>::to_vec),
+        });
+        self.compile_scoped_block(body);
+        self.emit(OpCode::PopHandler);
+        let to_after_catch = self.emit(OpCode::Jump(0));
+
+        let to_finally_error = match catch {
+            Some((_, handler)) => {
+                self.patch_jump(to_handler);
+                let to_finally_error = finally.map(|_| self.emit(OpCode::PushHandler(0)));
+                if let Some(context) = self.trys.last_mut() {
+                    context.has_handler = to_finally_error.is_some();
+                }
+                // 错误值是 catch 语句块作用域中的第一个槽位
+                self.emit(OpCode::EnterCatch);
+                self.scope_depth += 1;
+                self.compile_block(handler);
+                self.scope_depth -= 1;
+                self.emit(OpCode::ExitScope);
+                if to_finally_error.is_some() {
+                    self.emit(OpCode::PopHandler);
+                }
+                to_finally_error
+            }
+            None => Some(to_handler),
+        };
+        self.trys.pop();
+        self.patch_jump(to_after_catch);
+
+        if let (Some(cleanup), Some(to_finally_error)) = (finally, to_finally_error) {
+            self.compile_scoped_block(cleanup);
+            let to_end = self.emit(OpCode::Jump(0));
+            self.patch_jump(to_finally_error);
+            self.pending_errors.push(self.loops.len());
+            self.compile_scoped_block(cleanup);
+            self.pending_errors.pop();
+            self.emit_at(OpCode::Rethrow, pos);
+            self.patch_jump(to_end);
+        }
+    }
+
+    fn compile_while(&mut self, condition: &ASTNode, body: &[ASTNode]) {
+        let loop_start = self.here();
+        self.compile_expression(condition);
+        let to_exit = self.emit(OpCode::JumpIfFalse(0));
+
+        self.emit(OpCode::EnterScope);
+        self.scope_depth += 1;
+        self.loops.push(LoopContext { body_depth: self.scope_depth, breaks: Vec::new(), continues: Vec::new() });
+        self.compile_block(body);
+        let context = self.loops.pop();
+        self.scope_depth -= 1;
+
+        let body_end = self.emit(OpCode::ExitScope);
+        self.emit(OpCode::Jump(loop_start));
+
+        let exit = self.here();
+        self.patch_jump(to_exit);
+
+        if let Some(context) = context {
+            self.finish_loop(context, body_end, exit);
+        }
+    }
+
+    /// 依次尝试每个分支的模式，匹配的分支在新的块作用域中执行，`compile_arm` 编译分支体。
+    /// match 表达式的分支体在栈上留下一个值，离开分支的作用域不影响栈
+    fn compile_match<T>(&mut self, subject: &ASTNode, arms: &[(Pattern, T)], pos: &Position, compile_arm: impl Fn(&mut Self, &T)) {
+        self.compile_expression(subject);
+
+        let mut to_end = Vec::new();
+        for (pattern, arm) in arms {
+            self.chunk.patterns.push(pattern.clone());
+            let to_next = self.emit(OpCode::MatchArm(self.chunk.patterns.len() - 1, 0));
+
+            self.scope_depth += 1;
+            compile_arm(self, arm);
+            self.scope_depth -= 1;
+
+            self.emit(OpCode::ExitScope);
+            to_end.push(self.emit(OpCode::Jump(0)));
+            self.patch_jump(to_next);
+        }
+        self.emit_at(OpCode::NoMatch, pos);
+
+        for at in to_end {
+            self.patch_jump(at);
+        }
+    }
+
+    /// 函数调用：先检查函数是否存在，再计算参数
+    fn compile_call(&mut self, name: &str, args: &[ASTNode], pos: &Position) {
+        let mutates = Builtin::lookup(name).is_some_and(Builtin::mutates);
+        let name = self.name(name);
+        self.emit_at(OpCode::CheckFunction(name), pos);
+        // push(list, 1) 等调用修改的是变量本身，第一个参数不求值
+        if mutates && let Some(ASTNode::Identifier(target, binding, _)) = args.first() {
+            for arg in &args[1..] {
+                self.compile_expression(arg);
+            }
+            let target = self.name(target);
+            self.emit_at(OpCode::CallInPlace(name, target, *binding, args.len() - 1), pos);
+            return;
+        }
+        for arg in args {
+            self.compile_expression(arg);
+        }
+        self.emit_at(OpCode::Call(name, args.len()), pos);
+    }
+
+    fn compile_expression(&mut self, node: &ASTNode) {
+        match node {
+            ASTNode::Integer(n) => self.emit_constant(Value::Int(*n)),
+            ASTNode::Number(n) => self.emit_constant(Value::Double(*n)),
+            ASTNode::String(s) => self.emit_constant(Value::Str(s.clone())),
+            ASTNode::Bool(b) => self.emit_constant(Value::Bool(*b)),
+            ASTNode::Identifier(name, binding, pos) => {
+                let name = self.name(name);
+                self.emit_at(OpCode::GetVar(name, *binding), pos);
+            }
+            ASTNode::IndexAccess(array_expr, index_expr, pos) => {
+                // 与解释器相同，下标中没有函数调用时直接读取变量中的元素，不把整个变量压栈
+                if let ASTNode::Identifier(name, binding, _) = array_expr.as_ref() && !may_call(index_expr) {
+                    self.compile_expression(index_expr);
+                    let name = self.name(name);
+                    self.emit_at(OpCode::IndexVar(name, *binding), pos);
+                    return;
+                }
+                self.compile_expression(array_expr);
+                self.compile_expression(index_expr);
+                self.emit_at(OpCode::Index, pos);
+            }
+            ASTNode::FieldAccess(struct_expr, field, pos) => {
+                self.compile_expression(struct_expr);
+                let field = self.name(field);
+                self.emit_at(OpCode::GetField(field), pos);
+            }
+            ASTNode::ListLiteral(elements) => {
+                for element in elements {
+                    self.compile_expression(element);
+                }
+                self.emit(OpCode::MakeList(elements.len()));
+            }
+            ASTNode::MapLiteral(entries, pos) => {
+                for (key, value) in entries {
+                    self.compile_expression(key);
+                    self.compile_expression(value);
+                }
+                self.emit_at(OpCode::MakeMap(entries.len()), pos);
+            }
+            ASTNode::FunctionCall(name, args, pos) => self.compile_call(name, args, pos),
+            ASTNode::Call(callee, args, pos) => {
+                self.compile_expression(callee);
+                for arg in args {
+                    self.compile_expression(arg);
+                }
+                self.emit_at(OpCode::CallValue(args.len()), pos);
+            }
+            ASTNode::Lambda(params, return_type, body, pos) => {
+                let function = self.compile_typed_function(params, return_type, body, pos);
+                self.chunk.functions.push(Rc::new(function));
+                self.emit(OpCode::MakeClosure(self.chunk.functions.len() - 1));
+            }
+            ASTNode::BinaryOp(left, op, right, pos) => match op.as_str() {
+                // 短路求值：左操作数已能决定结果时跳过右操作数
+                "&&" | "||" => {
+                    self.compile_expression(left);
+                    let short_circuit = if op == "&&" { OpCode::JumpIfFalse(0) } else { OpCode::JumpIfTrue(0) };
+                    let to_short = self.emit(short_circuit);
+                    self.compile_expression(right);
+                    self.emit(OpCode::Truthy);
+                    let to_end = self.emit(OpCode::Jump(0));
+                    self.patch_jump(to_short);
+                    self.emit_constant(Value::Bool(op == "||"));
+                    self.patch_jump(to_end);
+                }
+                _ => {
+                    self.compile_expression(left);
+                    self.compile_expression(right);
+                    let op = BinaryOp::from_symbol(op).expect("the parser only produces known binary operators");
+                    self.emit_at(OpCode::Binary(op), pos);
+                }
+            },
+            ASTNode::UnaryOp(op, expr, pos) => {
+                self.compile_expression(expr);
+                self.emit_at(OpCode::Unary(op.clone()), pos);
+            }
+            ASTNode::TypeConversion(target_type, expr, pos) => {
+                self.compile_expression(expr);
+                self.emit_at(OpCode::Convert(target_type.clone()), pos);
+            }
+            ASTNode::IfExpr(condition, then_expr, else_expr) => {
+                self.compile_expression(condition);
+                let to_else = self.emit(OpCode::JumpIfFalse(0));
+                self.compile_expression(then_expr);
+                let to_end = self.emit(OpCode::Jump(0));
+                self.patch_jump(to_else);
+                self.compile_expression(else_expr);
+                self.patch_jump(to_end);
+            }
+            ASTNode::EnumVariant(enum_name, variant, args, pos) => {
+                for arg in args {
+                    self.compile_expression(arg);
+                }
+                let enum_name = self.name(enum_name);
+                let variant = self.name(variant);
+                self.emit_at(OpCode::MakeVariant(enum_name, variant, args.len()), pos);
+            }
+            ASTNode::MatchExpr(subject, arms, pos) => {
+                self.compile_match(subject, arms, pos, |compiler, arm_expr| compiler.compile_expression(arm_expr));
+            }
+            _ => self.emit_constant(Value::Double(0.0)),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::ast::Type;
+use crate::interpreter::Value;
+
+/// 作用域的种类，用于在诊断信息中描述查找过的作用域
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeKind {
+    Global,
+    Function(Rc<str>),
+    Block,
+}
+
+impl ScopeKind {
+    pub fn describe(&self) -> String {
+        match self {
+            ScopeKind::Global => "global scope".to_string(),
+            ScopeKind::Function(name) => format!("function '{}'", name),
+            ScopeKind::Block => "block".to_string(),
+        }
+    }
+}
+
+/// 变量的值以及声明时指定的类型（无类型变量为 `None`）
+#[derive(Debug, Clone)]
+pub struct Variable {
+    pub value: Value,
+    pub var_type: Option<Type>,
+    pub constant: bool, // 常量不能赋值
+}
+
+impl Variable {
+    pub fn new(value: Value, var_type: Option<Type>) -> Self {
+        Self { value, var_type, constant: false }
+    }
+    
+    pub fn constant(value: Value, var_type: Option<Type>) -> Self {
+        Self { value, var_type, constant: true }
+    }
+}
+
+pub type Env = Rc<RefCell<Environment>>;
+
+/// 全局变量名到槽位的映射。解析器为程序中的全局变量名分配槽位，同一个名称在解释器中始终对应同一个槽位，
+/// 所以之后在 REPL 中或 import 时解析的代码也使用相同的槽位
+#[derive(Debug, Default)]
+pub struct GlobalSlots {
+    slots: HashMap<String, usize>,
+}
+
+impl GlobalSlots {
+    /// 名称对应的槽位，第一次出现的名称分配新的槽位
+    pub fn slot(&mut self, name: &str) -> usize {
+        if let Some(&slot) = self.slots.get(name) {
+            return slot;
+        }
+        let slot = self.slots.len();
+        self.slots.insert(name.to_string(), slot);
+        slot
+    }
+}
+
+/// 一层作用域。局部作用域（函数和语句块）中的变量保存在槽位中，槽位由解析器在执行前分配；
+/// 全局作用域中的变量同样按槽位保存，宿主程序按名称访问时通过 [`GlobalSlots`] 找到槽位。
+/// 访问局部变量时沿着 `parent` 向外经过解析器给出的层数。
+#[derive(Debug)]
+pub struct Environment {
+    kind: ScopeKind,
+    global_slots: GlobalSlots,
+    globals: Vec<Option<Variable>>, // 全局变量，已分配槽位但还没有声明的变量是 None
+    slots: Vec<Variable>,           // 局部变量
+    parent: Option<Env>,
+}
+
+impl Environment {
+    pub fn new_global() -> Env {
+        Rc::new(RefCell::new(Self {
+            kind: ScopeKind::Global,
+            global_slots: GlobalSlots::default(),
+            globals: Vec::new(),
+            slots: Vec::new(),
+            parent: None,
+        }))
+    }
+
+    pub fn new_child(parent: &Env, kind: ScopeKind) -> Env {
+        Rc::new(RefCell::new(Self {
+            kind,
+            global_slots: GlobalSlots::default(),
+            globals: Vec::new(),
+            slots: Vec::new(),
+            parent: Some(Rc::clone(parent)),
+        }))
+    }
+
+    /// 外层作用域，全局作用域没有外层
+    pub fn parent(&self) -> Option<Env> {
+        self.parent.clone()
+    }
+
+    /// 解析器用来为全局变量名分配槽位
+    pub fn global_slots(&mut self) -> &mut GlobalSlots {
+        &mut self.global_slots
+    }
+
+    /// 按名称声明全局变量，覆盖已有的同名变量
+    pub fn define(&mut self, name: &str, variable: Variable) {
+        let slot = self.global_slots.slot(name);
+        self.define_global(slot, variable);
+    }
+
+    /// 在全局变量的槽位中声明变量，覆盖同一槽位中已有的变量
+    pub fn define_global(&mut self, slot: usize, variable: Variable) {
+        if slot >= self.globals.len() {
+            self.globals.resize(slot + 1, None);
+        }
+        self.globals[slot] = Some(variable);
+    }
+
+    /// 按名称查找全局变量
+    pub fn get(&self, name: &str) -> Option<&Variable> {
+        self.global(*self.global_slots.slots.get(name)?)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Variable> {
+        let slot = *self.global_slots.slots.get(name)?;
+        self.global_mut(slot)
+    }
+
+    /// 槽位中已经声明的全局变量
+    pub fn global(&self, slot: usize) -> Option<&Variable> {
+        self.globals.get(slot)?.as_ref()
+    }
+
+    pub fn global_mut(&mut self, slot: usize) -> Option<&mut Variable> {
+        self.globals.get_mut(slot)?.as_mut()
+    }
+
+    /// 所有全局变量的名称和值
+    pub fn variables(&self) -> impl Iterator<Item = (&String, &Variable)> {
+        self.global_slots.slots.iter().filter_map(|(name, &slot)| Some((name, self.global(slot)?)))
+    }
+
+    /// 所有全局变量的名称
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        self.variables().map(|(name, _)| name)
+    }
+
+    /// 所有全局常量的名称
+    pub fn constant_names(&self) -> impl Iterator<Item = &String> {
+        self.variables().filter(|(_, variable)| variable.constant).map(|(name, _)| name)
+    }
+
+    /// 在当前作用域的槽位中声明变量，覆盖同一槽位中已有的变量
+    pub fn define_slot(&mut self, slot: usize, variable: Variable) {
+        if slot < self.slots.len() {
+            self.slots[slot] = variable;
+        } else {
+            // 槽位按声明的顺序分配，执行到这里时之前的槽位都已经声明过
+            self.slots.resize(slot, Variable::new(Value::Double(0.0), None));
+            self.slots.push(variable);
+        }
+    }
+
+    /// 移除当前作用域的所有局部变量，作用域可以像新建的一样重新使用
+    pub fn clear_slots(&mut self) {
+        self.slots.clear();
+    }
+
+    /// 借用向外第 `depth` 层作用域中的槽位，槽位不存在时返回 `None`
+    pub fn with_slot<R>(&self, depth: usize, slot: usize, f: impl FnOnce(&Variable) -> R) -> Option<R> {
+        if depth == 0 {
+            return self.slots.get(slot).map(f);
+        }
+        self.parent.as_ref()?.borrow().with_slot(depth - 1, slot, f)
+    }
+
+    /// 可变地借用向外第 `depth` 层作用域中的槽位，用于赋值和原地修改变量中的元素
+    pub fn with_slot_mut<R>(&mut self, depth: usize, slot: usize, f: impl FnOnce(&mut Variable) -> R) -> Option<R> {
+        if depth == 0 {
+            return self.slots.get_mut(slot).map(f);
+        }
+        self.parent.as_ref()?.borrow_mut().with_slot_mut(depth - 1, slot, f)
+    }
+
+    /// 由内向外列出查找变量时经过的作用域，例如 `block -> function 'area' -> global scope`
+    pub fn describe_chain(&self) -> String {
+        let mut scopes = vec![self.kind.describe()];
+        let mut current = self.parent.clone();
+        while let Some(env) = current {
+            let env = env.borrow();
+            scopes.push(env.kind.describe());
+            current = env.parent.clone();
+        }
+        scopes.join(" -> ")
+    }
+}
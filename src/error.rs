@@ -7,8 +7,24 @@ pub mod error_messages {
         format!("Unexpected character: '{}' at line {}, column {}", ch, line, column)
     }
     
-    pub fn unexpected_dot() -> String {
-        "Unexpected character: '.'. Did you mean '..' for range?".to_string()
+    pub fn integer_literal_too_large(digits: &str) -> String {
+        format!("Integer literal {} is too large for int (maximum is {})", digits, i64::MAX)
+    }
+    
+    pub fn integer_overflow(left: i64, op: &str, right: i64) -> String {
+        format!("Runtime error: integer overflow in {} {} {}", left, op, right)
+    }
+    
+    pub fn help_integer_overflow() -> String {
+        "help: the result does not fit in int; convert an operand with <double> to compute with larger values".to_string()
+    }
+    
+    pub fn unexpected_tilde() -> String {
+        "Unexpected character: '~'. Did you mean '~/' for integer division?".to_string()
+    }
+    
+    pub fn unexpected_single_logical(ch: char) -> String {
+        format!("Unexpected character: '{}'. Did you mean '{}{}' for logical operation?", ch, ch, ch)
     }
     
     // Parser 错误 - Unexpected tokens
@@ -36,6 +52,22 @@ pub mod error_messages {
         format!("Syntax error: unexpected {} keyword, please check loop syntax", keyword)
     }
     
+    pub fn loop_control_outside_loop(keyword: &str) -> String {
+        format!("Syntax error: '{}' can only be used inside a while or for loop", keyword)
+    }
+    
+    pub fn help_loop_control(keyword: &str) -> String {
+        format!("help: '{}' applies to the innermost enclosing loop; it cannot leave a function body", keyword)
+    }
+    
+    pub fn return_outside_function() -> String {
+        "Syntax error: 'return' can only be used inside a func or expr body".to_string()
+    }
+    
+    pub fn help_return_outside_function() -> String {
+        "help: top-level code has no caller to return to; use an if/else block to skip the remaining statements".to_string()
+    }
+    
     pub fn missing_loop_parens(keyword: &str) -> String {
         format!("Syntax error: {} loop requires parentheses around the condition, expected {}(condition)", keyword, keyword)
     }
@@ -129,17 +161,403 @@ pub mod error_messages {
         format!("Function '{}' is not defined", name)
     }
     
+    pub fn native_function_error(name: &str, message: &str) -> String {
+        format!("Error in native function '{}': {}", name, message)
+    }
+    
+    pub fn help_native_function() -> String {
+        "help: check the number and types of the arguments passed to this function".to_string()
+    }
+    
+    pub fn call_depth_exceeded(name: &str, limit: usize) -> String {
+        format!("Runtime error: calling '{}' exceeds the maximum call depth of {}", name, limit)
+    }
+    
+    pub fn help_call_depth_exceeded() -> String {
+        "help: check that the recursion reaches its base case, or rewrite it as a loop".to_string()
+    }
+    
+    pub fn input_error(name: &str, reason: &str) -> String {
+        format!("Runtime error: cannot read input for '{}': {}", name, reason)
+    }
+    
+    pub fn builtin_argument_type(name: &str, expected: &str, actual: &str) -> String {
+        format!("Type error: '{}' expects {}, got '{}'", name, expected, actual)
+    }
+    
+    pub fn builtin_needs_variable(name: &str) -> String {
+        format!("'{}' modifies its first argument, which must be a list variable", name)
+    }
+    
+    pub fn builtin_index_out_of_bounds(index: i64, length: usize) -> String {
+        format!("Index out of bounds: index {} is out of range for list of length {}", index, length)
+    }
+    
+    pub fn pop_empty_list() -> String {
+        "Cannot pop from an empty list".to_string()
+    }
+    
+    pub fn range_zero_step() -> String {
+        "Invalid argument: the step of 'range' cannot be 0".to_string()
+    }
+    
+    pub fn builtin_empty_pattern(name: &str) -> String {
+        format!("Invalid argument: the str to search for in '{}' cannot be empty", name)
+    }
+    
+    pub fn builtin_negative_argument(name: &str, value: i64) -> String {
+        format!("Invalid argument: '{}' expects a non-negative int, got {}", name, value)
+    }
+    
+    pub fn substring_out_of_bounds(start: usize, end: usize, length: usize) -> String {
+        format!("Index out of bounds: range {}..{} is out of range for str of length {}", start, end, length)
+    }
+    
+    pub fn format_unmatched_brace() -> String {
+        "Invalid format string: unmatched brace, write {{ or }} for a literal brace".to_string()
+    }
+    
+    pub fn format_argument_count(placeholders: usize, values: usize) -> String {
+        format!("Invalid format string: {} placeholders but {} values", placeholders, values)
+    }
+    
+    pub fn function_min_arity_error(name: &str, expected: usize, actual: usize) -> String {
+        format!("Function '{}' expects at least {} arguments, got {}", name, expected, actual)
+    }
+    
+    pub fn math_domain_error(name: &str, args: &str) -> String {
+        format!("Math domain error: {}({}) is not defined", name, args)
+    }
+    
+    pub fn int_out_of_range(value: &str) -> String {
+        format!("Runtime error: {} is out of range for int", value)
+    }
+    
+    pub fn assign_to_constant(name: &str) -> String {
+        format!("Cannot assign to constant '{}'", name)
+    }
+    
+    pub fn help_assign_to_constant() -> String {
+        "help: constants cannot be changed after they are declared, use var for values that change".to_string()
+    }
+    
+    pub fn try_requires_block() -> String {
+        "try, catch and finally must be followed by a block in braces".to_string()
+    }
+    
+    pub fn catch_requires_variable() -> String {
+        "catch requires a variable name in parentheses for the caught error".to_string()
+    }
+    
+    pub fn try_without_handler() -> String {
+        "try must be followed by catch, finally or both".to_string()
+    }
+    
+    pub fn catch_without_try(keyword: &str) -> String {
+        format!("Syntax error: '{}' without a matching try", keyword)
+    }
+    
+    pub fn help_try_syntax() -> String {
+        "help: syntax is `try { ... } catch (e) { ... } finally { ... }`".to_string()
+    }
+    
+    pub fn example_try_syntax() -> String {
+        "example: try { var n = <int>s; } catch (e) { println(e.message); }".to_string()
+    }
+    
+    pub fn redeclare_constant(name: &str) -> String {
+        format!("Cannot redeclare constant '{}' in the same scope", name)
+    }
+    
+    pub fn constant_declared_at(name: &str, location: &str) -> String {
+        format!("'{}' is declared as a constant at {}", name, location)
+    }
+    
+    pub fn builtin_constant(name: &str) -> String {
+        format!("'{}' is a built-in constant", name)
+    }
+    
+    // 类型检查错误
+    pub fn operation_type_error(op: &str, left: &str, right: &str) -> String {
+        format!("Type error: cannot perform operation '{}' between '{}' and '{}'", op, left, right)
+    }
+    
+    pub fn help_operation_type() -> String {
+        "help: ensure both operands are of compatible types for the operation".to_string()
+    }
+    
+    pub fn example_operation_type() -> String {
+        "example: for arithmetic operations, both operands should be numeric types".to_string()
+    }
+    
+    pub fn range_outside_for() -> String {
+        "Type error: a range 'start..end' can only be used as the range of a for loop".to_string()
+    }
+    
+    pub fn help_range_outside_for() -> String {
+        "help: use range(start, end, 1) to get a list of the numbers in a range".to_string()
+    }
+    
+    pub fn range_bound_type(actual: &str) -> String {
+        format!("Type error: the bounds of a for loop range must be int, got '{}'", actual)
+    }
+    
+    pub fn help_range_bound_type() -> String {
+        "help: convert the bound with <int>, e.g. for i in 0..<int>(n) { ... }".to_string()
+    }
+    
+    pub fn incompatible_value(actual: &str, expected: &str) -> String {
+        format!("Cannot convert a value of type '{}' to {}", actual, expected)
+    }
+    
+    pub fn incompatible_assignment(name: &str, expected: &str, reason: &str) -> String {
+        format!("Type error: cannot assign to '{}' of type '{}': {}", name, expected, reason)
+    }
+    
+    pub fn help_incompatible_assignment(expected: &str) -> String {
+        format!("help: assign a value that can be converted to '{}', or declare the variable without a type", expected)
+    }
+    
+    pub fn impossible_conversion(actual: &str, expected: &str) -> String {
+        format!("Type conversion failed: a value of type '{}' cannot be converted to '{}'", actual, expected)
+    }
+    
+    pub fn help_impossible_conversion() -> String {
+        "help: arrays, lists, maps, enums and functions can only be converted to str".to_string()
+    }
+    
+    // 函数的参数类型和返回类型
+    pub fn argument_type_error(function: &str, param: &str, expected: &str, reason: &str) -> String {
+        format!("Type error: parameter '{}' of '{}' expects '{}': {}", param, function, expected, reason)
+    }
+    
+    pub fn return_type_error(function: &str, expected: &str, reason: &str) -> String {
+        format!("Type error: '{}' must return '{}': {}", function, expected, reason)
+    }
+    
+    pub fn function_declared_at(name: &str, location: &str) -> String {
+        format!("'{}' is declared at {}", name, location)
+    }
+    
+    pub fn help_argument_type(expected: &str) -> String {
+        format!("help: pass a value that can be converted to '{}', or convert it explicitly with <{}>value", expected, expected)
+    }
+    
+    pub fn help_return_type(expected: &str) -> String {
+        format!("help: return a value that can be converted to '{}', or remove the return type from the declaration", expected)
+    }
+    
+    pub fn invalid_return_type() -> String {
+        "Syntax error: expected a return type after '->'".to_string()
+    }
+    
+    pub fn help_return_type_syntax() -> String {
+        "help: syntax is `func name(<type>param, ...) -> type { ... }` where type is int, str, bool, float or double".to_string()
+    }
+    
+    pub fn example_return_type_syntax() -> String {
+        "example: func area(<double>w, <double>h) -> double { return w * h; }".to_string()
+    }
+    
+    pub fn help_builtin_usage(usage: &str) -> String {
+        format!("help: {}", usage)
+    }
+    
     // Array/List 错误
     pub fn array_type_error(expected_type: &str, actual_type: &str) -> String {
         format!("Type error in array assignment: cannot assign value of type '{}' to array of type '{}'", actual_type, expected_type)
     }
     
-    pub fn array_index_out_of_bounds(index: usize, length: usize) -> String {
-        format!("Index out of bounds: index {} is out of range for array of length {}", index, length)
+    pub fn invalid_array_size() -> String {
+        "Array size must be a non-negative int literal".to_string()
     }
     
-    pub fn list_index_out_of_bounds(index: usize, length: usize) -> String {
-        format!("Index out of bounds: index {} is out of range for list of length {}", index, length)
+    pub fn unsupported_assignment_target() -> String {
+        "Syntax error: cannot assign to the result of a function call".to_string()
+    }
+    
+    pub fn help_assignment_target() -> String {
+        "help: only variables and their elements or fields can be assigned; store the result in a variable first".to_string()
+    }
+    
+    pub fn example_assignment_target() -> String {
+        "example: var p = make_point(); p.x = 1;".to_string()
+    }
+    
+    pub fn help_array_size() -> String {
+        "help: the shape of an array is fixed when it is declared; use a list for a length computed at run time".to_string()
+    }
+    
+    pub fn example_array_size() -> String {
+        "example: var <int>grid[3][4]; or var items = []; push(items, x);".to_string()
+    }
+    
+    pub fn too_many_array_values(length: usize, count: usize) -> String {
+        format!("Too many initial values: array of length {} got {} values", length, count)
+    }
+    
+    pub fn help_array_values() -> String {
+        "help: give at most as many values as the array has elements, missing values are filled with the default value".to_string()
+    }
+    
+    pub fn index_out_of_bounds(index: i64, type_name: &str, length: usize) -> String {
+        format!("Index out of bounds: index {} is out of range for {} of length {}", index, type_name, length)
+    }
+    
+    pub fn index_type_error(target_type: &str, index_type: &str) -> String {
+        format!("Type error: index of {} must be an int, got '{}'", target_type, index_type)
+    }
+    
+    pub fn not_indexable(type_name: &str) -> String {
+        format!("Type error: value of type '{}' cannot be indexed", type_name)
+    }
+    
+    pub fn not_index_assignable(type_name: &str) -> String {
+        format!("Type error: cannot assign to an index of value of type '{}'", type_name)
+    }
+    
+    pub fn help_index_type() -> String {
+        "help: arrays, lists and strs are indexed by ints, e.g. xs[0]; maps are indexed by their keys, e.g. m[\"a\"]".to_string()
+    }
+    
+    // Struct 错误
+    pub fn struct_arity_error(name: &str, expected: usize, actual: usize) -> String {
+        format!("Struct '{}' has {} fields, got {} values", name, expected, actual)
+    }
+    
+    pub fn help_struct_constructor(name: &str, fields: &str) -> String {
+        format!("help: pass one value per field in declaration order: {}({})", name, fields)
+    }
+    
+    pub fn unknown_field(struct_name: &str, field: &str) -> String {
+        format!("Struct '{}' has no field '{}'", struct_name, field)
+    }
+    
+    pub fn help_struct_fields(struct_name: &str, fields: &str) -> String {
+        format!("help: the fields of '{}' are: {}", struct_name, fields)
+    }
+    
+    pub fn field_access_on_non_struct(field: &str, type_name: &str) -> String {
+        format!("Type error: cannot access field '{}' on value of type '{}'", field, type_name)
+    }
+    
+    pub fn struct_field_type_error(struct_name: &str, field: &str, expected: &str, actual: &str) -> String {
+        format!("Type error: field '{}' of struct '{}' expects '{}', got '{}'", field, struct_name, expected, actual)
+    }
+    
+    pub fn duplicate_struct_field(struct_name: &str, field: &str) -> String {
+        format!("Syntax error: duplicate field '{}' in struct '{}'", field, struct_name)
+    }
+    
+    pub fn expected_field_name() -> String {
+        "Syntax error: expected a field name after '.'".to_string()
+    }
+    
+    pub fn help_struct_declaration() -> String {
+        "help: declare fields separated by commas, e.g. struct Point { <double>x, <double>y }".to_string()
+    }
+    
+    // 函数值错误
+    pub fn not_callable(type_name: &str) -> String {
+        format!("Type error: value of type '{}' is not callable", type_name)
+    }
+    
+    pub fn help_not_callable() -> String {
+        "help: only functions and lambdas can be called, e.g. var twice = func (x) => x * 2; twice(3)".to_string()
+    }
+    
+    // Enum/match 错误
+    pub fn undefined_enum(name: &str) -> String {
+        format!("Enum '{}' is not defined", name)
+    }
+    
+    pub fn unknown_variant(enum_name: &str, variant: &str) -> String {
+        format!("Enum '{}' has no variant '{}'", enum_name, variant)
+    }
+    
+    pub fn help_enum_variants(enum_name: &str, variants: &str) -> String {
+        format!("help: the variants of '{}' are: {}", enum_name, variants)
+    }
+    
+    pub fn variant_payload_count(enum_name: &str, variant: &str, expected: usize, actual: usize) -> String {
+        format!("Variant '{}::{}' expects {} values, got {}", enum_name, variant, expected, actual)
+    }
+    
+    pub fn variant_payload_type_error(enum_name: &str, variant: &str, field: &str, expected: &str, actual: &str) -> String {
+        format!("Type error: value '{}' of variant '{}::{}' expects '{}', got '{}'", field, enum_name, variant, expected, actual)
+    }
+    
+    pub fn duplicate_enum_variant(enum_name: &str, variant: &str) -> String {
+        format!("Syntax error: duplicate variant '{}' in enum '{}'", variant, enum_name)
+    }
+    
+    pub fn help_enum_declaration() -> String {
+        "help: declare variants separated by commas, e.g. enum Shape { Circle(<double>r), Empty }".to_string()
+    }
+    
+    pub fn invalid_pattern() -> String {
+        "Syntax error: expected a pattern: a literal, a range like 1..5, an enum variant like Shape::Circle(r), or _".to_string()
+    }
+    
+    pub fn missing_fat_arrow() -> String {
+        "Syntax error: expected '=>' after the match pattern".to_string()
+    }
+    
+    pub fn help_match_arm() -> String {
+        "help: write each arm as pattern => body, e.g. 1 => println(\"one\")".to_string()
+    }
+    
+    pub fn mixed_enum_patterns(expected_enum: &str, found_enum: &str) -> String {
+        format!("Type error: match arms mix variants of enum '{}' and enum '{}'", expected_enum, found_enum)
+    }
+    
+    pub fn non_exhaustive_match(missing: &str) -> String {
+        format!("Non-exhaustive match: missing {}", missing)
+    }
+    
+    pub fn non_exhaustive_literal_match() -> String {
+        "Non-exhaustive match: literal and range patterns cannot cover every value".to_string()
+    }
+    
+    pub fn help_non_exhaustive_match() -> String {
+        "help: add an arm for each missing case, or a '_' arm to handle everything else".to_string()
+    }
+    
+    pub fn no_matching_arm(value: &str) -> String {
+        format!("Runtime error: no match arm matches value {}", value)
+    }
+    
+    // Map 错误
+    pub fn map_key_not_found(key: &str) -> String {
+        format!("Key not found: map has no entry for key {}", key)
+    }
+    
+    pub fn invalid_map_key(type_name: &str) -> String {
+        format!("Type error: map keys must be str, int or bool, got '{}'", type_name)
+    }
+    
+    pub fn help_map_key_not_found() -> String {
+        "help: add the entry with map[key] = value before reading it".to_string()
+    }
+    
+    pub fn help_map_key() -> String {
+        "help: use a str, int or bool value as the key, e.g. counts[\"apple\"] or grid[3]".to_string()
+    }
+    
+    pub fn map_entry_missing_colon() -> String {
+        "Syntax error: expected ':' between key and value in map literal".to_string()
+    }
+    
+    pub fn help_map_literal() -> String {
+        "help: write each entry as key: value, e.g. {\"a\": 1, \"b\": 2}; use {:} for an empty map".to_string()
+    }
+    
+    pub fn division_by_zero(op: &str) -> String {
+        format!("Runtime error: division by zero in '{}' operation", op)
+    }
+    
+    pub fn help_division_by_zero() -> String {
+        "help: check that the divisor is not zero before dividing, e.g. if (b != 0) { ... }".to_string()
     }
     
     pub fn help_array_bounds() -> String {
@@ -198,6 +616,18 @@ pub mod error_messages {
         "example: var name = \"Alice\"".to_string()
     }
     
+    pub fn const_requires_initializer() -> String {
+        "Constant declaration requires an initializer".to_string()
+    }
+    
+    pub fn help_const_initializer() -> String {
+        "help: syntax is `const NAME = value` or `const <type>NAME = value`".to_string()
+    }
+    
+    pub fn example_const_initializer() -> String {
+        "example: const <int>MAX_USERS = 100;".to_string()
+    }
+    
     pub fn help_undefined_identifier() -> String {
         "help: you must declare variables before using them".to_string()
     }
@@ -210,11 +640,12 @@ pub mod error_messages {
 #[derive(Debug, Clone)]
 pub struct CompilerError {
     pub message: String,
-    pub line: usize,
+    pub line: usize,   // 行号为 0 表示错误没有对应的源代码，例如宿主程序调用了不存在的函数
     pub column: usize,
     pub file_path: String,
     pub source_line: String,
     pub suggestion: Option<String>,
+    pub note: Option<String>,
     pub help: Option<String>,
     pub example: Option<String>,
 }
@@ -228,6 +659,7 @@ impl CompilerError {
             file_path,
             source_line,
             suggestion: None,
+            note: None,
             help: None,
             example: None,
         }
@@ -238,6 +670,11 @@ impl CompilerError {
         self
     }
     
+    pub fn with_note(mut self, note: String) -> Self {
+        self.note = Some(note);
+        self
+    }
+    
     pub fn with_help(mut self, help: String) -> Self {
         self.help = Some(help);
         self
@@ -252,41 +689,42 @@ impl CompilerError {
 impl fmt::Display for CompilerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // 主要错误消息
-        writeln!(f, "{}", self.message)?;
-        
-        // 位置信息
-        writeln!(f, "  --> {}:{}:{}", self.file_path, self.line, self.column)?;
-        writeln!(f, "  |")?;
-        
-        // 源代码行
-        writeln!(f, "{} | {}", self.line, self.source_line)?;
-        write!(f, "  | ")?;
-        
-        // 指向错误的箭头
-        for _i in 1..self.column {
-            write!(f, " ")?;
-        }
+        write!(f, "{}", self.message)?;
         
-        // 计算错误长度（对于标识符，通常是标识符的长度）
-        let error_length = if self.message.contains("is not defined") || 
-                             self.message.contains("requires an initializer") || 
-                             self.message.contains("is not defined") {
-            // 从未定义变量名或标识符中提取长度
-            if let Some(start) = self.message.find('"') {
-                if let Some(end) = self.message[start+1..].find('"') {
-                    end
+        // 没有对应源代码的错误只显示消息和提示
+        if self.line > 0 {
+            writeln!(f)?;
+            
+            // 位置信息
+            writeln!(f, "  --> {}:{}:{}", self.file_path, self.line, self.column)?;
+            writeln!(f, "  |")?;
+            
+            // 源代码行
+            writeln!(f, "{} | {}", self.line, self.source_line)?;
+            write!(f, "  | ")?;
+            
+            // 指向错误的箭头
+            for _i in 1..self.column {
+                write!(f, " ")?;
+            }
+            
+            // 计算错误长度（对于标识符，通常是标识符的长度）
+            let error_length = if self.message.contains("is not defined") || 
+                                 self.message.contains("requires an initializer") || 
+                                 self.message.contains("is not defined") {
+                // 从未定义变量名或标识符中提取长度
+                if let Some(start) = self.message.find('"') {
+                    self.message[start+1..].find('"').unwrap_or(1)
                 } else {
                     1
                 }
             } else {
                 1
+            };
+            
+            for _ in 0..error_length {
+                write!(f, "^")?;
             }
-        } else {
-            1
-        };
-        
-        for _ in 0..error_length {
-            write!(f, "^")?;
         }
         
         if let Some(suggestion) = &self.suggestion {
@@ -294,6 +732,13 @@ impl fmt::Display for CompilerError {
             writeln!(f, "  = {}", suggestion)?;
         }
         
+        if let Some(note) = &self.note {
+            if self.suggestion.is_none() {
+                writeln!(f)?;
+            }
+            writeln!(f, "  = note: {}", note)?;
+        }
+        
         // 添加帮助信息
         if let Some(help) = &self.help {
             writeln!(f)?;
@@ -308,6 +753,105 @@ impl fmt::Display for CompilerError {
     }
 }
 
+/// 运行时错误的种类，在产生错误的地方指定。catch 得到的错误值中的 `kind` 字段是它的名称
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    Syntax,       // 执行之前发现的语法和名称错误，只有导入的文件中的错误能被 catch
+    Type,
+    Conversion,
+    Index,
+    Key,
+    Arithmetic,
+    Math,
+    Argument,
+    Match,
+    Name,
+    Import,
+    Constant,
+    Thrown,         // throw 抛出的不是错误值的值
+    Custom(String), // 重新抛出的错误值中的种类不是以上任何一种
+    Runtime,
+}
+
+impl ErrorKind {
+    pub fn name(&self) -> &str {
+        match self {
+            ErrorKind::Syntax => "SyntaxError",
+            ErrorKind::Type => "TypeError",
+            ErrorKind::Conversion => "ConversionError",
+            ErrorKind::Index => "IndexError",
+            ErrorKind::Key => "KeyError",
+            ErrorKind::Arithmetic => "ArithmeticError",
+            ErrorKind::Math => "MathError",
+            ErrorKind::Argument => "ArgumentError",
+            ErrorKind::Match => "MatchError",
+            ErrorKind::Name => "NameError",
+            ErrorKind::Import => "ImportError",
+            ErrorKind::Constant => "ConstantError",
+            ErrorKind::Thrown => "Error",
+            ErrorKind::Custom(name) => name,
+            ErrorKind::Runtime => "RuntimeError",
+        }
+    }
+    
+    /// 由名称得到种类，用于重新抛出 catch 得到的错误值
+    pub fn from_name(name: &str) -> Self {
+        const KINDS: [ErrorKind; 14] = [
+            ErrorKind::Syntax, ErrorKind::Type, ErrorKind::Conversion, ErrorKind::Index, ErrorKind::Key,
+            ErrorKind::Arithmetic, ErrorKind::Math, ErrorKind::Argument, ErrorKind::Match, ErrorKind::Name,
+            ErrorKind::Import, ErrorKind::Constant, ErrorKind::Thrown, ErrorKind::Runtime,
+        ];
+        KINDS.into_iter()
+            .find(|kind| kind.name() == name)
+            .unwrap_or_else(|| ErrorKind::Custom(name.to_string()))
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// 解释执行期间产生的错误，携带完整的诊断信息，由调用方决定如何处理。
+/// `kind` 是错误的种类，程序中 catch 到的错误值通过它区分不同的错误
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub diagnostic: Box<CompilerError>,
+    pub kind: ErrorKind,
+    pub additional: Vec<CompilerError>, // 执行之前一次报告的其他错误，按源代码中的顺序排列
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind, diagnostic: CompilerError) -> Self {
+        Self {
+            kind,
+            diagnostic: Box::new(diagnostic),
+            additional: Vec::new(),
+        }
+    }
+    
+    /// 把多个错误合并为一个，第一个错误作为主要的诊断信息
+    pub fn from_errors(kind: ErrorKind, mut errors: Vec<CompilerError>) -> Self {
+        let first = errors.remove(0);
+        Self { additional: errors, ..Self::new(kind, first) }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.additional.is_empty() {
+            return write!(f, "{}", self.diagnostic);
+        }
+        // 多个错误之间用一个空行分隔
+        write!(f, "{}", self.diagnostic.to_string().trim_end())?;
+        for error in &self.additional {
+            write!(f, "\n\n{}", error.to_string().trim_end())?;
+        }
+        write!(f, "\n\n{} errors found before execution", self.additional.len() + 1)
+    }
+}
+
     // 便捷函数用于创建常见错误
 pub fn create_undefined_identifier_error(name: &str, line: usize, column: usize, file_path: &str, source_line: &str) -> CompilerError {
     use error_messages::*;
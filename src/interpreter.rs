@@ -1,11 +1,23 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::env;
-use crate::ast::{ASTNode, Type};
-use crate::error::{error_messages, create_undefined_identifier_error, CompilerError};
+use crate::ast::{ASTNode, Accessor, Binding, Param, Pattern, Position, Type, Variant};
+use crate::bytecode::Chunk;
+use crate::compiler::Compiler;
+use crate::resolver::Resolver;
+use crate::checker::Checker;
+use crate::environment::{Env, Environment, ScopeKind, Variable};
+use crate::error::{error_messages, create_undefined_identifier_error, CompilerError, ErrorKind, RuntimeError};
 
-#[derive(Clone, Debug)]
+mod vm;
+pub(crate) mod builtins;
+
+use builtins::Builtin;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Int(i64),
     Str(String),
@@ -14,10 +26,89 @@ pub enum Value {
     Double(f64),
     Array(Vec<Value>),     // 固定长度的同类型数组
     List(Vec<Value>),      // 动态长度的异类型列表
+    Map(Vec<(Value, Value)>), // 按插入顺序保存的键值对，键为 str、int 或 bool
+    Struct(Rc<StructType>, Vec<Value>), // 结构体类型和按声明顺序排列的字段值
+    Enum(Rc<EnumType>, usize, Vec<Value>), // 枚举类型、成员的下标和成员携带的值
+    Function(Rc<Closure>),  // 具名函数或 lambda
+}
+
+/// 用 struct 声明的结构体类型
+#[derive(Debug)]
+pub struct StructType {
+    pub name: String,
+    pub fields: Vec<(String, Option<Type>)>, // 字段名和声明的类型，没有类型的字段可以保存任意值
+    pub(crate) caught: Option<RuntimeError>, // catch 得到的错误值的类型保存原来的错误，重新抛出时使用
+}
+
+/// 结构体类型按名称和字段比较，错误值保存的原来的错误不影响比较
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.fields == other.fields
+    }
+}
+
+impl StructType {
+    fn field_index(&self, field: &str) -> Option<usize> {
+        self.fields.iter().position(|(name, _)| name == field)
+    }
+    
+    /// 以逗号分隔的字段名，用于诊断信息
+    fn field_names(&self) -> String {
+        let names: Vec<&str> = self.fields.iter().map(|(name, _)| name.as_str()).collect();
+        names.join(", ")
+    }
+}
+
+/// 用 enum 声明的枚举类型
+#[derive(Debug, PartialEq)]
+pub struct EnumType {
+    pub name: String,
+    pub variants: Vec<Variant>, // 成员名和成员携带的字段，字段的写法与结构体相同
+}
+
+impl EnumType {
+    fn variant_index(&self, variant: &str) -> Option<usize> {
+        self.variants.iter().position(|(name, _)| name == variant)
+    }
+    
+    /// 以逗号分隔的成员名，用于诊断信息
+    fn variant_names(&self) -> String {
+        let names: Vec<&str> = self.variants.iter().map(|(name, _)| name.as_str()).collect();
+        names.join(", ")
+    }
+}
+
+/// 函数值：函数以及调用时函数作用域的外层作用域。具名函数的外层是全局作用域，
+/// lambda 的外层是定义它的位置的作用域，所以 lambda 可以读写那里可见的局部变量
+pub struct Closure {
+    pub name: Option<Rc<str>>, // lambda 没有名称
+    pub(crate) function: Rc<Function>,
+    pub(crate) env: Env,
+}
+
+impl Closure {
+    /// 诊断信息中使用的函数名
+    fn name(&self) -> Rc<str> {
+        self.name.clone().unwrap_or_else(|| "<lambda>".into())
+    }
+}
+
+// 作用域中可能保存着这个函数值本身，所以只显示函数名
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+/// 函数值只与自身相等
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
 }
 
 impl Value {
-    fn get_type_name(&self) -> String {
+    pub fn get_type_name(&self) -> String {
         match self {
             Value::Int(_) => "int".to_string(),
             Value::Str(_) => "str".to_string(),
@@ -26,28 +117,14 @@ impl Value {
             Value::Double(_) => "double".to_string(),
             Value::Array(_) => "array".to_string(),
             Value::List(_) => "list".to_string(),
+            Value::Map(_) => "map".to_string(),
+            Value::Struct(struct_type, _) => struct_type.name.clone(),
+            Value::Enum(enum_type, ..) => enum_type.name.clone(),
+            Value::Function(_) => "function".to_string(),
         }
     }
     
-    fn to_string(&self) -> String {
-        match self {
-            Value::Int(n) => n.to_string(),
-            Value::Str(s) => s.clone(),
-            Value::Bool(b) => b.to_string(),
-            Value::Float(f) => f.to_string(),
-            Value::Double(d) => d.to_string(),
-            Value::Array(arr) => {
-                let elements: Vec<String> = arr.iter().map(|v| v.to_string()).collect();
-                format!("[{}]", elements.join(", "))
-            },
-            Value::List(lst) => {
-                let elements: Vec<String> = lst.iter().map(|v| v.to_string()).collect();
-                format!("[{}]", elements.join(", "))
-            },
-        }
-    }
-    
-    fn to_f64(&self) -> f64 {
+    pub fn to_f64(&self) -> f64 {
         match self {
             Value::Int(n) => *n as f64,
             Value::Str(s) => s.parse().unwrap_or(0.0),
@@ -56,10 +133,19 @@ impl Value {
             Value::Double(d) => *d,
             Value::Array(_) => 0.0,  // 数组转为数字时返回0
             Value::List(_) => 0.0,   // 列表转为数字时返回0
+            Value::Map(_) => 0.0,
+            Value::Struct(..) => 0.0,
+            Value::Enum(..) => 0.0,
+            Value::Function(_) => 0.0,
         }
     }
     
-    fn get_type(&self) -> Type {
+    /// 作为条件时的真假，与 if/while 的判断方式一致：非零即为真
+    pub fn is_truthy(&self) -> bool {
+        self.to_f64() != 0.0
+    }
+    
+    pub fn get_type(&self) -> Type {
         match self {
             Value::Int(_) => Type::Int,
             Value::Str(_) => Type::Str,
@@ -68,10 +154,14 @@ impl Value {
             Value::Double(_) => Type::Double,
             Value::Array(_) => Type::Any,  // 数组类型
             Value::List(_) => Type::Any,   // 列表类型
+            Value::Map(_) => Type::Any,
+            Value::Struct(..) => Type::Any,
+            Value::Enum(..) => Type::Any,
+            Value::Function(_) => Type::Any,
         }
     }
     
-    fn convert_to(&self, target_type: &Type) -> Result<Value, String> {
+    pub fn convert_to(&self, target_type: &Type) -> Result<Value, String> {
         match (self, target_type) {
             // Same type - no conversion needed
             (Value::Int(_), Type::Int) => Ok(self.clone()),
@@ -81,6 +171,10 @@ impl Value {
             (Value::Double(_), Type::Double) => Ok(self.clone()),
             (Value::Array(_), Type::Any) => Ok(self.clone()),  // 数组保持不变
             (Value::List(_), Type::Any) => Ok(self.clone()),   // 列表保持不变
+            (Value::Map(_), Type::Any) => Ok(self.clone()),
+            (Value::Struct(..), Type::Any) => Ok(self.clone()),
+            (Value::Enum(..), Type::Any) => Ok(self.clone()),
+            (Value::Function(_), Type::Any) => Ok(self.clone()),
             
             // Int to other types
             (Value::Int(n), Type::Float) => Ok(Value::Float(*n as f32)),
@@ -130,9 +224,13 @@ impl Value {
             (Value::Bool(b), Type::Double) => Ok(Value::Double(if *b { 1.0 } else { 0.0 })),
             (Value::Bool(b), Type::Str) => Ok(Value::Str(b.to_string())),
             
-            // Array/List to String
+            // Array/List/Map/Struct/Enum/Function to String
             (Value::Array(_), Type::Str) => Ok(Value::Str(self.to_string())),
             (Value::List(_), Type::Str) => Ok(Value::Str(self.to_string())),
+            (Value::Map(_), Type::Str) => Ok(Value::Str(self.to_string())),
+            (Value::Struct(..), Type::Str) => Ok(Value::Str(self.to_string())),
+            (Value::Enum(..), Type::Str) => Ok(Value::Str(self.to_string())),
+            (Value::Function(_), Type::Str) => Ok(Value::Str(self.to_string())),
             
             // Int to Int (already handled above, but keeping for completeness)
             _ => Err(format!("Cannot convert {:?} to {:?}", self.get_type(), target_type)),
@@ -140,77 +238,610 @@ impl Value {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Clone)]
-pub struct Function {
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Str(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Float(x) => write!(f, "{}", x),
+            Value::Double(d) => write!(f, "{}", d),
+            Value::Array(arr) => {
+                let elements: Vec<String> = arr.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            },
+            Value::List(lst) => {
+                let elements: Vec<String> = lst.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", elements.join(", "))
+            },
+            Value::Map(entries) => {
+                let elements: Vec<String> = entries.iter().map(|(k, v)| format!("{}: {}", k, v)).collect();
+                write!(f, "{{{}}}", elements.join(", "))
+            },
+            Value::Struct(struct_type, values) if values.is_empty() => write!(f, "{} {{}}", struct_type.name),
+            Value::Struct(struct_type, values) => {
+                let fields: Vec<String> = struct_type.fields.iter().zip(values)
+                    .map(|((name, _), value)| format!("{}: {}", name, value))
+                    .collect();
+                write!(f, "{} {{ {} }}", struct_type.name, fields.join(", "))
+            },
+            Value::Enum(enum_type, index, values) if values.is_empty() => {
+                write!(f, "{}::{}", enum_type.name, enum_type.variants[*index].0)
+            },
+            Value::Enum(enum_type, index, values) => {
+                let elements: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}::{}({})", enum_type.name, enum_type.variants[*index].0, elements.join(", "))
+            },
+            Value::Function(closure) => match &closure.name {
+                Some(name) => write!(f, "<func {}>", name),
+                None => write!(f, "<lambda>"),
+            },
+        }
+    }
+}
+
+/// 比较两个值是否相等，类型无法比较时返回 `None`
+fn values_equal(left: &Value, right: &Value) -> Option<bool> {
+    match (left, right) {
+        (Value::Int(a), Value::Int(b)) => Some(a == b),
+        (Value::Int(_) | Value::Float(_) | Value::Double(_),
+         Value::Int(_) | Value::Float(_) | Value::Double(_)) => Some(left.to_f64() == right.to_f64()),
+        (Value::Str(a), Value::Str(b)) => Some(a == b),
+        (Value::Bool(a), Value::Bool(b)) => Some(a == b),
+        // 同一个枚举的值可以比较，成员和携带的值都相等时才相等
+        (Value::Enum(a, i, x), Value::Enum(b, j, y)) if a == b => {
+            Some(i == j && x.iter().zip(y).all(|(x, y)| values_equal(x, y) == Some(true)))
+        }
+        _ => None,
+    }
+}
+
+/// 解析器产生的二元运算符。`&&` 和 `||` 需要短路求值，由执行引擎单独处理，不在其中
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    IntDiv,
+    Pow,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    Range, // `..`，只在 for 循环中表示区间
+}
+
+impl BinaryOp {
+    /// 语法树中运算符的写法对应的运算，`&&` 和 `||` 返回 `None`
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        let op = match symbol {
+            "+" => BinaryOp::Add,
+            "-" => BinaryOp::Sub,
+            "*" => BinaryOp::Mul,
+            "/" => BinaryOp::Div,
+            "%" => BinaryOp::Rem,
+            "~/" => BinaryOp::IntDiv,
+            "**" => BinaryOp::Pow,
+            "<" => BinaryOp::Less,
+            ">" => BinaryOp::Greater,
+            "<=" => BinaryOp::LessEqual,
+            ">=" => BinaryOp::GreaterEqual,
+            "==" => BinaryOp::Equal,
+            "!=" => BinaryOp::NotEqual,
+            ".." => BinaryOp::Range,
+            _ => return None,
+        };
+        Some(op)
+    }
+    
+    /// 诊断信息中显示的运算符
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::IntDiv => "~/",
+            BinaryOp::Pow => "**",
+            BinaryOp::Less => "<",
+            BinaryOp::Greater => ">",
+            BinaryOp::LessEqual => "<=",
+            BinaryOp::GreaterEqual => ">=",
+            BinaryOp::Equal => "==",
+            BinaryOp::NotEqual => "!=",
+            BinaryOp::Range => "..",
+        }
+    }
+}
+
+/// 两个整数之间的运算，结果溢出时返回 `None`。
+/// `/` 和 `~/` 都向零取整，负指数的乘方结果为 double；除数为 0 和 0 的负数次幂由调用方检查。
+fn integer_operation(op: BinaryOp, left: i64, right: i64) -> Option<Value> {
+    let result = match op {
+        BinaryOp::Add => Value::Int(left.checked_add(right)?),
+        BinaryOp::Sub => Value::Int(left.checked_sub(right)?),
+        BinaryOp::Mul => Value::Int(left.checked_mul(right)?),
+        // int 之间的除法与 ~/ 相同，向零取整
+        BinaryOp::Div | BinaryOp::IntDiv => Value::Int(left.checked_div(right)?),
+        BinaryOp::Rem => Value::Int(left.checked_rem(right)?),
+        BinaryOp::Pow if right < 0 => Value::Double((left as f64).powf(right as f64)),
+        BinaryOp::Pow => Value::Int(left.checked_pow(u32::try_from(right).ok()?)?),
+        BinaryOp::Less => Value::Bool(left < right),
+        BinaryOp::Greater => Value::Bool(left > right),
+        BinaryOp::LessEqual => Value::Bool(left <= right),
+        BinaryOp::GreaterEqual => Value::Bool(left >= right),
+        BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Range => unreachable!("apply_binary_op handles {} itself", op.symbol()),
+    };
+    Some(result)
+}
+
+/// 浮点数之间的运算，算术结果由 `make` 包装为 float 或 double
+fn real_operation(op: BinaryOp, left: f64, right: f64, make: fn(f64) -> Value) -> Value {
+    match op {
+        BinaryOp::Add => make(left + right),
+        BinaryOp::Sub => make(left - right),
+        BinaryOp::Mul => make(left * right),
+        BinaryOp::Div => make(left / right),
+        BinaryOp::Rem => make(left % right),
+        BinaryOp::IntDiv => make((left / right).trunc()),
+        BinaryOp::Pow => make(left.powf(right)),
+        BinaryOp::Less => Value::Bool(left < right),
+        BinaryOp::Greater => Value::Bool(left > right),
+        BinaryOp::LessEqual => Value::Bool(left <= right),
+        BinaryOp::GreaterEqual => Value::Bool(left >= right),
+        BinaryOp::Equal | BinaryOp::NotEqual | BinaryOp::Range => unreachable!("apply_binary_op handles {} itself", op.symbol()),
+    }
+}
+
+/// 可以作为映射键的值
+fn is_map_key(value: &Value) -> bool {
+    matches!(value, Value::Str(_) | Value::Int(_) | Value::Bool(_))
+}
+
+/// 诊断信息中显示的映射键，字符串键带引号以便与数字键区分
+fn describe_map_key(key: &Value) -> String {
+    match key {
+        Value::Str(s) => format!("\"{}\"", s),
+        other => other.to_string(),
+    }
+}
+
+/// 插入或更新映射中的一项，新键添加在末尾
+fn map_insert(entries: &mut Vec<(Value, Value)>, key: Value, value: Value) {
+    match entries.iter_mut().find(|(existing, _)| *existing == key) {
+        Some((_, slot)) => *slot = value,
+        None => entries.push((key, value)),
+    }
+}
+
+/// 赋值路径中已求值的一层访问：下标的值或字段名
+pub(crate) enum PathStep<'a> {
+    Index(Value),
+    Field(&'a str),
+}
+
+/// for 循环依次交给循环变量的值：区间中的每个整数，或映射中的每个键
+enum ForItems {
+    Range(std::ops::Range<i64>),
+    Items(std::vec::IntoIter<Value>),
+}
+
+impl ForItems {
+    /// `start..end`，起点和终点都必须是 int，否则返回不是 int 的那个值
+    fn range(start: Value, end: Value) -> Result<Self, Value> {
+        match (start, end) {
+            (Value::Int(start), Value::Int(end)) => Ok(ForItems::Range(start..end)),
+            (Value::Int(_), other) | (other, _) => Err(other),
+        }
+    }
+    
+    /// `for x in value`：数组和列表遍历开始时的所有元素，映射遍历所有键，int 视为 `0..value`，其他值返回错误
+    fn over(value: Value) -> Result<Self, Value> {
+        match value {
+            Value::Array(items) | Value::List(items) => Ok(ForItems::Items(items.into_iter())),
+            Value::Map(entries) => {
+                let keys: Vec<Value> = entries.into_iter().map(|(key, _)| key).collect();
+                Ok(ForItems::Items(keys.into_iter()))
+            }
+            other => Self::range(Value::Int(0), other),
+        }
+    }
+}
+
+impl Iterator for ForItems {
+    type Item = Value;
+    
+    fn next(&mut self) -> Option<Value> {
+        match self {
+            ForItems::Range(range) => range.next().map(Value::Int),
+            ForItems::Items(items) => items.next(),
+        }
+    }
+}
+
+/// 计算表达式时是否可能调用函数。没有函数调用的表达式不会修改任何变量
+pub(crate) fn may_call(node: &ASTNode) -> bool {
+    match node {
+        ASTNode::Integer(_) | ASTNode::Number(_) | ASTNode::String(_) | ASTNode::Bool(_) | ASTNode::Identifier(..) => false,
+        ASTNode::BinaryOp(left, _, right, _) | ASTNode::IndexAccess(left, right, _) => may_call(left) || may_call(right),
+        ASTNode::UnaryOp(_, expr, _) | ASTNode::FieldAccess(expr, _, _) | ASTNode::TypeConversion(_, expr, _) => may_call(expr),
+        _ => true,
+    }
+}
+
+/// catch 得到的错误值的字段：信息、种类、行和列
+fn error_fields(error: &RuntimeError) -> Vec<Value> {
+    vec![
+        Value::Str(error.diagnostic.message.clone()),
+        Value::Str(error.kind.name().to_string()),
+        Value::Int(error.diagnostic.line as i64),
+        Value::Int(error.diagnostic.column as i64),
+    ]
+}
+
+/// 模式中字面量的值，语法分析保证模式中只有字面量节点
+fn literal_value(node: &ASTNode) -> Value {
+    match node {
+        ASTNode::Integer(n) => Value::Int(*n),
+        ASTNode::Number(n) => Value::Double(*n),
+        ASTNode::String(s) => Value::Str(s.clone()),
+        ASTNode::Bool(b) => Value::Bool(*b),
+        _ => unreachable!("patterns only contain literals"),
+    }
+}
+
+/// 值是否匹配 match 分支的模式。字面量按 == 比较，范围不包含终点
+fn pattern_matches(pattern: &Pattern, value: &Value) -> bool {
+    match pattern {
+        Pattern::Wildcard => true,
+        Pattern::Literal(literal) => values_equal(&literal_value(literal), value) == Some(true),
+        Pattern::Range(start, end) => {
+            matches!(value, Value::Int(_) | Value::Float(_) | Value::Double(_))
+                && literal_value(start).to_f64() <= value.to_f64()
+                && value.to_f64() < literal_value(end).to_f64()
+        }
+        Pattern::Variant(enum_name, variant, ..) => match value {
+            Value::Enum(enum_type, index, _) => enum_type.name == *enum_name && enum_type.variants[*index].0 == *variant,
+            _ => false,
+        },
+    }
+}
+
+/// 把枚举成员携带的值放入分支作用域中模式绑定的槽位，名称为 _ 的值被忽略
+fn bind_pattern(env: &Env, pattern: &Pattern, value: Value) {
+    if let (Pattern::Variant(_, _, bindings, _), Value::Enum(_, _, values)) = (pattern, value) {
+        for ((name, binding), value) in bindings.iter().zip(values) {
+            if name != "_" && let Binding::Local { slot, .. } = binding {
+                env.borrow_mut().define_slot(*slot, Variable::new(value, None));
+            }
+        }
+    }
+}
+
+/// 数组元素在未显式初始化时使用的默认值
+fn default_value(element_type: &Type) -> Value {
+    match element_type {
+        Type::Int => Value::Int(0),
+        Type::Float => Value::Float(0.0),
+        Type::Double => Value::Double(0.0),
+        Type::Bool => Value::Bool(false),
+        Type::Str => Value::Str("".to_string()),
+        Type::Any => Value::Int(0), // Default to 0 for any type
+    }
+}
+
+/// 把值转换为数组中 `slot` 位置的元素。多维数组的一行只能替换为长度相同的数组或列表，其中的元素逐个转换
+fn conform_element(slot: &Value, value: Value, element_type: &Type) -> Option<Value> {
+    match (slot, value) {
+        (Value::Array(row), Value::Array(items) | Value::List(items)) if items.len() == row.len() => row
+            .iter()
+            .zip(items)
+            .map(|(slot, item)| conform_element(slot, item, element_type))
+            .collect::<Option<Vec<_>>>()
+            .map(Value::Array),
+        (Value::Array(_), _) => None,
+        (_, value) => value.convert_to(element_type).ok(),
+    }
+}
+
+/// 数组中 `slot` 位置的元素类型，多维数组的一行写作 int[4]
+fn slot_type_name(slot: &Value, element_type: &Type) -> String {
+    let mut name = element_type.to_string();
+    let mut current = slot;
+    while let Value::Array(row) = current {
+        name.push_str(&format!("[{}]", row.len()));
+        match row.first() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+    name
+}
+
+pub(crate) struct Function {
     pub params: Vec<String>,
-    pub body: Vec<ASTNode>,
-    pub is_expr: bool,        // 标记是否为表达式函数
-    pub param_types: Vec<String>,  // 参数类型信息
+    pub body: FunctionBody,
+    pub signature: Signature,
+    pub(crate) source: Rc<SourceFile>, // 声明函数的文件，函数体中的错误引用它
+}
+
+/// 一个源文件的路径和源代码行，诊断信息引用它们
+#[derive(Debug, Default)]
+pub(crate) struct SourceFile {
+    pub path: String,
+    pub lines: Vec<String>,
+}
+
+impl SourceFile {
+    pub fn new(path: String, lines: Vec<String>) -> Rc<Self> {
+        Rc::new(Self { path, lines })
+    }
+}
+
+/// 函数声明的参数类型和返回类型。调用时参数和返回值转换为声明的类型，没有声明类型的不做转换
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Signature {
+    pub param_types: Vec<Option<Type>>,
+    pub return_type: Option<Type>,
+    pub origin: String, // 函数的声明位置 `文件:行:列`，用于类型错误的提示
+}
+
+impl Signature {
+    pub fn new(params: &[Param], return_type: Option<Type>, origin: String) -> Self {
+        Self {
+            param_types: params.iter().map(|(_, param_type)| param_type.clone()).collect(),
+            return_type,
+            origin,
+        }
+    }
+}
+
+/// 函数体：由定义它的执行引擎决定是语法树还是字节码
+pub(crate) enum FunctionBody {
+    Ast(Rc<[ASTNode]>),
+    Bytecode(Rc<Chunk>),
+}
+
+/// 执行引擎：直接遍历语法树，或先编译为字节码再由虚拟机执行。两者的语义和诊断信息相同。
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Engine {
+    #[default]
+    TreeWalker,
+    Vm,
+}
+
+/// 原生函数的实现：接收已求值的参数，返回结果或错误消息
+pub type NativeFn = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+/// 由宿主程序用Rust实现、可在ECL脚本中调用的函数
+#[derive(Clone)]
+pub(crate) struct NativeFunction {
+    pub arity: usize,
+    pub func: NativeFn,
+}
+
+/// 语句执行后的控制流信号，break/continue 沿着语句块向外传递，直到遇到最内层的循环；
+/// return 穿过所有的语句块和循环，直到函数体
+#[derive(Debug, Clone, PartialEq)]
+enum ControlFlow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
 }
 
+/// 同时执行的函数调用的最大层数，超过时报告错误，而不是耗尽宿主的调用栈
+const MAX_CALL_DEPTH: usize = 3000;
+
 pub struct Interpreter {
-    variables: HashMap<String, (Value, Option<Type>)>,
-    functions: HashMap<String, Function>,
+    globals: Env,
+    env: Env,                        // 当前作用域
+    engine: Engine,
+    functions: HashMap<String, Rc<Function>>,
+    structs: HashMap<String, Rc<StructType>>,
+    enums: HashMap<String, Rc<EnumType>>,
+    natives: HashMap<String, NativeFunction>,
     output_buffer: String,
-    file_path: String,
-    source_lines: Vec<String>,
+    source: Rc<SourceFile>,          // 当前执行的代码所在的文件
+    constant_origins: HashMap<String, String>, // 程序中声明的全局常量的声明位置，内置常量不在其中
+    error_type: Rc<StructType>,      // catch 到的错误值的结构体类型的名称和字段
+    call_depth: usize,               // 正在执行的函数调用的层数，包括虚拟机的调用帧
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+        let globals = Environment::new_global();
+        for (name, value) in builtins::CONSTANTS {
+            globals.borrow_mut().define(name, Variable::constant(Value::Double(value), Some(Type::Double)));
+        }
         Self {
-            variables: HashMap::new(),
+            env: Rc::clone(&globals),
+            globals,
+            engine: Engine::default(),
             functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            natives: HashMap::new(),
             output_buffer: String::new(),
-            file_path: String::from("<unknown>"),
-            source_lines: Vec::new(),
+            source: SourceFile::new(String::from("<unknown>"), Vec::new()),
+            constant_origins: HashMap::new(),
+            error_type: Rc::new(StructType {
+                name: "Error".to_string(),
+                fields: vec![
+                    ("message".to_string(), Some(Type::Str)),
+                    ("kind".to_string(), Some(Type::Str)),
+                    ("line".to_string(), Some(Type::Int)),
+                    ("column".to_string(), Some(Type::Int)),
+                ],
+                caught: None,
+            }),
+            call_depth: 0,
         }
     }
     
     pub fn with_source(mut self, file_path: String, source_lines: Vec<String>) -> Self {
-        self.file_path = file_path;
-        self.source_lines = source_lines;
+        self.set_source(file_path, source_lines);
+        self
+    }
+    
+    pub fn with_engine(mut self, engine: Engine) -> Self {
+        self.engine = engine;
         self
     }
     
-    pub fn run(&mut self, input: &str) {
+    /// 选择之后执行代码使用的引擎，已定义的函数仍由定义它们的引擎执行
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+    
+    /// 设置后续诊断信息使用的文件路径和源代码行，已定义的变量和函数保持不变
+    pub fn set_source(&mut self, file_path: String, source_lines: Vec<String>) {
+        self.source = SourceFile::new(file_path, source_lines);
+    }
+    
+    /// 读取一个全局变量的值
+    pub fn get_global(&self, name: &str) -> Option<Value> {
+        self.globals.borrow().get(name).map(|variable| variable.value.clone())
+    }
+    
+    /// 定义或覆盖一个无类型的全局变量，供宿主程序向脚本传入数据
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        self.globals.borrow_mut().define(name, Variable::new(value, None));
+    }
+    
+    /// 注册一个原生函数，脚本中可以用普通调用语法或表达式函数的中缀语法调用它。
+    /// 返回的 `Err` 消息会作为指向调用位置的诊断信息报告。
+    pub fn register_native<F>(&mut self, name: &str, arity: usize, func: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        let native = NativeFunction {
+            arity,
+            func: Rc::new(func),
+        };
+        self.natives.insert(name.to_string(), native);
+    }
+    
+    /// 从宿主程序调用脚本中定义的 `func`/`expr` 函数或已注册的原生函数，返回函数的返回值。
+    /// 函数没有执行 return 时返回 0。
+    pub fn call(&mut self, name: &str, args: &[Value]) -> Result<Value, RuntimeError> {
+        // 宿主调用没有对应的源代码，行号为 0 的诊断信息不显示位置
+        let pos = Position::new(0, 0);
+        let result = self.call_function(name, args.to_vec(), &pos);
+        self.flush_output();
+        result
+    }
+    
+    /// 输出 print 语句缓冲的内容
+    fn flush_output(&mut self) {
+        if !self.output_buffer.is_empty() {
+            println!("{}", self.output_buffer);
+            self.output_buffer.clear();
+        }
+    }
+    
+    /// 解析并执行一段源代码，出错时返回诊断信息，由调用方决定如何处理
+    pub fn run(&mut self, input: &str) -> Result<(), RuntimeError> {
         use crate::lexer::Lexer;
         use crate::parser::Parser;
         
         let lexer = Lexer::new(input);
         let mut parser = Parser::new(lexer);
         
-        // 如果没有设置源文件信息，从输入中提取
-        if self.source_lines.is_empty() {
-            self.source_lines = input.lines().map(|s| s.to_string()).collect();
-        }
+        // 诊断信息总是引用本次执行的源代码
+        self.source = SourceFile::new(self.source.path.clone(), input.lines().map(|s| s.to_string()).collect());
         
-        match parser.parse(&self.file_path, &self.source_lines) {
-            Ok(ast) => {
-                for node in ast {
-                    self.evaluate(&node);
-                }
-                
-                if !self.output_buffer.is_empty() {
-                    println!("{}", self.output_buffer);
-                    self.output_buffer.clear();
-                }
-            }
-            Err(error) => {
-                eprintln!("{}", error);
+        let mut ast = parser.parse(&self.source.path, &self.source.lines)
+            .map_err(|error| RuntimeError::new(ErrorKind::Syntax, error))?;
+        self.resolve(&mut ast).map_err(|error| RuntimeError::new(ErrorKind::Syntax, error))?;
+        self.check(&ast)?;
+        let result = self.execute_program(&ast);
+        
+        // 无论执行是否成功，都输出已缓冲的内容
+        self.flush_output();
+        
+        result
+    }
+    
+    /// 为程序中的变量名确定绑定，在执行之前报告未定义的变量
+    #[allow(clippy::result_large_err)]
+    fn resolve(&self, ast: &mut [ASTNode]) -> Result<(), CompilerError> {
+        let mut globals = self.globals.borrow_mut();
+        let known_globals: Vec<String> = globals.names().cloned().collect();
+        let constants: Vec<(String, Option<String>)> = globals.constant_names()
+            .map(|name| (name.clone(), self.constant_origins.get(name).cloned()))
+            .collect();
+        Resolver::new(&self.source.path, &self.source.lines, known_globals, globals.global_slots())
+            .with_functions(self.functions.keys().cloned())
+            .with_constants(constants)
+            .with_enums(self.enums.values().map(|enum_type| {
+                let variants = enum_type.variants.iter().map(|(variant, fields)| (variant.clone(), fields.len())).collect();
+                (enum_type.name.clone(), variants)
+            }))
+            .resolve(ast)
+    }
+    
+    /// 在执行之前检查程序中一定会失败的类型转换和运算，一次报告所有错误
+    fn check(&self, ast: &[ASTNode]) -> Result<(), RuntimeError> {
+        Checker::new(&self.source.path, &self.source.lines)
+            .with_globals(self.globals.borrow().variables())
+            .with_functions(&self.functions)
+            .check(ast)
+            .map_err(|errors| RuntimeError::from_errors(ErrorKind::Type, errors))
+    }
+    
+    /// 使用当前的执行引擎执行一段已解析的程序
+    fn execute_program(&mut self, ast: &[ASTNode]) -> Result<(), RuntimeError> {
+        match self.engine {
+            Engine::TreeWalker => ast.iter().try_for_each(|node| self.evaluate(node).map(|_| ())),
+            Engine::Vm => {
+                let chunk = Rc::new(Compiler::compile_program(ast, &self.source));
+                self.run_chunk(&chunk).map(|_| ())
             }
         }
     }
     
-    fn evaluate(&mut self, node: &ASTNode) {
+    /// 当前文件中 `pos` 的 `文件:行:列` 形式，用于指出声明位置
+    fn location(&self, pos: &Position) -> String {
+        format!("{}:{}:{}", self.source.path, pos.line, pos.column)
+    }
+    
+    /// 使用当前文件和源代码行构造指向 `pos` 的诊断信息
+    fn error_at(&self, message: String, pos: &Position) -> CompilerError {
+        // 行号为 0 表示没有对应的源代码（例如宿主程序直接调用函数）
+        let source_line = pos.line.checked_sub(1)
+            .and_then(|index| self.source.lines.get(index))
+            .cloned()
+            .unwrap_or_default();
+        
+        CompilerError::new(
+            message,
+            pos.line,
+            pos.column,
+            self.source.path.clone(),
+            source_line,
+        )
+    }
+    
+    fn evaluate(&mut self, node: &ASTNode) -> Result<ControlFlow, RuntimeError> {
         match node {
             ASTNode::Print(expr) => {
-                let value = self.evaluate_print_expression(expr);
+                let value = self.evaluate_print_expression(expr)?;
                 self.output_buffer.push_str(&value);
             }
             ASTNode::Println(expr) => {
-                let value = self.evaluate_print_expression(expr);
+                let value = self.evaluate_print_expression(expr)?;
                 if !self.output_buffer.is_empty() {
                     self.output_buffer.push_str(&value);
                     println!("{}", self.output_buffer);
@@ -219,733 +850,938 @@ impl Interpreter {
                     println!("{}", value);
                 }
             }
-            ASTNode::Var(name, expr) => {
-                let value = self.evaluate_value(expr);
-                self.variables.insert(name.clone(), (value, None));
+            ASTNode::Var(name, binding, expr, _) => {
+                let value = self.evaluate_value(expr)?;
+                self.define_variable(name, *binding, Variable::new(value, None));
             }
-            ASTNode::TypedVar(name, var_type, expr) => {
-                let value = self.evaluate_value(expr);
-                // Type check and convert if needed
-                match value.convert_to(var_type) {
-                    Ok(converted_value) => {
-                        self.variables.insert(name.clone(), (converted_value, Some(var_type.clone())));
-                    }
-                    Err(error_msg) => {
-                        eprintln!("Type error: {}", error_msg);
-                        std::process::exit(1);
-                    }
-                }
+            ASTNode::TypedVar(name, binding, var_type, expr, pos) => {
+                let value = self.evaluate_value(expr)?;
+                self.define_typed_variable(name, *binding, var_type, value, pos)?;
             }
-            ASTNode::ArrayDecl(name, element_type, size, init_values) => {
-                // Create an array with the specified size and initialize it
-                let mut array_elements = Vec::new();
-                let size = *size; // Dereference size
-                
-                // If we have initialization values
-                if !init_values.is_empty() {
-                    // Check if we have a single value to initialize all elements
-                    if init_values.len() == 1 {
-                        let init_value = self.evaluate_value(&init_values[0]);
-                        // Convert to the specified type if needed
-                        let typed_value = match init_value.convert_to(&element_type) {
-                            Ok(v) => v,
-                            Err(e) => {
-                                eprintln!("Type error in array initialization: {}", e);
-                                std::process::exit(1);
-                            }
-                        };
-                        
-                        // Initialize all elements with this value
-                        for _ in 0..size {
-                            array_elements.push(typed_value.clone());
-                        }
-                    } else {
-                        // Initialize with provided values
-                        for i in 0..std::cmp::min(size, init_values.len()) {
-                            let init_value = self.evaluate_value(&init_values[i]);
-                            // Convert to the specified type if needed
-                            let typed_value = match init_value.convert_to(&element_type) {
-                                Ok(v) => v,
-                                Err(e) => {
-                                    eprintln!("Type error in array initialization: {}", e);
-                                    std::process::exit(1);
-                                }
-                            };
-                            array_elements.push(typed_value);
-                        }
-                        
-                        // If we have fewer values than size, fill the rest with default values
-                        if init_values.len() < size {
-                            let default_value = match element_type {
-                                Type::Int => Value::Int(0),
-                                Type::Float => Value::Float(0.0),
-                                Type::Double => Value::Double(0.0),
-                                Type::Bool => Value::Bool(false),
-                                Type::Str => Value::Str("".to_string()),
-                                Type::Any => Value::Int(0), // Default to 0 for any type
-                            };
-                            
-                            for _ in init_values.len()..size {
-                                array_elements.push(default_value.clone());
-                            }
-                        }
-                    }
-                } else {
-                    // No initialization values, initialize with default values
-                    let default_value = match element_type {
-                        Type::Int => Value::Int(0),
-                        Type::Float => Value::Float(0.0),
-                        Type::Double => Value::Double(0.0),
-                        Type::Bool => Value::Bool(false),
-                        Type::Str => Value::Str("".to_string()),
-                        Type::Any => Value::Int(0), // Default to 0 for any type
-                    };
-                    
-                    for _ in 0..size {
-                        array_elements.push(default_value.clone());
-                    }
+            ASTNode::ArrayDecl(name, binding, element_type, sizes, init_values, pos) => {
+                // 只有一个初始值时用它填充整个数组，否则最多使用最外层长度个初始值
+                let count = if init_values.len() == 1 { 1 } else { init_values.len().min(sizes[0]) };
+                let mut values = Vec::with_capacity(count);
+                for init in init_values.iter().take(count) {
+                    values.push(self.evaluate_value(init)?);
                 }
-                
-                // Store the array with its element type as the variable type
-                self.variables.insert(name.clone(), (Value::Array(array_elements), Some(element_type.clone())));
+                self.define_array(name, *binding, element_type, sizes, values, pos)?;
+            }
+            ASTNode::ConstDecl(name, binding, const_type, expr, pos) => {
+                let value = self.evaluate_value(expr)?;
+                self.define_constant(name, *binding, const_type.as_ref(), value, pos)?;
             }
-            ASTNode::ListDecl(name, init_values) => {
+            ASTNode::ListDecl(name, binding, init_values, _) => {
                 // Create a list with the provided initialization values
                 let mut list_elements = Vec::new();
                 
                 for init_value in init_values {
-                    let value = self.evaluate_value(init_value);
+                    let value = self.evaluate_value(init_value)?;
                     list_elements.push(value);
                 }
                 
-                self.variables.insert(name.clone(), (Value::List(list_elements), None));
+                self.define_variable(name, *binding, Variable::new(Value::List(list_elements), None));
             }
-            ASTNode::Assign(name, expr) => {
-                let new_value = self.evaluate_value(expr);
-                
-                // Check if variable exists and has a type
-                if let Some((_, var_type)) = self.variables.get(name) {
-                    if let Some(expected_type) = var_type {
-                        // Type check and convert if needed
-                        match new_value.convert_to(expected_type) {
-                            Ok(converted_value) => {
-                                self.variables.insert(name.clone(), (converted_value, Some(expected_type.clone())));
-                            }
-                            Err(error_msg) => {
-                                eprintln!("Type error in assignment: {}", error_msg);
-                                std::process::exit(1);
-                            }
-                        }
-                    } else {
-                        // Untyped variable, just assign
-                        self.variables.insert(name.clone(), (new_value, None));
-                    }
-                } else {
-                    // Variable doesn't exist, create it without type
-                    self.variables.insert(name.clone(), (new_value, None));
-                }
+            ASTNode::Assign(name, binding, expr, pos) => {
+                let new_value = self.evaluate_value(expr)?;
+                self.assign_variable(name, *binding, new_value, pos)?;
             }
-            ASTNode::IndexAssign(array_expr, index_expr, value_expr, pos) => {
-                // Handle array/list index assignment: array[index] = value
-                let array_identifier = match array_expr.as_ref() {
-                    ASTNode::Identifier(name, _) => name,
-                    _ => return, // Not a valid array identifier
-                };
-                
-                let index_val = self.evaluate_value(index_expr);
-                let value_to_assign = self.evaluate_value(value_expr);
-                let index = index_val.to_f64() as usize;
-                
-                // Get the current array/list from variables
-                if let Some((current_value, var_type)) = self.variables.get(array_identifier).cloned() {
-                    let new_value = match &current_value {
-                        Value::Array(arr) => {
-                            if index < arr.len() {
-                                // For arrays, check type compatibility based on the variable's type
-                                if let Some(expected_type) = &var_type {
-                                    // For array variables, the type is the element type
-                                    match value_to_assign.convert_to(expected_type) {
-                                        Ok(converted_value) => {
-                                            let mut new_arr = arr.clone();
-                                            new_arr[index] = converted_value;
-                                            Value::Array(new_arr)
-                                        }
-                                        Err(_) => {
-                                            // Create a proper compiler error with full formatting
-                                            let error_msg = error_messages::array_type_error(
-                                                &expected_type.to_string(), 
-                                                &value_to_assign.get_type_name()
-                                            );
-                                            
-                                            let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                                                .unwrap_or(&String::new()).clone();
-                                            
-                                            let compiler_error = CompilerError::new(
-                                                error_msg,
-                                                pos.line,
-                                                pos.column,
-                                                self.file_path.clone(),
-                                                source_line,
-                                            )
-                                            .with_help(error_messages::help_array_assignment())
-                                            .with_example(error_messages::example_array_assignment());
-                                            
-                                            eprintln!("{}", compiler_error);
-                                            std::process::exit(1);
-                                        }
-                                    }
-                                } else {
-                                    // If no type info, just assign
-                                    let mut new_arr = arr.clone();
-                                    new_arr[index] = value_to_assign;
-                                    Value::Array(new_arr)
-                                }
-                            } else {
-                                // Index out of bounds - ignore or handle error
-                                current_value
-                            }
-                        }
-                        Value::List(lst) => {
-                            if index < lst.len() {
-                                let mut new_lst = lst.clone();
-                                new_lst[index] = value_to_assign;
-                                Value::List(new_lst)
-                            } else {
-                                // Index out of bounds - ignore or handle error
-                                current_value
-                            }
-                        }
-                        _ => {
-                            // Not an array or list - can't do index assignment
-                            current_value
-                        }
-                    };
-                    
-                    // Update the variable with the modified array/list
-                    self.variables.insert(array_identifier.clone(), (new_value, var_type));
+            ASTNode::PathAssign(name, binding, path, value_expr, pos) => {
+                // 先由外向内计算下标，再计算赋给元素或字段的值
+                let mut steps = Vec::with_capacity(path.len());
+                for accessor in path {
+                    steps.push(match accessor {
+                        Accessor::Index(index_expr) => PathStep::Index(self.evaluate_value(index_expr)?),
+                        Accessor::Field(field) => PathStep::Field(field),
+                    });
                 }
+                let value = self.evaluate_value(value_expr)?;
+                self.assign_path(name, *binding, &steps, value, pos)?;
             }
-            ASTNode::For(var, range_expr, body) => {
-                // 解析range表达式，支持 range(start, end) 或 start..end 格式
-                if let ASTNode::BinaryOp(left, op, right, _) = range_expr.as_ref() {
-                    if op == ".." {
-                        // 处理 start..end 格式
-                        let start_val = self.evaluate_expression(left);
-                        let end_val = self.evaluate_expression(right);
-                        
-                        let mut i = start_val as i32;
-                        while i < end_val as i32 {
-                            self.variables.insert(var.clone(), (Value::Int(i as i64), None));
-                            for stmt in body.iter() {
-                                self.evaluate(stmt);
-                            }
-                            i += 1;
-                        }
-                    } else if let ASTNode::Identifier(func_name, _) = range_expr.as_ref() {
-                        if func_name == "range" {
-                            // 这里需要更复杂的逻辑来处理 range(start, end)
-                            // 暂时使用简单的实现
-                            let start_val = 0.0; // 默认值
-                            let end_val = 10.0;  // 默认值
-                            
-                            let mut i = start_val as i32;
-                            while i < end_val as i32 {
-                                self.variables.insert(var.clone(), (Value::Int(i as i64), None));
-                                for stmt in body.iter() {
-                                    self.evaluate(stmt);
-                                }
-                                i += 1;
-                            }
-                        }
-                    } else {
-                        // 默认处理，假设是一个范围表达式
-                        let range_val = self.evaluate_expression(range_expr);
-                        let start_val = 0.0;
-                        let end_val = range_val;
-                        
-                        let mut i = start_val as i32;
-                        while i < end_val as i32 {
-                            self.variables.insert(var.clone(), (Value::Int(i as i64), None));
-                            for stmt in body.iter() {
-                                self.evaluate(stmt);
-                            }
-                            i += 1;
-                        }
+            ASTNode::For(_, range_expr, body, pos) => {
+                // 解析range表达式，支持 start..end 格式；映射遍历其中的键，其他表达式视为 0..end
+                let items = match range_expr.as_ref() {
+                    ASTNode::BinaryOp(left, op, right, _) if op == ".." => {
+                        let start = self.evaluate_value(left)?;
+                        let end = self.evaluate_value(right)?;
+                        ForItems::range(start, end)
                     }
-                } else {
-                    // 默认处理
-                    let range_val = self.evaluate_expression(range_expr);
-                    let start_val = 0.0;
-                    let end_val = range_val;
-                    
-                    let mut i = start_val as i32;
-                    while i < end_val as i32 {
-                        self.variables.insert(var.clone(), (Value::Int(i as i64), None));
-                        for stmt in body.iter() {
-                            self.evaluate(stmt);
-                        }
-                        i += 1;
+                    _ => ForItems::over(self.evaluate_value(range_expr)?),
+                }
+                .map_err(|bound| self.range_bound_error(&bound, pos))?;
+                
+                // 循环变量只在循环体内可见，每次迭代使用新的作用域
+                for item in items {
+                    let loop_env = Environment::new_child(&self.env, ScopeKind::Block);
+                    loop_env.borrow_mut().define_slot(0, Variable::new(item, None));
+                    match self.execute_block(body, loop_env)? {
+                        ControlFlow::Break => break,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                        ControlFlow::Normal | ControlFlow::Continue => {}
                     }
                 }
             }
             ASTNode::If(condition, then_branch, else_branch) => {
-                let cond_val = self.evaluate_expression(condition);
+                let cond_val = self.evaluate_expression(condition)?;
+                // break/continue/return 需要穿过 if 传递给外层的循环或函数
                 if cond_val != 0.0 {
-                    for stmt in then_branch.iter() {
-                        self.evaluate(stmt);
-                    }
+                    return self.execute_block(then_branch, Environment::new_child(&self.env, ScopeKind::Block));
                 } else if let Some(else_stmts) = else_branch {
-                    for stmt in else_stmts.iter() {
-                        self.evaluate(stmt);
-                    }
+                    return self.execute_block(else_stmts, Environment::new_child(&self.env, ScopeKind::Block));
                 }
             }
             ASTNode::While(condition, body) => {
                 loop {
-                    let cond_val = self.evaluate_expression(condition);
+                    let cond_val = self.evaluate_expression(condition)?;
                     if cond_val == 0.0 {
                         break;
                     }
-                    for stmt in body.iter() {
-                        self.evaluate(stmt);
+                    match self.execute_block(body, Environment::new_child(&self.env, ScopeKind::Block))? {
+                        ControlFlow::Break => break,
+                        ControlFlow::Return(value) => return Ok(ControlFlow::Return(value)),
+                        ControlFlow::Normal | ControlFlow::Continue => {}
                     }
                 }
             }
-            ASTNode::Input(prompt, var_name) => {
-                use std::io::{self, Write};
-                
-                let prompt_str = self.evaluate_print_expression(prompt);
-                print!("{}", prompt_str);
-                io::stdout().flush().unwrap();
-                
-                let mut input = String::new();
-                io::stdin().read_line(&mut input).expect("Failed to read input");
-                let input = input.trim();
-                
-                // Try to parse as number, otherwise store as string
-                if let Ok(num) = input.parse::<f64>() {
-                    self.variables.insert(var_name.clone(), (Value::Double(num), None));
-                } else {
-                    self.variables.insert(var_name.clone(), (Value::Str(input.to_string()), None));
-                }
+            ASTNode::Input(prompt, var_name, binding, pos) => {
+                let prompt_str = self.evaluate_print_expression(prompt)?;
+                self.read_input(&prompt_str, var_name, *binding, pos)?;
             }
-            ASTNode::Function(name, params, body) => {
+            ASTNode::Function(name, params, return_type, body, pos) => {
                 let function = Function {
-                    params: params.clone(),
-                    body: body.to_vec(),
-                    is_expr: false,
-                    param_types: Vec::new(),
+                    params: params.iter().map(|(param, _)| param.clone()).collect(),
+                    body: FunctionBody::Ast(body.as_slice().into()),
+                    signature: Signature::new(params, return_type.clone(), self.location(pos)),
+                    source: Rc::clone(&self.source),
                 };
-                self.functions.insert(name.clone(), function);
+                self.functions.insert(name.clone(), Rc::new(function));
             }
             ASTNode::Expr(name, typed_params, body) => {
-                let param_names: Vec<String> = typed_params.iter().map(|(_, name)| name.clone()).collect();
-                let param_types: Vec<String> = typed_params.iter().map(|(ptype, _)| ptype.clone()).collect();
                 let function = Function {
-                    params: param_names,
-                    body: body.to_vec(),
-                    is_expr: true,
-                    param_types,
+                    params: typed_params.iter().map(|(_, name)| name.clone()).collect(),
+                    body: FunctionBody::Ast(body.as_slice().into()),
+                    signature: Signature::default(),
+                    source: Rc::clone(&self.source),
                 };
-                self.functions.insert(name.clone(), function);
+                self.functions.insert(name.clone(), Rc::new(function));
+            }
+            ASTNode::StructDecl(name, fields, _) => {
+                let struct_type = StructType { name: name.clone(), fields: fields.clone(), caught: None };
+                self.structs.insert(name.clone(), Rc::new(struct_type));
             }
-            ASTNode::FunctionCall(name, args) => {
-                self.execute_function_call(name, args);
+            ASTNode::EnumDecl(name, variants, _) => {
+                let enum_type = EnumType { name: name.clone(), variants: variants.clone() };
+                self.enums.insert(name.clone(), Rc::new(enum_type));
             }
-            ASTNode::Return(expr) => {
-                let return_value = self.evaluate_value(expr);
-                // For now, we'll store the return value in a special variable
-                self.variables.insert("__return_value__".to_string(), (return_value, None));
+            ASTNode::Match(subject, arms, pos) => {
+                let value = self.evaluate_value(subject)?;
+                let index = self.select_arm(arms.iter().map(|(pattern, _)| pattern), &value, pos)?;
+                let (pattern, body) = &arms[index];
+                // break/continue/return 需要穿过 match 传递给外层的循环或函数
+                let arm_env = Environment::new_child(&self.env, ScopeKind::Block);
+                bind_pattern(&arm_env, pattern, value);
+                return self.execute_block(body, arm_env);
+            }
+            ASTNode::FunctionCall(name, args, pos) => {
+                self.execute_function_call(name, args, pos)?;
+            }
+            ASTNode::Call(callee, args, pos) => {
+                self.execute_call(callee, args, pos)?;
+            }
+            ASTNode::Return(expr, _) => {
+                // 语法分析已经保证 return 只出现在函数体中
+                return Ok(ControlFlow::Return(self.evaluate_value(expr)?));
             }
             ASTNode::Import(filename, pos) => {
                 // Handle import statement
-                self.handle_import(filename, pos);
+                self.handle_import(filename, pos)?;
+            }
+            ASTNode::Try(body, catch, finally, _) => {
+                let mut result = self.execute_block(body, Environment::new_child(&self.env, ScopeKind::Block));
+                if let (Err(error), Some((_, handler))) = (&result, catch) {
+                    // 错误值总是 catch 语句块作用域中的第一个槽位
+                    let catch_env = Environment::new_child(&self.env, ScopeKind::Block);
+                    catch_env.borrow_mut().define_slot(0, Variable::new(self.error_value(error.clone()), None));
+                    result = self.execute_block(handler, catch_env);
+                }
+                // finally 中的 break/continue/return 取代 try 和 catch 的结果
+                if let Some(cleanup) = finally {
+                    let flow = self.execute_block(cleanup, Environment::new_child(&self.env, ScopeKind::Block))?;
+                    if !matches!(flow, ControlFlow::Normal) {
+                        return Ok(flow);
+                    }
+                }
+                return result;
+            }
+            ASTNode::Throw(expr, pos) => {
+                let value = self.evaluate_value(expr)?;
+                return Err(self.throw_error(value, pos));
             }
+            ASTNode::Break(_) => return Ok(ControlFlow::Break),
+            ASTNode::Continue(_) => return Ok(ControlFlow::Continue),
             _ => {}
         }
+        
+        Ok(ControlFlow::Normal)
     }
     
-    fn evaluate_value(&mut self, node: &ASTNode) -> Value {
-        match node {
-            ASTNode::Number(n) => Value::Double(*n),
-            ASTNode::String(s) => Value::Str(s.clone()),
-            ASTNode::Bool(b) => Value::Bool(*b),
-            ASTNode::Identifier(name, pos) => {
-                self.variables.get(name).map(|(v, _)| v.clone()).unwrap_or_else(|| {
-                    // Check if it's a return value from a function
-                    if name == "__return_value__" {
-                        return Value::Double(0.0);
-                    }
-                    // 使用标识符节点中的位置信息
-                    let error = create_undefined_identifier_error(
-                        name,
-                        pos.line,
-                        pos.column,
-                        &self.file_path,
-                        self.source_lines.get(pos.line.saturating_sub(1)).unwrap_or(&String::new())
-                    );
-                    eprintln!("{}", error);
-                    std::process::exit(1);
-                })
-            }
-            ASTNode::IndexAccess(array_expr, index_expr, pos) => {
-                let array_val = self.evaluate_value(array_expr);
-                let index_val = self.evaluate_value(index_expr);
-                
-                let index = index_val.to_f64() as usize;
-                
-                match array_val {
-                    Value::Array(arr) => {
-                        if index < arr.len() {
-                            arr[index].clone()
-                        } else {
-                            // Index out of bounds - report error with standard compiler error format
-                            let error_msg = error_messages::array_index_out_of_bounds(index, arr.len());
-                            let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                                .unwrap_or(&String::new()).clone();
-                            
-                            let compiler_error = CompilerError::new(
-                                error_msg,
-                                pos.line,
-                                pos.column,
-                                self.file_path.clone(),
-                                source_line,
-                            )
-                            .with_help(error_messages::help_array_bounds())
-                            .with_example(error_messages::example_array_bounds());
-                            
-                            eprintln!("{}", compiler_error);
-                            std::process::exit(1);
-                        }
-                    }
-                    Value::List(lst) => {
-                        if index < lst.len() {
-                            lst[index].clone()
-                        } else {
-                            // Index out of bounds - report error with standard compiler error format
-                            let error_msg = error_messages::list_index_out_of_bounds(index, lst.len());
-                            let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                                .unwrap_or(&String::new()).clone();
-                            
-                            let compiler_error = CompilerError::new(
-                                error_msg,
-                                pos.line,
-                                pos.column,
-                                self.file_path.clone(),
-                                source_line,
-                            )
-                            .with_help(error_messages::help_array_bounds())
-                            .with_example(error_messages::example_array_bounds());
-                            
-                            eprintln!("{}", compiler_error);
-                            std::process::exit(1);
-                        }
-                    }
-                    _ => {
-                        // Not an array or list - return 0
-                        Value::Double(0.0)
-                    }
-                }
+    /// 在给定的作用域中依次执行语句，结束后恢复原来的作用域。
+    /// 遇到 break/continue/return 时停止执行剩余的语句，并将该信号返回给外层。
+    fn execute_block(&mut self, statements: &[ASTNode], env: Env) -> Result<ControlFlow, RuntimeError> {
+        let saved_env = std::mem::replace(&mut self.env, env);
+        let mut result = Ok(ControlFlow::Normal);
+        for stmt in statements {
+            result = self.evaluate(stmt);
+            if !matches!(result, Ok(ControlFlow::Normal)) {
+                break;
             }
-            ASTNode::FunctionCall(name, args) => {
-                self.execute_function_call(name, args);
-                // Return the function's return value, or 0.0 if no return
-                self.variables.get("__return_value__").map(|(v, _)| v.clone()).unwrap_or(Value::Double(0.0))
+        }
+        self.env = saved_env;
+        result
+    }
+    
+    /// 按照解析器给出的绑定借用变量，变量不存在时返回 `None`
+    fn with_variable<R>(&self, name: &str, binding: Binding, f: impl FnOnce(&Variable) -> R) -> Option<R> {
+        match binding {
+            Binding::Local { depth, slot } => self.env.borrow().with_slot(depth, slot, f),
+            Binding::Global { slot } => self.globals.borrow().global(slot).map(f),
+            Binding::Unresolved => self.globals.borrow().get(name).map(f),
+        }
+    }
+    
+    /// 可变地借用变量。赋值和修改变量中的元素都直接在作用域中进行，不复制变量的值
+    fn with_variable_mut<R>(&self, name: &str, binding: Binding, f: impl FnOnce(&mut Variable) -> R) -> Option<R> {
+        match binding {
+            Binding::Local { depth, slot } => self.env.borrow_mut().with_slot_mut(depth, slot, f),
+            Binding::Global { slot } => self.globals.borrow_mut().global_mut(slot).map(f),
+            Binding::Unresolved => self.globals.borrow_mut().get_mut(name).map(f),
+        }
+    }
+    
+    fn read_variable(&self, name: &str, binding: Binding, pos: &Position) -> Result<Value, RuntimeError> {
+        match self.with_variable(name, binding, |variable| variable.value.clone()) {
+            Some(value) => Ok(value),
+            None => {
+                // 没有同名的全局变量时，名称指的是具名函数
+                if !matches!(binding, Binding::Local { .. }) && let Some(function) = self.functions.get(name) {
+                    return Ok(Value::Function(Rc::new(Closure {
+                        name: Some(name.into()),
+                        function: Rc::clone(function),
+                        env: Rc::clone(&self.globals),
+                    })));
+                }
+                // 使用标识符节点中的位置信息
+                Err(self.undefined_identifier_error(name, pos))
             }
-            ASTNode::BinaryOp(left, op, right, pos) => {
-                let left_val = self.evaluate_value(left);
-                let right_val = self.evaluate_value(right);
-                
-                // Handle string concatenation - only allow string + string
-                if op == "+" {
-                    match (&left_val, &right_val) {
-                        (Value::Str(s1), Value::Str(s2)) => {
-                            return Value::Str(format!("{}{}", s1, s2));
-                        }
-                        // For other types, we now require explicit conversion
-                        _ => {}
+        }
+    }
+    
+    /// 声明变量：局部变量保存在当前作用域的槽位中，全局变量保存在全局槽位中
+    fn define_variable(&mut self, name: &str, binding: Binding, variable: Variable) {
+        match binding {
+            Binding::Local { slot, .. } => self.env.borrow_mut().define_slot(slot, variable),
+            Binding::Global { slot } => self.globals.borrow_mut().define_global(slot, variable),
+            Binding::Unresolved => self.globals.borrow_mut().define(name, variable),
+        }
+    }
+    
+    /// 在当前作用域中声明带类型的变量，初始值先转换为声明的类型
+    fn define_typed_variable(&mut self, name: &str, binding: Binding, var_type: &Type, value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        match value.convert_to(var_type) {
+            Ok(converted_value) => {
+                self.define_variable(name, binding, Variable::new(converted_value, Some(var_type.clone())));
+                Ok(())
+            }
+            Err(error_msg) => Err(RuntimeError::new(ErrorKind::Type, self.error_at(format!("Type error: {}", error_msg), pos))),
+        }
+    }
+    
+    /// 声明常量，带类型的常量的值先转换为声明的类型。全局常量记录声明位置，用于赋值错误的提示
+    fn define_constant(&mut self, name: &str, binding: Binding, const_type: Option<&Type>, value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        let value = match const_type {
+            Some(const_type) => value.convert_to(const_type)
+                .map_err(|error_msg| RuntimeError::new(ErrorKind::Type, self.error_at(format!("Type error: {}", error_msg), pos)))?,
+            None => value,
+        };
+        if !matches!(binding, Binding::Local { .. }) {
+            self.constant_origins.insert(name.to_string(), self.location(pos));
+        }
+        self.define_variable(name, binding, Variable::constant(value, const_type.cloned()));
+        Ok(())
+    }
+    
+    /// 给常量赋值的错误，指出常量的声明位置
+    fn assign_to_constant_error(&self, name: &str, binding: Binding, pos: &Position) -> RuntimeError {
+        let error = self.error_at(error_messages::assign_to_constant(name), pos)
+            .with_help(error_messages::help_assign_to_constant());
+        // 局部常量在执行之前就已经检查过，这里只会遇到全局常量
+        let error = match (binding, self.constant_origins.get(name)) {
+            (Binding::Local { .. }, _) => error,
+            (_, Some(origin)) => error.with_note(error_messages::constant_declared_at(name, origin)),
+            (_, None) => error.with_note(error_messages::builtin_constant(name)),
+        };
+        RuntimeError::new(ErrorKind::Constant, error)
+    }
+    
+    /// 把运行时错误转换为 catch 得到的错误值，错误值的类型保存原来的错误
+    fn error_value(&self, error: RuntimeError) -> Value {
+        let fields = error_fields(&error);
+        let error_type = StructType {
+            name: self.error_type.name.clone(),
+            fields: self.error_type.fields.clone(),
+            caught: Some(error),
+        };
+        Value::Struct(Rc::new(error_type), fields)
+    }
+    
+    /// throw 语句产生的错误。再次抛出没有修改过的错误值时原样返回原来的错误，包括帮助信息和原来文件中的位置；
+    /// 修改过的错误值按字段中的信息和种类产生错误，位置没有修改时仍然指向原来的文件。
+    /// 抛出其他值时以值的字符串形式作为错误信息
+    fn throw_error(&self, value: Value, pos: &Position) -> RuntimeError {
+        if let Value::Struct(struct_type, fields) = &value
+            && let Some(error) = &struct_type.caught
+        {
+            if *fields == error_fields(error) {
+                return error.clone();
+            }
+            if let [Value::Str(message), Value::Str(kind), Value::Int(line), Value::Int(column)] = fields.as_slice() {
+                let original = &error.diagnostic;
+                let diagnostic = if (*line, *column) == (original.line as i64, original.column as i64) {
+                    // 位置没有修改时保留原来的文件和源代码行，错误可能来自导入的文件
+                    CompilerError::new(message.clone(), original.line, original.column, original.file_path.clone(), original.source_line.clone())
+                } else {
+                    // 修改后的位置指向当前文件，不在当前文件中时使用 throw 语句的位置
+                    let origin = self.source_position(*line, *column).unwrap_or_else(|| pos.clone());
+                    self.error_at(message.clone(), &origin)
+                };
+                return RuntimeError::new(ErrorKind::from_name(kind), diagnostic);
+            }
+        }
+        RuntimeError::new(ErrorKind::Thrown, self.error_at(value.to_string(), pos))
+    }
+    
+    /// 当前文件中存在的位置：行号从 1 开始，列号最多比这一行的字符数多 1
+    fn source_position(&self, line: i64, column: i64) -> Option<Position> {
+        let line = usize::try_from(line).ok()?;
+        let column = usize::try_from(column).ok()?;
+        let source_line = self.source.lines.get(line.checked_sub(1)?)?;
+        (1..=source_line.chars().count() + 1).contains(&column).then(|| Position::new(line, column))
+    }
+    
+    fn convert_array_element(&self, value: Value, element_type: &Type, pos: &Position) -> Result<Value, RuntimeError> {
+        value.convert_to(element_type).map_err(|e| {
+            RuntimeError::new(ErrorKind::Type, self.error_at(format!("Type error in array initialization: {}", e), pos))
+        })
+    }
+    
+    /// 声明数组，`sizes` 是由外向内每一维的长度
+    fn define_array(&mut self, name: &str, binding: Binding, element_type: &Type, sizes: &[usize], values: Vec<Value>, pos: &Position) -> Result<(), RuntimeError> {
+        let array = self.build_array(values, element_type, sizes, pos)?;
+        
+        // Store the array with its element type as the variable type
+        self.define_variable(name, binding, Variable::new(array, Some(element_type.clone())));
+        Ok(())
+    }
+    
+    /// 由初始值构造数组：只有一个初始值时填充整个数组，初始值不足时用元素类型的默认值补齐，多于数组长度时报错。
+    /// 多维数组的每个元素是内层的数组，列表初始值按同样的规则构造内层数组，其他值填充整个内层数组
+    fn build_array(&self, values: Vec<Value>, element_type: &Type, sizes: &[usize], pos: &Position) -> Result<Value, RuntimeError> {
+        let (&size, inner_sizes) = sizes.split_first().expect("an array has at least one dimension");
+        if values.len() > size.max(1) {
+            return Err(RuntimeError::new(ErrorKind::Index, self.error_at(error_messages::too_many_array_values(size, values.len()), pos)
+                .with_help(error_messages::help_array_values())));
+        }
+        let element = |value: Value| match (inner_sizes, value) {
+            ([], value) => self.convert_array_element(value, element_type, pos),
+            (_, Value::List(items) | Value::Array(items)) => self.build_array(items, element_type, inner_sizes, pos),
+            (_, value) => self.build_array(vec![value], element_type, inner_sizes, pos),
+        };
+        
+        let elements = if values.len() == 1 {
+            let value = values.into_iter().next().expect("checked length");
+            vec![element(value)?; size]
+        } else {
+            let mut elements = values.into_iter().map(element).collect::<Result<Vec<_>, _>>()?;
+            if elements.len() < size {
+                let default = match inner_sizes {
+                    [] => default_value(element_type),
+                    _ => self.build_array(Vec::new(), element_type, inner_sizes, pos)?,
+                };
+                elements.resize(size, default);
+            }
+            elements
+        };
+        Ok(Value::Array(elements))
+    }
+    
+    /// 给变量赋值：带类型的变量先进行类型转换，变量不存在时在当前作用域中创建
+    fn assign_variable(&mut self, name: &str, binding: Binding, new_value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        // 变量存在时闭包取走新的值，否则新的值留给下面声明变量
+        let mut pending = Some(new_value);
+        let assigned = self.with_variable_mut(name, binding, |variable| {
+            let new_value = pending.take().expect("the variable is borrowed once");
+            if variable.constant {
+                return Err(self.assign_to_constant_error(name, binding, pos));
+            }
+            variable.value = match &variable.var_type {
+                Some(expected_type) => new_value.convert_to(expected_type).map_err(|error_msg| {
+                    RuntimeError::new(ErrorKind::Type, self.error_at(format!("Type error in assignment: {}", error_msg), pos))
+                })?,
+                None => new_value,
+            };
+            Ok(())
+        });
+        if let Some(result) = assigned {
+            return result;
+        }
+        // Variable doesn't exist, create it without type in the current scope
+        if let Some(new_value) = pending {
+            self.define_variable(name, binding, Variable::new(new_value, None));
+        }
+        Ok(())
+    }
+    
+    /// 修改变量中路径 `path` 上的元素或字段，例如 `grid[i][j]` 中的 `[i, j]` 或 `shapes[0].items[2]` 中的 `[0, items, 2]`。
+    /// 映射中不存在的键会被添加；数组/列表的下标越界、路径中间的键或字段不存在、路径上的值不能按下标或字段修改时
+    /// 报告错误，变量保持不变
+    fn assign_path(&mut self, name: &str, binding: Binding, path: &[PathStep], value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        let assigned = self.with_variable_mut(name, binding, |variable| {
+            if variable.constant {
+                return Err(self.assign_to_constant_error(name, binding, pos));
+            }
+            // For array variables, the type is the element type
+            self.write_path(&mut variable.value, variable.var_type.as_ref(), path, value, pos)
+        });
+        assigned.unwrap_or_else(|| Err(self.undefined_identifier_error(name, pos)))
+    }
+    
+    /// 把 `target` 中路径 `path` 上的元素或字段原地替换为 `value`，下标和字段的检查与读取时相同。
+    /// 所有检查都在替换之前完成，出错时 `target` 保持不变。数组的元素类型只沿着下标向内传递
+    fn write_path(&self, target: &mut Value, element_type: Option<&Type>, path: &[PathStep], value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        let (step, rest) = path.split_first().expect("assignment path is never empty");
+        let index_val = match step {
+            PathStep::Index(index_val) => index_val,
+            PathStep::Field(field) => {
+                let index = self.field_index(target, field, pos)?;
+                let Value::Struct(struct_type, values) = target else {
+                    unreachable!("field_index only succeeds for structs");
+                };
+                return match rest {
+                    [] => {
+                        values[index] = self.convert_field(struct_type, index, value, pos)?;
+                        Ok(())
                     }
+                    _ => self.write_path(&mut values[index], None, rest, value, pos),
+                };
+            }
+        };
+        match target {
+            Value::Array(arr) => {
+                let index = self.sequence_index("array", index_val, arr.len(), pos)?;
+                self.write_element(&mut arr[index], element_type, rest, value, pos)
+            }
+            Value::List(lst) => {
+                let index = self.sequence_index("list", index_val, lst.len(), pos)?;
+                self.write_element(&mut lst[index], None, rest, value, pos)
+            }
+            Value::Map(entries) => {
+                self.check_map_key(index_val, pos)?;
+                if rest.is_empty() {
+                    map_insert(entries, index_val.clone(), value);
+                    return Ok(());
                 }
-                
-                // For other operations, convert to f64
-                // Check if both operands are numeric types before doing arithmetic
-                match (&left_val, &right_val) {
-                    (Value::Int(_) | Value::Float(_) | Value::Double(_), 
-                     Value::Int(_) | Value::Float(_) | Value::Double(_)) => {
-                        let left_f64 = left_val.to_f64();
-                        let right_f64 = right_val.to_f64();
-                        
-                        match op.as_str() {
-                            "+" => Value::Double(left_f64 + right_f64),
-                            "-" => Value::Double(left_f64 - right_f64),
-                            "*" => Value::Double(left_f64 * right_f64),
-                            "/" => {
-                                if right_f64 != 0.0 {
-                                    Value::Double(left_f64 / right_f64)
-                                } else {
-                                    Value::Double(0.0)
-                                }
-                            }
-                            "==" => Value::Bool(left_f64 == right_f64),
-                            "<" => Value::Bool(left_f64 < right_f64),
-                            ">" => Value::Bool(left_f64 > right_f64),
-                            _ => Value::Double(0.0),
-                        }
-                    }
-                    // Handle string concatenation with explicit conversion requirement
-                    (Value::Str(_), Value::Str(_)) if op == "+" => {
-                        // This case is already handled above
-                        Value::Str(format!("{}{}", left_val.to_string(), right_val.to_string()))
-                    }
-                    // All other combinations are type errors
-                    _ => {
-                        // Create a type error for incompatible operations
-                        let error_msg = format!("Type error: cannot perform operation '{}' between '{}' and '{}'",
-                                              op, left_val.get_type_name(), right_val.get_type_name());
-                        let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                            .unwrap_or(&String::new()).clone();
-                        
-                        let compiler_error = CompilerError::new(
-                            error_msg,
-                            pos.line,
-                            pos.column,
-                            self.file_path.clone(),
-                            source_line,
-                        )
-                        .with_help("help: ensure both operands are of compatible types for the operation".to_string())
-                        .with_example("example: for arithmetic operations, both operands should be numeric types".to_string());
-                        
-                        eprintln!("{}", compiler_error);
-                        std::process::exit(1);
-                    }
+                match entries.iter_mut().find(|(key, _)| key == index_val) {
+                    Some((_, current)) => self.write_element(current, None, rest, value, pos),
+                    // 路径中间不存在的键没有可以修改的元素
+                    None => Err(self.key_not_found_error(index_val, pos)),
                 }
             }
-            ASTNode::TypeConversion(target_type, expr, pos) => {
-                let value = self.evaluate_value(expr);
-                match value.convert_to(target_type) {
-                    Ok(converted_value) => converted_value,
-                    Err(error_msg) => {
-                        // Create a proper compiler error with full formatting
-                        let error_msg = format!("Type conversion failed: {}", error_msg);
-                        let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                            .unwrap_or(&String::new()).clone();
-                        
-                        let compiler_error = CompilerError::new(
-                            error_msg,
-                            pos.line,
-                            pos.column,
-                            self.file_path.clone(),
-                            source_line,
-                        )
-                        .with_help(error_messages::help_array_type())
-                        .with_example(error_messages::example_array_type());
-                        
-                        eprintln!("{}", compiler_error);
-                        std::process::exit(1);
-                    }
+            other => Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::not_index_assignable(&other.get_type_name()), pos)
+                .with_help(error_messages::help_index_type()))),
+        }
+    }
+    
+    /// 替换下标对应的元素：路径已经结束时写入新的值（数组元素转换为元素类型），否则继续修改元素内部
+    fn write_element(&self, current: &mut Value, element_type: Option<&Type>, rest: &[PathStep], value: Value, pos: &Position) -> Result<(), RuntimeError> {
+        match (rest, element_type) {
+            ([], Some(expected_type)) => match conform_element(current, value.clone(), expected_type) {
+                Some(converted_value) => {
+                    *current = converted_value;
+                    Ok(())
+                }
+                None => {
+                    let error_msg = error_messages::array_type_error(
+                        &slot_type_name(current, expected_type),
+                        &value.get_type_name()
+                    );
+                    
+                    let compiler_error = self.error_at(error_msg, pos)
+                        .with_help(error_messages::help_array_assignment())
+                        .with_example(error_messages::example_array_assignment());
+                    
+                    Err(RuntimeError::new(ErrorKind::Type, compiler_error))
+                }
+            },
+            // If no type info, just assign
+            ([], None) => {
+                *current = value;
+                Ok(())
+            }
+            _ => self.write_path(current, element_type, rest, value, pos),
+        }
+    }
+    
+    /// 读取变量中的元素。变量在作用域中借用，只复制读到的元素而不是整个数组、列表或映射
+    fn index_variable(&self, name: &str, binding: Binding, index_val: &Value, pos: &Position) -> Result<Value, RuntimeError> {
+        if let Some(element) = self.with_variable(name, binding, |variable| self.index_value(&variable.value, index_val, pos)) {
+            return element;
+        }
+        // 变量不存在时，名称可能指的是具名函数
+        let value = self.read_variable(name, binding, pos)?;
+        self.index_value(&value, index_val, pos)
+    }
+    
+    /// 读取数组、列表或映射中的元素或字符串中的字符
+    fn index_value(&self, array_val: &Value, index_val: &Value, pos: &Position) -> Result<Value, RuntimeError> {
+        match array_val {
+            Value::Array(arr) => {
+                let index = self.sequence_index("array", index_val, arr.len(), pos)?;
+                Ok(arr[index].clone())
+            }
+            Value::List(lst) => {
+                let index = self.sequence_index("list", index_val, lst.len(), pos)?;
+                Ok(lst[index].clone())
+            }
+            Value::Map(entries) => {
+                self.check_map_key(index_val, pos)?;
+                match entries.iter().find(|(key, _)| key == index_val) {
+                    Some((_, value)) => Ok(value.clone()),
+                    None => Err(self.key_not_found_error(index_val, pos)),
+                }
+            }
+            // 字符串的下标按字符计算，得到只有一个字符的字符串
+            Value::Str(s) => {
+                let index = self.sequence_index("str", index_val, s.chars().count(), pos)?;
+                Ok(Value::Str(s.chars().nth(index).expect("index is within the str").to_string()))
+            }
+            other => Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::not_indexable(&other.get_type_name()), pos)
+                .with_help(error_messages::help_index_type()))),
+        }
+    }
+    
+    /// 检查数组、列表或字符串的下标：下标必须是 int，负数和不小于 `length` 的下标越界
+    fn sequence_index(&self, type_name: &str, index_val: &Value, length: usize, pos: &Position) -> Result<usize, RuntimeError> {
+        let Value::Int(index) = *index_val else {
+            let error_msg = error_messages::index_type_error(type_name, &index_val.get_type_name());
+            return Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_msg, pos)
+                .with_help(error_messages::help_index_type())));
+        };
+        match usize::try_from(index) {
+            Ok(index) if index < length => Ok(index),
+            _ => Err(self.index_error(error_messages::index_out_of_bounds(index, type_name, length), pos)),
+        }
+    }
+    
+    fn key_not_found_error(&self, key: &Value, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Key, self.error_at(error_messages::map_key_not_found(&describe_map_key(key)), pos)
+            .with_help(error_messages::help_map_key_not_found()))
+    }
+    
+    fn check_map_key(&self, key: &Value, pos: &Position) -> Result<(), RuntimeError> {
+        if is_map_key(key) {
+            return Ok(());
+        }
+        Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::invalid_map_key(&key.get_type_name()), pos)
+            .with_help(error_messages::help_map_key())))
+    }
+    
+    /// 由依次排列的键和值构造映射，重复的键保留最后一个值
+    fn build_map(&self, keys_and_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        let mut entries = Vec::with_capacity(keys_and_values.len() / 2);
+        let mut items = keys_and_values.into_iter();
+        while let (Some(key), Some(value)) = (items.next(), items.next()) {
+            self.check_map_key(&key, pos)?;
+            map_insert(&mut entries, key, value);
+        }
+        Ok(Value::Map(entries))
+    }
+    
+    /// 用构造参数创建结构体，每个参数先转换为对应字段声明的类型
+    // 不内联到 call_function 中，以免增大每一层递归调用的栈帧
+    #[inline(never)]
+    fn construct_struct(&self, struct_type: Rc<StructType>, arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        if arg_values.len() != struct_type.fields.len() {
+            let error_msg = error_messages::struct_arity_error(&struct_type.name, struct_type.fields.len(), arg_values.len());
+            return Err(RuntimeError::new(ErrorKind::Argument, self.error_at(error_msg, pos)
+                .with_help(error_messages::help_struct_constructor(&struct_type.name, &struct_type.field_names()))));
+        }
+        
+        let mut values = Vec::with_capacity(arg_values.len());
+        for (index, value) in arg_values.into_iter().enumerate() {
+            values.push(self.convert_field(&struct_type, index, value, pos)?);
+        }
+        Ok(Value::Struct(struct_type, values))
+    }
+    
+    /// 创建枚举值，成员携带的每个值先转换为对应字段声明的类型
+    fn construct_variant(&self, enum_name: &str, variant: &str, arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        let Some(enum_type) = self.enums.get(enum_name) else {
+            return Err(RuntimeError::new(ErrorKind::Name, self.error_at(error_messages::undefined_enum(enum_name), pos)));
+        };
+        let Some(index) = enum_type.variant_index(variant) else {
+            return Err(RuntimeError::new(ErrorKind::Name, self.error_at(error_messages::unknown_variant(enum_name, variant), pos)
+                .with_help(error_messages::help_enum_variants(enum_name, &enum_type.variant_names()))));
+        };
+        
+        let fields = &enum_type.variants[index].1;
+        if arg_values.len() != fields.len() {
+            let error_msg = error_messages::variant_payload_count(enum_name, variant, fields.len(), arg_values.len());
+            return Err(RuntimeError::new(ErrorKind::Argument, self.error_at(error_msg, pos)));
+        }
+        
+        let mut values = Vec::with_capacity(arg_values.len());
+        for ((field, field_type), value) in fields.iter().zip(arg_values) {
+            let value = match field_type {
+                Some(field_type) => value.convert_to(field_type).map_err(|_| {
+                    let error_msg = error_messages::variant_payload_type_error(enum_name, variant, field, &field_type.to_string(), &value.get_type_name());
+                    RuntimeError::new(ErrorKind::Type, self.error_at(error_msg, pos))
+                })?,
+                None => value,
+            };
+            values.push(value);
+        }
+        Ok(Value::Enum(Rc::clone(enum_type), index, values))
+    }
+    
+    /// 第一个与值匹配的分支的下标，没有分支匹配时报告错误
+    fn select_arm<'p>(&self, mut patterns: impl Iterator<Item = &'p Pattern>, value: &Value, pos: &Position) -> Result<usize, RuntimeError> {
+        patterns.position(|pattern| pattern_matches(pattern, value))
+            .ok_or_else(|| self.no_matching_arm_error(value, pos))
+    }
+    
+    /// 运行时没有分支匹配，只会发生在有字面量或范围模式、并且导入的文件使静态检查无法进行时
+    fn no_matching_arm_error(&self, value: &Value, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Match, self.error_at(error_messages::no_matching_arm(&describe_map_key(value)), pos)
+            .with_help(error_messages::help_non_exhaustive_match()))
+    }
+    
+    /// 将值转换为字段声明的类型，没有类型的字段接受任意值
+    fn convert_field(&self, struct_type: &StructType, index: usize, value: Value, pos: &Position) -> Result<Value, RuntimeError> {
+        let (field, field_type) = &struct_type.fields[index];
+        let Some(field_type) = field_type else {
+            return Ok(value);
+        };
+        value.convert_to(field_type).map_err(|_| {
+            let error_msg = error_messages::struct_field_type_error(&struct_type.name, field, &field_type.to_string(), &value.get_type_name());
+            RuntimeError::new(ErrorKind::Type, self.error_at(error_msg, pos))
+        })
+    }
+    
+    /// 结构体中字段的下标，字段不存在或值不是结构体时报告错误
+    fn field_index(&self, value: &Value, field: &str, pos: &Position) -> Result<usize, RuntimeError> {
+        let Value::Struct(struct_type, _) = value else {
+            let error_msg = error_messages::field_access_on_non_struct(field, &value.get_type_name());
+            return Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_msg, pos)));
+        };
+        struct_type.field_index(field).ok_or_else(|| {
+            RuntimeError::new(ErrorKind::Name, self.error_at(error_messages::unknown_field(&struct_type.name, field), pos)
+                .with_help(error_messages::help_struct_fields(&struct_type.name, &struct_type.field_names())))
+        })
+    }
+    
+    fn field_value(&self, value: Value, field: &str, pos: &Position) -> Result<Value, RuntimeError> {
+        let index = self.field_index(&value, field, pos)?;
+        match value {
+            Value::Struct(_, mut values) => Ok(values.swap_remove(index)),
+            _ => unreachable!("field_index only succeeds for structs"),
+        }
+    }
+    
+    /// 显示提示信息并读取一行输入，数字按 int 或 double 保存，其他内容保存为字符串
+    fn read_input(&mut self, prompt: &str, var_name: &str, binding: Binding, pos: &Position) -> Result<(), RuntimeError> {
+        use std::io::{self, Write};
+        
+        // 导入的常量只能在运行时检查
+        if self.with_variable(var_name, binding, |variable| variable.constant) == Some(true) {
+            return Err(self.assign_to_constant_error(var_name, binding, pos));
+        }
+        
+        print!("{}", prompt);
+        let mut input = String::new();
+        io::stdout().flush()
+            .and_then(|()| io::stdin().read_line(&mut input))
+            .map_err(|error| RuntimeError::new(ErrorKind::Runtime, self.error_at(error_messages::input_error(var_name, &error.to_string()), pos)))?;
+        let input = input.trim();
+        
+        // Try to parse as number, otherwise store as string
+        let value = if let Ok(num) = input.parse::<i64>() {
+            Value::Int(num)
+        } else if let Ok(num) = input.parse::<f64>() {
+            Value::Double(num)
+        } else {
+            Value::Str(input.to_string())
+        };
+        
+        // 与赋值相同：更新已有的变量，否则在当前作用域中创建
+        if self.with_variable_mut(var_name, binding, |variable| variable.value = value.clone()).is_none() {
+            self.define_variable(var_name, binding, Variable::new(value, None));
+        }
+        Ok(())
+    }
+    
+    fn apply_unary_op(&self, op: &str, value: Value, pos: &Position) -> Result<Value, RuntimeError> {
+        match op {
+            "!" => Ok(Value::Bool(!value.is_truthy())),
+            _ => Err(RuntimeError::new(ErrorKind::Runtime, self.error_at(format!("Unknown unary operator '{}'", op), pos))),
+        }
+    }
+    
+    fn convert_value(&self, value: Value, target_type: &Type, pos: &Position) -> Result<Value, RuntimeError> {
+        value.convert_to(target_type)
+            .map_err(|error_msg| self.conversion_error(error_msg, pos))
+    }
+    
+    fn evaluate_value(&mut self, node: &ASTNode) -> Result<Value, RuntimeError> {
+        match node {
+            ASTNode::Integer(n) => Ok(Value::Int(*n)),
+            ASTNode::Number(n) => Ok(Value::Double(*n)),
+            ASTNode::String(s) => Ok(Value::Str(s.clone())),
+            ASTNode::Bool(b) => Ok(Value::Bool(*b)),
+            ASTNode::Identifier(name, binding, pos) => self.read_variable(name, *binding, pos),
+            ASTNode::IndexAccess(array_expr, index_expr, pos) => {
+                // 下标中没有函数调用时不会修改变量，可以先计算下标再借用变量读取元素
+                if let ASTNode::Identifier(name, binding, _) = array_expr.as_ref() && !may_call(index_expr) {
+                    let index_val = self.evaluate_value(index_expr)?;
+                    return self.index_variable(name, *binding, &index_val, pos);
                 }
+                let array_val = self.evaluate_value(array_expr)?;
+                let index_val = self.evaluate_value(index_expr)?;
+                self.index_value(&array_val, &index_val, pos)
+            }
+            ASTNode::FieldAccess(struct_expr, field, pos) => {
+                let value = self.evaluate_value(struct_expr)?;
+                self.field_value(value, field, pos)
+            }
+            ASTNode::ListLiteral(elements) => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate_value(element)?);
+                }
+                Ok(Value::List(values))
+            }
+            ASTNode::MapLiteral(entries, pos) => {
+                let mut keys_and_values = Vec::with_capacity(entries.len() * 2);
+                for (key, value) in entries {
+                    keys_and_values.push(self.evaluate_value(key)?);
+                    keys_and_values.push(self.evaluate_value(value)?);
+                }
+                self.build_map(keys_and_values, pos)
+            }
+            ASTNode::FunctionCall(name, args, pos) => {
+                self.execute_function_call(name, args, pos)
+            }
+            ASTNode::BinaryOp(left, op, right, pos) => {
+                self.evaluate_binary_op(left, op, right, pos)
+            }
+            ASTNode::UnaryOp(op, expr, pos) => {
+                let value = self.evaluate_value(expr)?;
+                self.apply_unary_op(op, value, pos)
+            }
+            ASTNode::TypeConversion(target_type, expr, pos) => {
+                let value = self.evaluate_value(expr)?;
+                self.convert_value(value, target_type, pos)
             }
             ASTNode::IfExpr(condition, then_expr, else_expr) => {
-                let cond_val = self.evaluate_expression(condition);
+                let cond_val = self.evaluate_expression(condition)?;
                 if cond_val != 0.0 {
                     self.evaluate_value(then_expr)
                 } else {
                     self.evaluate_value(else_expr)
                 }
             }
-            _ => Value::Double(0.0),
+            ASTNode::Call(callee, args, pos) => self.execute_call(callee, args, pos),
+            ASTNode::Lambda(params, return_type, body, pos) => {
+                let function = Function {
+                    params: params.iter().map(|(param, _)| param.clone()).collect(),
+                    body: FunctionBody::Ast(body.as_slice().into()),
+                    signature: Signature::new(params, return_type.clone(), self.location(pos)),
+                    source: Rc::clone(&self.source),
+                };
+                Ok(self.make_lambda(Rc::new(function)))
+            }
+            ASTNode::EnumVariant(enum_name, variant, args, pos) => {
+                let mut arg_values = Vec::with_capacity(args.len());
+                for arg in args {
+                    arg_values.push(self.evaluate_value(arg)?);
+                }
+                self.construct_variant(enum_name, variant, arg_values, pos)
+            }
+            ASTNode::MatchExpr(subject, arms, pos) => {
+                let value = self.evaluate_value(subject)?;
+                let index = self.select_arm(arms.iter().map(|(pattern, _)| pattern), &value, pos)?;
+                let (pattern, arm_expr) = &arms[index];
+                let arm_env = Environment::new_child(&self.env, ScopeKind::Block);
+                bind_pattern(&arm_env, pattern, value);
+                let saved_env = std::mem::replace(&mut self.env, arm_env);
+                let result = self.evaluate_value(arm_expr);
+                self.env = saved_env;
+                result
+            }
+            _ => Ok(Value::Double(0.0)),
         }
     }
     
-    fn evaluate_print_expression(&mut self, node: &ASTNode) -> String {
-        match node {
-            ASTNode::String(s) => s.clone(),
-            ASTNode::Number(n) => n.to_string(),
-            ASTNode::Bool(b) => b.to_string(),
-            ASTNode::Identifier(name, pos) => {
-                self.variables.get(name).map(|(v, _)| v.to_string()).unwrap_or_else(|| {
-                    // 使用标识符节点中的位置信息
-                    let error = create_undefined_identifier_error(
-                        name,
-                        pos.line,
-                        pos.column,
-                        &self.file_path,
-                        self.source_lines.get(pos.line.saturating_sub(1)).unwrap_or(&String::new())
-                    );
-                    eprintln!("{}", error);
-                    std::process::exit(1);
-                })
+    /// 计算二元运算。`&&` 和 `||` 短路求值：左操作数已能决定结果时不再计算右操作数
+    fn evaluate_binary_op(&mut self, left: &ASTNode, op: &str, right: &ASTNode, pos: &Position) -> Result<Value, RuntimeError> {
+        match op {
+            "&&" => {
+                if !self.evaluate_value(left)?.is_truthy() {
+                    return Ok(Value::Bool(false));
+                }
+                return Ok(Value::Bool(self.evaluate_value(right)?.is_truthy()));
             }
-            ASTNode::BinaryOp(left, op, right, pos) => {
-                let left_val = self.evaluate_value(left);
-                let right_val = self.evaluate_value(right);
+            "||" => {
+                if self.evaluate_value(left)?.is_truthy() {
+                    return Ok(Value::Bool(true));
+                }
+                return Ok(Value::Bool(self.evaluate_value(right)?.is_truthy()));
+            }
+            _ => {}
+        }
+        
+        let left_val = self.evaluate_value(left)?;
+        let right_val = self.evaluate_value(right)?;
+        let op = BinaryOp::from_symbol(op).expect("the parser only produces known binary operators");
+        self.apply_binary_op(op, left_val, right_val, pos)
+    }
+    
+    /// 对两个已求值的操作数进行二元运算（`&&` 和 `||` 的短路求值由调用方处理）
+    fn apply_binary_op(&self, op: BinaryOp, left_val: Value, right_val: Value, pos: &Position) -> Result<Value, RuntimeError> {
+        // 检查器在执行之前报告 for 循环之外的区间，区间不是数值运算
+        if op == BinaryOp::Range {
+            return Err(RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::range_outside_for(), pos)
+                .with_help(error_messages::help_range_outside_for())));
+        }
+        
+        // Handle string concatenation - only allow string + string
+        // For other types, we now require explicit conversion
+        if op == BinaryOp::Add && let (Value::Str(s1), Value::Str(s2)) = (&left_val, &right_val) {
+            return Ok(Value::Str(format!("{}{}", s1, s2)));
+        }
+        
+        // 相等比较同样适用于字符串和布尔值
+        if matches!(op, BinaryOp::Equal | BinaryOp::NotEqual) && let Some(equal) = values_equal(&left_val, &right_val) {
+            return Ok(Value::Bool(equal == (op == BinaryOp::Equal)));
+        }
+        
+        // 数值运算按照 int -> float -> double 的顺序提升类型：
+        // 两个 int 的结果为 int，int 与 float 的结果为 float，只要有 double 参与结果就是 double
+        match (&left_val, &right_val) {
+            (Value::Int(_) | Value::Float(_) | Value::Double(_), 
+             Value::Int(_) | Value::Float(_) | Value::Double(_)) => {
+                let left_f64 = left_val.to_f64();
+                let right_f64 = right_val.to_f64();
                 
-                // Handle string concatenation - only allow string + string
-                if op == "+" {
-                    match (&left_val, &right_val) {
-                        (Value::Str(s1), Value::Str(s2)) => {
-                            return format!("{}{}", s1, s2);
-                        }
-                        // For other types, we now require explicit conversion
-                        _ => {}
-                    }
+                // 0 的负数次幂等于除以 0
+                if (matches!(op, BinaryOp::Div | BinaryOp::Rem | BinaryOp::IntDiv) && right_f64 == 0.0)
+                    || (op == BinaryOp::Pow && left_f64 == 0.0 && right_f64 < 0.0)
+                {
+                    return Err(RuntimeError::new(ErrorKind::Arithmetic, self.error_at(error_messages::division_by_zero(op.symbol()), pos)
+                        .with_help(error_messages::help_division_by_zero())));
                 }
                 
-                // For other operations, convert to f64
-                // Check if both operands are numeric types before doing arithmetic
                 match (&left_val, &right_val) {
-                    (Value::Int(_) | Value::Float(_) | Value::Double(_), 
-                     Value::Int(_) | Value::Float(_) | Value::Double(_)) => {
-                        let left_f64 = left_val.to_f64();
-                        let right_f64 = right_val.to_f64();
-                        
-                        match op.as_str() {
-                            "+" => (left_f64 + right_f64).to_string(),
-                            "-" => (left_f64 - right_f64).to_string(),
-                            "*" => (left_f64 * right_f64).to_string(),
-                            "/" => {
-                                if right_f64 != 0.0 {
-                                    (left_f64 / right_f64).to_string()
-                                } else {
-                                    "0".to_string()
-                                }
-                            }
-                            "==" => (left_f64 == right_f64).to_string(),
-                            "<" => (left_f64 < right_f64).to_string(),
-                            ">" => (left_f64 > right_f64).to_string(),
-                            _ => "0".to_string(),
-                        }
-                    }
-                    // Handle string concatenation with explicit conversion requirement
-                    (Value::Str(_), Value::Str(_)) if op == "+" => {
-                        // This case is already handled above
-                        format!("{}{}", left_val.to_string(), right_val.to_string())
-                    }
-                    // All other combinations are type errors
-                    _ => {
-                        // Create a type error for incompatible operations
-                        let error_msg = format!("Type error: cannot perform operation '{}' between '{}' and '{}'",
-                                              op, left_val.get_type_name(), right_val.get_type_name());
-                        let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                            .unwrap_or(&String::new()).clone();
-                        
-                        let compiler_error = CompilerError::new(
-                            error_msg,
-                            pos.line,
-                            pos.column,
-                            self.file_path.clone(),
-                            source_line,
-                        )
-                        .with_help("help: ensure both operands are of compatible types for the operation".to_string())
-                        .with_example("example: for arithmetic operations, both operands should be numeric types".to_string());
-                        
-                        eprintln!("{}", compiler_error);
-                        std::process::exit(1);
+                    (Value::Int(a), Value::Int(b)) => integer_operation(op, *a, *b).ok_or_else(|| {
+                        RuntimeError::new(ErrorKind::Arithmetic, self.error_at(error_messages::integer_overflow(*a, op.symbol(), *b), pos)
+                            .with_help(error_messages::help_integer_overflow()))
+                    }),
+                    (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+                        Ok(real_operation(op, left_f64, right_f64, |result| Value::Float(result as f32)))
                     }
+                    _ => Ok(real_operation(op, left_f64, right_f64, Value::Double)),
                 }
             }
-            ASTNode::FunctionCall(name, args) => {
-                self.execute_function_call(name, args);
-                // Return the function's return value, or "0" if no return
-                self.variables.get("__return_value__").map(|(v, _)| v.to_string()).unwrap_or_else(|| "0".to_string())
+            // All other combinations are type errors
+            _ => Err(self.operation_type_error(op.symbol(), &left_val, &right_val, pos)),
+        }
+    }
+    
+    fn evaluate_print_expression(&mut self, node: &ASTNode) -> Result<String, RuntimeError> {
+        match node {
+            ASTNode::String(s) => Ok(s.clone()),
+            ASTNode::Integer(n) => Ok(n.to_string()),
+            ASTNode::Number(n) => Ok(n.to_string()),
+            ASTNode::Bool(b) => Ok(b.to_string()),
+            ASTNode::Identifier(name, binding, pos) => {
+                Ok(self.read_variable(name, *binding, pos)?.to_string())
+            }
+            ASTNode::BinaryOp(left, op, right, pos) => {
+                Ok(self.evaluate_binary_op(left, op, right, pos)?.to_string())
+            }
+            ASTNode::UnaryOp(..) | ASTNode::ListLiteral(..) | ASTNode::MapLiteral(..) | ASTNode::FieldAccess(..)
+            | ASTNode::EnumVariant(..) | ASTNode::MatchExpr(..) | ASTNode::Call(..) | ASTNode::Lambda(..) => {
+                Ok(self.evaluate_value(node)?.to_string())
+            }
+            ASTNode::FunctionCall(name, args, pos) => {
+                Ok(self.execute_function_call(name, args, pos)?.to_string())
             }
             ASTNode::TypeConversion(target_type, expr, pos) => {
-                let value = self.evaluate_value(expr);
-                match value.convert_to(target_type) {
-                    Ok(converted_value) => converted_value.to_string(),
-                    Err(error_msg) => {
-                        // Create a proper compiler error with full formatting
-                        let error_msg = format!("Type conversion failed: {}", error_msg);
-                        let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                            .unwrap_or(&String::new()).clone();
-                        
-                        let compiler_error = CompilerError::new(
-                            error_msg,
-                            pos.line,
-                            pos.column,
-                            self.file_path.clone(),
-                            source_line,
-                        )
-                        .with_help(error_messages::help_array_type())
-                        .with_example(error_messages::example_array_type());
-                        
-                        eprintln!("{}", compiler_error);
-                        std::process::exit(1);
-                    }
-                }
+                let value = self.evaluate_value(expr)?;
+                Ok(self.convert_value(value, target_type, pos)?.to_string())
             }
             ASTNode::IfExpr(condition, then_expr, else_expr) => {
-                let cond_val = self.evaluate_expression(condition);
+                let cond_val = self.evaluate_expression(condition)?;
                 if cond_val != 0.0 {
                     self.evaluate_print_expression(then_expr)
                 } else {
                     self.evaluate_print_expression(else_expr)
                 }
             }
-            ASTNode::IndexAccess(array_expr, index_expr, pos) => {
-                let array_val = self.evaluate_value(array_expr);
-                let index_val = self.evaluate_value(index_expr);
-                let index = index_val.to_f64() as usize;
-                
-                match array_val {
-                    Value::Array(arr) => {
-                        if index < arr.len() {
-                            arr[index].to_string()
-                        } else {
-                            // Index out of bounds - report error
-                            let error_msg = error_messages::array_index_out_of_bounds(index, arr.len());
-                            let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                                .unwrap_or(&String::new()).clone();
-                            
-                            let compiler_error = CompilerError::new(
-                                error_msg,
-                                pos.line,
-                                pos.column,
-                                self.file_path.clone(),
-                                source_line,
-                            )
-                            .with_help(error_messages::help_array_bounds())
-                            .with_example(error_messages::example_array_bounds());
-                            
-                            eprintln!("{}", compiler_error);
-                            std::process::exit(1);
-                        }
-                    }
-                    Value::List(lst) => {
-                        if index < lst.len() {
-                            lst[index].to_string()
-                        } else {
-                            // Index out of bounds - report error
-                            let error_msg = error_messages::list_index_out_of_bounds(index, lst.len());
-                            let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                                .unwrap_or(&String::new()).clone();
-                            
-                            let compiler_error = CompilerError::new(
-                                error_msg,
-                                pos.line,
-                                pos.column,
-                                self.file_path.clone(),
-                                source_line,
-                            )
-                            .with_help(error_messages::help_array_bounds())
-                            .with_example(error_messages::example_array_bounds());
-                            
-                            eprintln!("{}", compiler_error);
-                            std::process::exit(1);
-                        }
-                        
-                    }
-                    _ => {
-                        // Not an array or list - return "0"
-                        "0".to_string()
-                    }
-                }
-            }
-            _ => "0".to_string(),
+            ASTNode::IndexAccess(..) => Ok(self.evaluate_value(node)?.to_string()),
+            _ => Ok("0".to_string()),
         }
     }
     
-    fn handle_import(&mut self, filename: &str, pos: &crate::ast::Position) {
+    /// for 循环的范围的起点或终点不是 int
+    fn range_bound_error(&self, bound: &Value, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::range_bound_type(&bound.get_type_name()), pos)
+            .with_help(error_messages::help_range_bound_type()))
+    }
+    
+    fn undefined_identifier_error(&self, name: &str, pos: &Position) -> RuntimeError {
+        let error = create_undefined_identifier_error(
+            name,
+            pos.line,
+            pos.column,
+            &self.source.path,
+            self.source.lines.get(pos.line.saturating_sub(1)).map(String::as_str).unwrap_or("")
+        )
+        .with_note(format!("searched {}", self.env.borrow().describe_chain()));
+        RuntimeError::new(ErrorKind::Name, error)
+    }
+    
+    fn index_error(&self, message: String, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Index, self.error_at(message, pos)
+            .with_help(error_messages::help_array_bounds())
+            .with_example(error_messages::example_array_bounds()))
+    }
+    
+    fn conversion_error(&self, message: String, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Conversion, self.error_at(format!("Type conversion failed: {}", message), pos)
+            .with_help(error_messages::help_array_type())
+            .with_example(error_messages::example_array_type()))
+    }
+    
+    fn operation_type_error(&self, op: &str, left: &Value, right: &Value, pos: &Position) -> RuntimeError {
+        // Create a type error for incompatible operations
+        let error_msg = error_messages::operation_type_error(op, &left.get_type_name(), &right.get_type_name());
+        
+        RuntimeError::new(ErrorKind::Type, self.error_at(error_msg, pos)
+            .with_help(error_messages::help_operation_type())
+            .with_example(error_messages::example_operation_type()))
+    }
+    
+    fn handle_import(&mut self, filename: &str, pos: &Position) -> Result<(), RuntimeError> {
         // 构建完整的文件路径
         let import_path = if filename.ends_with(".ecl") {
             filename.to_string()
@@ -969,26 +1805,22 @@ impl Interpreter {
         }
         
         // 2. 尝试文件目录（当前执行的ECL文件所在目录）
-        if found_path.is_none() {
-            if let Some(current_dir) = Path::new(&self.file_path).parent() {
-                let file_dir_path = current_dir.join(&import_path);
-                tried_paths.push(file_dir_path.display().to_string());
-                if file_dir_path.exists() {
-                    found_path = Some(file_dir_path);
-                }
+        if found_path.is_none() && let Some(current_dir) = Path::new(&self.source.path).parent() {
+            let file_dir_path = current_dir.join(&import_path);
+            tried_paths.push(file_dir_path.display().to_string());
+            if file_dir_path.exists() {
+                found_path = Some(file_dir_path);
             }
         }
         
         // 3. 尝试系统库目录（lib/）
         if found_path.is_none() {
             // 获取可执行文件所在目录
-            if let Ok(exe_path) = env::current_exe() {
-                if let Some(exe_dir) = exe_path.parent() {
-                    let lib_path = exe_dir.join("lib").join(&import_path);
-                    tried_paths.push(lib_path.display().to_string());
-                    if lib_path.exists() {
-                        found_path = Some(lib_path);
-                    }
+            if let Ok(exe_path) = env::current_exe() && let Some(exe_dir) = exe_path.parent() {
+                let lib_path = exe_dir.join("lib").join(&import_path);
+                tried_paths.push(lib_path.display().to_string());
+                if lib_path.exists() {
+                    found_path = Some(lib_path);
                 }
             }
             
@@ -1014,49 +1846,35 @@ impl Interpreter {
                         let lexer = Lexer::new(&content);
                         let mut parser = Parser::new(lexer);
                         
-                        // 保存当前文件路径和源行
-                        let saved_file_path = self.file_path.clone();
-                        let saved_source_lines = self.source_lines.clone();
-                        
-                        // 设置新的文件路径和源行
-                        let import_source_lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-                        self.file_path = path.display().to_string();
-                        self.source_lines = import_source_lines.clone();
+                        // 保存当前文件路径和源行，并设置新的文件路径和源行
+                        let import_source = SourceFile::new(path.display().to_string(), content.lines().map(|s| s.to_string()).collect());
+                        let saved_source = std::mem::replace(&mut self.source, import_source);
                         
-                        // 解析导入的文件
-                        match parser.parse(&self.file_path, &self.source_lines) {
-                            Ok(ast_nodes) => {
-                                // 执行导入的文件中的所有语句
-                                for node in ast_nodes {
-                                    self.evaluate(&node);
+                        // 解析导入的文件，并在全局作用域中执行其中的所有语句
+                        let result = match parser.parse(&self.source.path, &self.source.lines) {
+                            Ok(mut ast_nodes) => match self.resolve(&mut ast_nodes).map_err(|error| RuntimeError::new(ErrorKind::Syntax, error))
+                                .and_then(|()| self.check(&ast_nodes))
+                            {
+                                Ok(()) => {
+                                    let saved_env = std::mem::replace(&mut self.env, Rc::clone(&self.globals));
+                                    let result = self.execute_program(&ast_nodes);
+                                    self.env = saved_env;
+                                    result
                                 }
-                            }
-                            Err(error) => {
-                                eprintln!("{}", error);
-                                std::process::exit(1);
-                            }
-                        }
+                                Err(error) => Err(error),
+                            },
+                            Err(error) => Err(RuntimeError::new(ErrorKind::Syntax, error)),
+                        };
                         
                         // 恢复原来的文件路径和源行
-                        self.file_path = saved_file_path;
-                        self.source_lines = saved_source_lines;
+                        self.source = saved_source;
+                        
+                        result
                     }
                     Err(e) => {
                         // 创建导入错误消息
                         let error_msg = format!("Import error: cannot read file '{}': {}", path.display(), e);
-                        let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                            .unwrap_or(&String::new()).clone();
-                        
-                        let compiler_error = CompilerError::new(
-                            error_msg,
-                            pos.line,
-                            pos.column,
-                            self.file_path.clone(),
-                            source_line,
-                        );
-                        
-                        eprintln!("{}", compiler_error);
-                        std::process::exit(1);
+                        Err(RuntimeError::new(ErrorKind::Import, self.error_at(error_msg, pos)))
                     }
                 }
             }
@@ -1067,74 +1885,265 @@ impl Interpreter {
                     import_path,
                     tried_paths.join("\n  - ")
                 );
-                let source_line = self.source_lines.get(pos.line.saturating_sub(1))
-                    .unwrap_or(&String::new()).clone();
-                
-                let compiler_error = CompilerError::new(
-                    error_msg,
-                    pos.line,
-                    pos.column,
-                    self.file_path.clone(),
-                    source_line,
-                );
-                
-                eprintln!("{}", compiler_error);
-                std::process::exit(1);
+                Err(RuntimeError::new(ErrorKind::Import, self.error_at(error_msg, pos)))
             }
         }
     }
     
-    fn evaluate_expression(&mut self, node: &ASTNode) -> f64 {
-        self.evaluate_value(node).to_f64()
+    fn evaluate_expression(&mut self, node: &ASTNode) -> Result<f64, RuntimeError> {
+        Ok(self.evaluate_value(node)?.to_f64())
     }
     
-    fn execute_function_call(&mut self, name: &str, args: &[ASTNode]) {
-        if let Some(function) = self.functions.get(name).cloned() {
-            if args.len() != function.params.len() {
-                eprintln!("{}", error_messages::function_arity_error(name, function.params.len(), args.len()));
-                return;
-            }
-            
-            // 先评估所有参数（在当前作用域中）
-            let mut arg_values = Vec::new();
-            for arg in args.iter() {
-                arg_values.push(self.evaluate_value(arg));
-            }
-            
-            // Save current variable scope
-            let mut saved_variables = self.variables.clone();
-            
-            // Remove return value from saved scope to start with a clean state
-            saved_variables.remove("__return_value__");
-            
-            // Create a clean scope with only function parameters
-            self.variables.clear();
-            for (param, value) in function.params.iter().zip(arg_values.iter()) {
-                self.variables.insert(param.clone(), (value.clone(), None));
+    fn execute_function_call(&mut self, name: &str, args: &[ASTNode], pos: &Position) -> Result<Value, RuntimeError> {
+        self.check_callable(name, pos)?;
+        
+        // push(list, 1) 等调用修改的是变量本身，第一个参数不求值
+        if Builtin::lookup(name).is_some_and(Builtin::mutates)
+            && let Some(ASTNode::Identifier(target, binding, _)) = args.first()
+        {
+            let mut arg_values = Vec::with_capacity(args.len() - 1);
+            for arg in &args[1..] {
+                arg_values.push(self.evaluate_value(arg)?);
             }
-            
-            // Execute function body
-            for stmt in function.body.iter() {
-                self.evaluate(stmt);
-                
-                // Check if we have a return value
-                if self.variables.contains_key("__return_value__") {
-                    break;
-                }
+            return self.call_in_place(name, target, *binding, arg_values, pos);
+        }
+        
+        // 先评估所有参数（在当前作用域中）
+        let mut arg_values = Vec::new();
+        for arg in args.iter() {
+            arg_values.push(self.evaluate_value(arg)?);
+        }
+        
+        self.call_function(name, arg_values, pos)
+    }
+    
+    /// 调用表达式的值，例如 f(1)(2) 或保存在局部变量中的函数
+    fn execute_call(&mut self, callee: &ASTNode, args: &[ASTNode], pos: &Position) -> Result<Value, RuntimeError> {
+        let callee = self.evaluate_value(callee)?;
+        let mut arg_values = Vec::with_capacity(args.len());
+        for arg in args {
+            arg_values.push(self.evaluate_value(arg)?);
+        }
+        self.call_value(callee, arg_values, pos)
+    }
+    
+    /// lambda 的外层作用域是创建它时的当前作用域
+    fn make_lambda(&self, function: Rc<Function>) -> Value {
+        Value::Function(Rc::new(Closure { name: None, function, env: Rc::clone(&self.env) }))
+    }
+    
+    /// 全局变量中的函数值。与具名函数和原生函数一样，它优先于同名的内置函数
+    fn global_function(&self, name: &str) -> Option<Value> {
+        self.get_global(name).filter(|value| matches!(value, Value::Function(_)))
+    }
+    
+    /// 同名的脚本函数、结构体、原生函数或全局变量中的函数值优先于内置函数
+    fn shadows_builtin(&self, name: &str) -> bool {
+        self.functions.contains_key(name) || self.structs.contains_key(name) || self.natives.contains_key(name)
+            || self.global_function(name).is_some()
+    }
+    
+    /// 调用前检查函数或结构体是否存在，使未定义函数的错误先于参数中的错误报告
+    fn check_callable(&self, name: &str, pos: &Position) -> Result<(), RuntimeError> {
+        if self.shadows_builtin(name) || Builtin::lookup(name).is_some() {
+            return Ok(());
+        }
+        match self.get_global(name) {
+            Some(Value::Function(_)) => Ok(()),
+            Some(other) => Err(self.not_callable_error(&other, pos)),
+            None => Err(RuntimeError::new(ErrorKind::Name, self.error_at(error_messages::undefined_function(name), pos))),
+        }
+    }
+    
+    /// 使用已求值的参数按名称调用ECL函数、结构体构造函数、原生函数或全局变量中的函数值，
+    /// 返回函数的返回值（没有 return 时为 0）
+    fn call_function(&mut self, name: &str, arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        if !self.functions.contains_key(name) && let Some(struct_type) = self.structs.get(name) {
+            return self.construct_struct(Rc::clone(struct_type), arg_values, pos);
+        }
+        
+        if let Some(function) = self.functions.get(name) {
+            let function = Rc::clone(function);
+            // 具名函数的外层是全局作用域：函数可以读取全局变量，但看不到调用方的局部变量
+            let globals = Rc::clone(&self.globals);
+            return self.invoke(&name.into(), &function, &globals, arg_values, pos);
+        }
+        if let Some(native) = self.natives.get(name).cloned() {
+            return self.call_native(name, &native, &arg_values, pos);
+        }
+        if let Some(callee) = self.global_function(name) {
+            return self.call_value(callee, arg_values, pos);
+        }
+        if let Some(builtin) = Builtin::lookup(name) {
+            self.check_builtin_arity(builtin, arg_values.len(), pos)?;
+            return builtin.call(&arg_values).map_err(|(kind, message)| self.builtin_error(builtin, kind, message, pos));
+        }
+        match self.get_global(name) {
+            Some(callee) => self.call_value(callee, arg_values, pos),
+            None => Err(RuntimeError::new(ErrorKind::Name, self.error_at(error_messages::undefined_function(name), pos))),
+        }
+    }
+    
+    /// 调用 push、pop 等修改第一个参数的内置函数，直接修改变量 `target` 中的列表。
+    /// 同名的脚本函数、原生函数和全局变量中的函数值优先，此时按普通调用传入变量的值。
+    fn call_in_place(&mut self, name: &str, target: &str, binding: Binding, mut arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        let builtin = match Builtin::lookup(name) {
+            Some(builtin) if !self.shadows_builtin(name) => builtin,
+            _ => {
+                arg_values.insert(0, self.read_variable(target, binding, pos)?);
+                return self.call_function(name, arg_values, pos);
             }
-            
-            // Get return value before restoring scope
-            let return_value = self.variables.get("__return_value__").map(|(v, _)| v.clone());
-            
-            // Restore variable scope
-            self.variables = saved_variables;
-            
-            // Set return value in the restored scope
-            if let Some(value) = return_value {
-                self.variables.insert("__return_value__".to_string(), (value, None));
+        };
+        
+        let called = self.with_variable_mut(target, binding, |variable| {
+            self.check_builtin_arity(builtin, arg_values.len() + 1, pos)?;
+            let call = |value: &mut Value| builtin.call_in_place(value, &arg_values)
+                .map_err(|(kind, message)| self.builtin_error(builtin, kind, message, pos));
+            if variable.constant {
+                // 导入的常量只能在运行时检查，内置函数本身的错误先报告
+                call(&mut variable.value.clone())?;
+                return Err(self.assign_to_constant_error(target, binding, pos));
             }
+            call(&mut variable.value)
+        });
+        if let Some(result) = called {
+            return result;
+        }
+        // 变量不存在时，名称可能指的是具名函数
+        let mut value = self.read_variable(target, binding, pos)?;
+        self.check_builtin_arity(builtin, arg_values.len() + 1, pos)?;
+        builtin.call_in_place(&mut value, &arg_values).map_err(|(kind, message)| self.builtin_error(builtin, kind, message, pos))
+    }
+    
+    fn check_builtin_arity(&self, builtin: Builtin, count: usize, pos: &Position) -> Result<(), RuntimeError> {
+        if count == builtin.arity() || (builtin.variadic() && count > builtin.arity()) {
+            return Ok(());
+        }
+        let error_msg = if builtin.variadic() {
+            error_messages::function_min_arity_error(builtin.name(), builtin.arity(), count)
         } else {
-            eprintln!("{}", error_messages::undefined_function(name));
+            error_messages::function_arity_error(builtin.name(), builtin.arity(), count)
+        };
+        Err(self.builtin_error(builtin, ErrorKind::Argument, error_msg, pos))
+    }
+    
+    fn builtin_error(&self, builtin: Builtin, kind: ErrorKind, message: String, pos: &Position) -> RuntimeError {
+        RuntimeError::new(kind, self.error_at(message, pos)
+            .with_help(error_messages::help_builtin_usage(builtin.usage())))
+    }
+    
+    /// 调用一个已求值的函数值
+    fn call_value(&mut self, callee: Value, arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Function(closure) => self.invoke(&closure.name(), &closure.function, &closure.env, arg_values, pos),
+            other => Err(self.not_callable_error(&other, pos)),
+        }
+    }
+    
+    fn not_callable_error(&self, value: &Value, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Type, self.error_at(error_messages::not_callable(&value.get_type_name()), pos)
+            .with_help(error_messages::help_not_callable()))
+    }
+    
+    /// 在外层作用域为 `parent` 的新函数作用域中执行函数体
+    fn invoke(&mut self, name: &Rc<str>, function: &Function, parent: &Env, arg_values: Vec<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        self.check_call_depth(name, pos)?;
+        let function_env = self.function_scope(name, function, parent, arg_values.into_iter(), pos)?;
+        
+        // 函数体中的错误指向声明函数的文件，它可能是导入的文件
+        let saved_env = std::mem::replace(&mut self.env, function_env);
+        let saved_source = std::mem::replace(&mut self.source, Rc::clone(&function.source));
+        self.call_depth += 1;
+        let result = match &function.body {
+            FunctionBody::Ast(body) => self.execute_function_body(body),
+            FunctionBody::Bytecode(chunk) => self.run_chunk(chunk),
+        };
+        self.call_depth -= 1;
+        self.source = saved_source;
+        self.env = saved_env;
+        
+        self.return_value(name, &function.signature, result?, pos)
+    }
+    
+    /// 无穷递归在调用层数达到上限时报告错误
+    fn check_call_depth(&self, name: &str, pos: &Position) -> Result<(), RuntimeError> {
+        if self.call_depth < MAX_CALL_DEPTH {
+            return Ok(());
         }
+        Err(RuntimeError::new(ErrorKind::Runtime, self.error_at(error_messages::call_depth_exceeded(name, MAX_CALL_DEPTH), pos)
+            .with_help(error_messages::help_call_depth_exceeded())))
     }
-}
\ No newline at end of file
+    
+    /// 调用函数时新建的函数作用域：检查参数个数，参数依次占用前几个槽位，带类型的参数先转换为声明的类型
+    // 不内联：递归的每一层都经过 invoke，这里的局部变量会增大它的栈帧
+    #[inline(never)]
+    fn function_scope(&self, name: &Rc<str>, function: &Function, parent: &Env, arg_values: impl ExactSizeIterator<Item = Value>, pos: &Position) -> Result<Env, RuntimeError> {
+        if arg_values.len() != function.params.len() {
+            let error_msg = error_messages::function_arity_error(name, function.params.len(), arg_values.len());
+            return Err(RuntimeError::new(ErrorKind::Argument, self.error_at(error_msg, pos)));
+        }
+        
+        let function_env = Environment::new_child(parent, ScopeKind::Function(Rc::clone(name)));
+        let signature = &function.signature;
+        for (slot, value) in arg_values.enumerate() {
+            let param_type = signature.param_types.get(slot).cloned().flatten();
+            let value = match &param_type {
+                Some(param_type) => value.convert_to(param_type).map_err(|reason| {
+                    let error_msg = error_messages::argument_type_error(name, &function.params[slot], &param_type.to_string(), &reason);
+                    self.signature_error(error_msg, error_messages::help_argument_type(&param_type.to_string()), name, signature, pos)
+                })?,
+                None => value,
+            };
+            function_env.borrow_mut().define_slot(slot, Variable::new(value, param_type));
+        }
+        Ok(function_env)
+    }
+    
+    /// 函数体执行结束后的返回值：转换为声明的返回类型，没有执行 return 时返回默认值
+    // 与 function_scope 一样不内联到 invoke 中
+    #[inline(never)]
+    fn return_value(&self, name: &str, signature: &Signature, result: Option<Value>, pos: &Position) -> Result<Value, RuntimeError> {
+        let Some(return_type) = &signature.return_type else {
+            return Ok(result.unwrap_or(Value::Double(0.0)));
+        };
+        // 声明了返回类型的函数没有执行 return 时返回该类型的默认值
+        match result {
+            Some(return_value) => return_value.convert_to(return_type).map_err(|reason| {
+                let error_msg = error_messages::return_type_error(name, &return_type.to_string(), &reason);
+                self.signature_error(error_msg, error_messages::help_return_type(&return_type.to_string()), name, signature, pos)
+            }),
+            None => Ok(default_value(return_type)),
+        }
+    }
+    
+    /// 参数或返回值与函数声明的类型不符：错误指向调用位置，提示指出函数的声明位置
+    fn signature_error(&self, message: String, help: String, name: &str, signature: &Signature, pos: &Position) -> RuntimeError {
+        RuntimeError::new(ErrorKind::Type, self.error_at(message, pos)
+            .with_help(help)
+            .with_note(error_messages::function_declared_at(name, &signature.origin)))
+    }
+    
+    /// 逐条执行函数体中的语句，直到出错或执行了 return
+    fn execute_function_body(&mut self, body: &[ASTNode]) -> Result<Option<Value>, RuntimeError> {
+        for stmt in body {
+            if let ControlFlow::Return(value) = self.evaluate(stmt)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+    
+    // 与 construct_struct 一样不内联到 call_function 中
+    #[inline(never)]
+    fn call_native(&mut self, name: &str, native: &NativeFunction, arg_values: &[Value], pos: &Position) -> Result<Value, RuntimeError> {
+        if arg_values.len() != native.arity {
+            let error_msg = error_messages::function_arity_error(name, native.arity, arg_values.len());
+            return Err(RuntimeError::new(ErrorKind::Argument, self.error_at(error_msg, pos)));
+        }
+        
+        (native.func)(arg_values).map_err(|message| {
+            RuntimeError::new(ErrorKind::Runtime, self.error_at(error_messages::native_function_error(name, &message), pos)
+                .with_help(error_messages::help_native_function()))
+        })
+    }
+}
@@ -0,0 +1,555 @@
+//! 用 Rust 实现的内置函数。push、pop、insert 和 remove 原地修改作为第一个参数传入的列表变量，
+//! 由解释器负责读取该变量并把修改后的值写回。字符串函数的下标和长度都按字符（而不是字节）计算。
+//! 数学函数的参数可以是任意数字，超出定义域时报错而不是返回 NaN。
+
+use crate::error::{error_messages, ErrorKind};
+use super::{integer_operation, values_equal, BinaryOp, Value};
+
+/// 预先定义在全局作用域中、不能赋值的数学常量
+pub(crate) const CONSTANTS: [(&str, f64); 3] = [
+    ("PI", std::f64::consts::PI),
+    ("E", std::f64::consts::E),
+    ("TAU", std::f64::consts::TAU),
+];
+
+/// 内置函数出错时的错误种类和错误信息，由解释器加上位置和用法提示
+pub(crate) type BuiltinError = (ErrorKind, String);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Builtin {
+    Len,
+    Push,
+    Pop,
+    Insert,
+    Remove,
+    Contains,
+    IndexOf,
+    Range,
+    Substring,
+    Split,
+    Join,
+    Replace,
+    Trim,
+    Upper,
+    Lower,
+    StartsWith,
+    EndsWith,
+    Find,
+    Repeat,
+    Chars,
+    Format,
+    Sqrt,
+    Pow,
+    Exp,
+    Ln,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Atan2,
+    Floor,
+    Ceil,
+    Round,
+    Abs,
+    Min,
+    Max,
+    Hypot,
+}
+
+impl Builtin {
+    pub(crate) fn lookup(name: &str) -> Option<Builtin> {
+        match name {
+            "len" => Some(Builtin::Len),
+            "push" => Some(Builtin::Push),
+            "pop" => Some(Builtin::Pop),
+            "insert" => Some(Builtin::Insert),
+            "remove" => Some(Builtin::Remove),
+            "contains" => Some(Builtin::Contains),
+            "index_of" => Some(Builtin::IndexOf),
+            "range" => Some(Builtin::Range),
+            "substring" => Some(Builtin::Substring),
+            "split" => Some(Builtin::Split),
+            "join" => Some(Builtin::Join),
+            "replace" => Some(Builtin::Replace),
+            "trim" => Some(Builtin::Trim),
+            "upper" => Some(Builtin::Upper),
+            "lower" => Some(Builtin::Lower),
+            "starts_with" => Some(Builtin::StartsWith),
+            "ends_with" => Some(Builtin::EndsWith),
+            "find" => Some(Builtin::Find),
+            "repeat" => Some(Builtin::Repeat),
+            "chars" => Some(Builtin::Chars),
+            "format" => Some(Builtin::Format),
+            "sqrt" => Some(Builtin::Sqrt),
+            "pow" => Some(Builtin::Pow),
+            "exp" => Some(Builtin::Exp),
+            "ln" => Some(Builtin::Ln),
+            "log" => Some(Builtin::Log),
+            "sin" => Some(Builtin::Sin),
+            "cos" => Some(Builtin::Cos),
+            "tan" => Some(Builtin::Tan),
+            "asin" => Some(Builtin::Asin),
+            "acos" => Some(Builtin::Acos),
+            "atan" => Some(Builtin::Atan),
+            "atan2" => Some(Builtin::Atan2),
+            "floor" => Some(Builtin::Floor),
+            "ceil" => Some(Builtin::Ceil),
+            "round" => Some(Builtin::Round),
+            "abs" => Some(Builtin::Abs),
+            "min" => Some(Builtin::Min),
+            "max" => Some(Builtin::Max),
+            "hypot" => Some(Builtin::Hypot),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Builtin::Len => "len",
+            Builtin::Push => "push",
+            Builtin::Pop => "pop",
+            Builtin::Insert => "insert",
+            Builtin::Remove => "remove",
+            Builtin::Contains => "contains",
+            Builtin::IndexOf => "index_of",
+            Builtin::Range => "range",
+            Builtin::Substring => "substring",
+            Builtin::Split => "split",
+            Builtin::Join => "join",
+            Builtin::Replace => "replace",
+            Builtin::Trim => "trim",
+            Builtin::Upper => "upper",
+            Builtin::Lower => "lower",
+            Builtin::StartsWith => "starts_with",
+            Builtin::EndsWith => "ends_with",
+            Builtin::Find => "find",
+            Builtin::Repeat => "repeat",
+            Builtin::Chars => "chars",
+            Builtin::Format => "format",
+            Builtin::Sqrt => "sqrt",
+            Builtin::Pow => "pow",
+            Builtin::Exp => "exp",
+            Builtin::Ln => "ln",
+            Builtin::Log => "log",
+            Builtin::Sin => "sin",
+            Builtin::Cos => "cos",
+            Builtin::Tan => "tan",
+            Builtin::Asin => "asin",
+            Builtin::Acos => "acos",
+            Builtin::Atan => "atan",
+            Builtin::Atan2 => "atan2",
+            Builtin::Floor => "floor",
+            Builtin::Ceil => "ceil",
+            Builtin::Round => "round",
+            Builtin::Abs => "abs",
+            Builtin::Min => "min",
+            Builtin::Max => "max",
+            Builtin::Hypot => "hypot",
+        }
+    }
+
+    /// 参数个数，可变参数的函数是最少的参数个数
+    pub(crate) fn arity(self) -> usize {
+        match self {
+            Builtin::Len | Builtin::Pop | Builtin::Trim | Builtin::Upper | Builtin::Lower | Builtin::Chars | Builtin::Format => 1,
+            Builtin::Sqrt | Builtin::Exp | Builtin::Ln | Builtin::Sin | Builtin::Cos | Builtin::Tan | Builtin::Asin
+            | Builtin::Acos | Builtin::Atan | Builtin::Floor | Builtin::Ceil | Builtin::Round | Builtin::Abs => 1,
+            Builtin::Push | Builtin::Remove | Builtin::Contains | Builtin::IndexOf | Builtin::Split | Builtin::Join
+            | Builtin::StartsWith | Builtin::EndsWith | Builtin::Find | Builtin::Repeat => 2,
+            Builtin::Pow | Builtin::Log | Builtin::Atan2 | Builtin::Min | Builtin::Max | Builtin::Hypot => 2,
+            Builtin::Insert | Builtin::Range | Builtin::Substring | Builtin::Replace => 3,
+        }
+    }
+
+    /// 是否在最少的参数之后接受任意多个参数
+    pub(crate) fn variadic(self) -> bool {
+        self == Builtin::Format
+    }
+
+    /// 是否原地修改第一个参数
+    pub(crate) fn mutates(self) -> bool {
+        matches!(self, Builtin::Push | Builtin::Pop | Builtin::Insert | Builtin::Remove)
+    }
+
+    /// 出错时在帮助信息中展示的用法
+    pub(crate) fn usage(self) -> &'static str {
+        match self {
+            Builtin::Len => "len(value) returns the number of elements of a list, array or map, or the characters of a str",
+            Builtin::Push => "push(list, value) appends value and returns the new length",
+            Builtin::Pop => "pop(list) removes and returns the last element",
+            Builtin::Insert => "insert(list, index, value) inserts value before index and returns the new length",
+            Builtin::Remove => "remove(list, index) removes and returns the element at index",
+            Builtin::Contains => "contains(collection, value) checks a list or array element, a map key or a substring",
+            Builtin::IndexOf => "index_of(collection, value) returns the first index of value in a list, array or str, or -1",
+            Builtin::Range => "range(start, end, step) returns the list of ints from start up to, but not including, end",
+            Builtin::Substring => "substring(s, start, end) returns the characters from start up to, but not including, end",
+            Builtin::Split => "split(s, separator) returns the list of parts between separators",
+            Builtin::Join => "join(list, separator) joins the elements of a list or array into a str",
+            Builtin::Replace => "replace(s, from, to) replaces every occurrence of from with to",
+            Builtin::Trim => "trim(s) removes leading and trailing whitespace",
+            Builtin::Upper => "upper(s) converts a str to upper case",
+            Builtin::Lower => "lower(s) converts a str to lower case",
+            Builtin::StartsWith => "starts_with(s, prefix) checks whether s begins with prefix",
+            Builtin::EndsWith => "ends_with(s, suffix) checks whether s ends with suffix",
+            Builtin::Find => "find(s, part) returns the character index of the first occurrence of part, or -1",
+            Builtin::Repeat => "repeat(s, count) returns s repeated count times",
+            Builtin::Chars => "chars(s) returns the list of characters of a str",
+            Builtin::Format => "format(template, values...) replaces each {} in template with the next value, {{ and }} are literal braces",
+            Builtin::Sqrt => "sqrt(x) returns the square root of a non-negative number",
+            Builtin::Pow => "pow(base, exponent) raises base to exponent, like base ** exponent",
+            Builtin::Exp => "exp(x) returns e raised to x",
+            Builtin::Ln => "ln(x) returns the natural logarithm of a positive number",
+            Builtin::Log => "log(x, base) returns the logarithm of a positive number in a positive base other than 1",
+            Builtin::Sin => "sin(x) returns the sine of x radians",
+            Builtin::Cos => "cos(x) returns the cosine of x radians",
+            Builtin::Tan => "tan(x) returns the tangent of x radians",
+            Builtin::Asin => "asin(x) returns the arcsine in radians of a number between -1 and 1",
+            Builtin::Acos => "acos(x) returns the arccosine in radians of a number between -1 and 1",
+            Builtin::Atan => "atan(x) returns the arctangent of x in radians",
+            Builtin::Atan2 => "atan2(y, x) returns the angle in radians of the point (x, y)",
+            Builtin::Floor => "floor(x) rounds down to an int",
+            Builtin::Ceil => "ceil(x) rounds up to an int",
+            Builtin::Round => "round(x) rounds to the nearest int, halfway cases away from zero",
+            Builtin::Abs => "abs(x) returns the absolute value, ints stay ints",
+            Builtin::Min => "min(a, b) returns the smaller number, ints stay ints",
+            Builtin::Max => "max(a, b) returns the larger number, ints stay ints",
+            Builtin::Hypot => "hypot(x, y) returns the length of the hypotenuse sqrt(x * x + y * y)",
+        }
+    }
+
+    /// 调用不修改参数的内置函数，参数个数已经检查过
+    pub(crate) fn call(self, args: &[Value]) -> Result<Value, BuiltinError> {
+        match self {
+            Builtin::Len => len(&args[0]),
+            Builtin::Contains => contains(self, &args[0], &args[1]),
+            Builtin::IndexOf => index_of(self, &args[0], &args[1]),
+            Builtin::Range => range(self, &args[0], &args[1], &args[2]),
+            Builtin::Format => format(self, args),
+            Builtin::Join => join(self, &args[0], &args[1]),
+            Builtin::Abs | Builtin::Min | Builtin::Max | Builtin::Pow | Builtin::Floor | Builtin::Ceil | Builtin::Round
+                if args.iter().all(|arg| matches!(arg, Value::Int(_))) => integer_function(self, args),
+            Builtin::Sqrt | Builtin::Pow | Builtin::Exp | Builtin::Ln | Builtin::Log | Builtin::Sin | Builtin::Cos
+            | Builtin::Tan | Builtin::Asin | Builtin::Acos | Builtin::Atan | Builtin::Atan2 | Builtin::Floor | Builtin::Ceil
+            | Builtin::Round | Builtin::Abs | Builtin::Min | Builtin::Max | Builtin::Hypot => math_function(self, args),
+            Builtin::Substring | Builtin::Split | Builtin::Replace | Builtin::Trim | Builtin::Upper | Builtin::Lower
+            | Builtin::StartsWith | Builtin::EndsWith | Builtin::Find | Builtin::Repeat | Builtin::Chars => {
+                let s = string_argument(self, &args[0])?;
+                string_function(self, s, &args[1..])
+            }
+            Builtin::Push | Builtin::Pop | Builtin::Insert | Builtin::Remove => {
+                Err((ErrorKind::Argument, error_messages::builtin_needs_variable(self.name())))
+            }
+        }
+    }
+
+    /// 调用修改 `target` 的内置函数，`args` 是除第一个参数以外的参数
+    pub(crate) fn call_in_place(self, target: &mut Value, args: &[Value]) -> Result<Value, BuiltinError> {
+        let list = match target {
+            Value::List(list) => list,
+            other => return Err(argument_type_error(self, "a list", other)),
+        };
+        match self {
+            Builtin::Push => {
+                list.push(args[0].clone());
+                Ok(Value::Int(list.len() as i64))
+            }
+            Builtin::Pop => list.pop().ok_or_else(|| (ErrorKind::Index, error_messages::pop_empty_list())),
+            Builtin::Insert => {
+                let index = list_index(self, &args[0], list.len())?;
+                list.insert(index, args[1].clone());
+                Ok(Value::Int(list.len() as i64))
+            }
+            Builtin::Remove => {
+                let index = list_index(self, &args[0], list.len())?;
+                Ok(list.remove(index))
+            }
+            _ => self.call(args),
+        }
+    }
+}
+
+fn argument_type_error(builtin: Builtin, expected: &str, actual: &Value) -> BuiltinError {
+    (ErrorKind::Type, error_messages::builtin_argument_type(builtin.name(), expected, &actual.get_type_name()))
+}
+
+/// 把下标参数转换为列表下标，insert 可以使用等于列表长度的下标在末尾插入
+fn list_index(builtin: Builtin, index: &Value, length: usize) -> Result<usize, BuiltinError> {
+    let Value::Int(index) = index else {
+        return Err(argument_type_error(builtin, "an int index", index));
+    };
+    let bound = if builtin == Builtin::Insert { length + 1 } else { length };
+    match usize::try_from(*index) {
+        Ok(index) if index < bound => Ok(index),
+        _ => Err((ErrorKind::Index, error_messages::builtin_index_out_of_bounds(*index, length))),
+    }
+}
+
+fn len(value: &Value) -> Result<Value, BuiltinError> {
+    let length = match value {
+        Value::Str(s) => s.chars().count(),
+        Value::Array(items) | Value::List(items) => items.len(),
+        Value::Map(entries) => entries.len(),
+        other => return Err(argument_type_error(Builtin::Len, "a str, array, list or map", other)),
+    };
+    Ok(Value::Int(length as i64))
+}
+
+fn contains(builtin: Builtin, collection: &Value, needle: &Value) -> Result<Value, BuiltinError> {
+    let found = match collection {
+        Value::Array(items) | Value::List(items) => items.iter().any(|item| values_equal(item, needle) == Some(true)),
+        Value::Map(entries) => entries.iter().any(|(key, _)| key == needle),
+        Value::Str(s) => match needle {
+            Value::Str(part) => s.contains(part.as_str()),
+            other => return Err(argument_type_error(builtin, "a str to search for in a str", other)),
+        },
+        other => return Err(argument_type_error(builtin, "a str, array, list or map", other)),
+    };
+    Ok(Value::Bool(found))
+}
+
+fn index_of(builtin: Builtin, collection: &Value, needle: &Value) -> Result<Value, BuiltinError> {
+    let index = match collection {
+        Value::Array(items) | Value::List(items) => items.iter().position(|item| values_equal(item, needle) == Some(true)),
+        // 字符串的下标按字符计算
+        Value::Str(s) => match needle {
+            Value::Str(part) => char_index(s, part),
+            other => return Err(argument_type_error(builtin, "a str to search for in a str", other)),
+        },
+        other => return Err(argument_type_error(builtin, "a str, array or list", other)),
+    };
+    Ok(Value::Int(index.map_or(-1, |index| index as i64)))
+}
+
+/// `part` 在 `s` 中第一次出现的字符下标
+fn char_index(s: &str, part: &str) -> Option<usize> {
+    s.find(part).map(|byte| s[..byte].chars().count())
+}
+
+fn range(builtin: Builtin, start: &Value, end: &Value, step: &Value) -> Result<Value, BuiltinError> {
+    let mut bounds = [0; 3];
+    for (bound, value) in bounds.iter_mut().zip([start, end, step]) {
+        match value {
+            Value::Int(n) => *bound = *n,
+            other => return Err(argument_type_error(builtin, "int arguments", other)),
+        }
+    }
+    let [start, end, step] = bounds;
+    if step == 0 {
+        return Err((ErrorKind::Argument, error_messages::range_zero_step()));
+    }
+
+    let mut items = Vec::new();
+    let mut current = start;
+    while (step > 0 && current < end) || (step < 0 && current > end) {
+        items.push(Value::Int(current));
+        match current.checked_add(step) {
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    Ok(Value::List(items))
+}
+
+fn string_argument(builtin: Builtin, value: &Value) -> Result<&str, BuiltinError> {
+    match value {
+        Value::Str(s) => Ok(s),
+        other => Err(argument_type_error(builtin, "a str", other)),
+    }
+}
+
+/// 不能为空的查找或分隔字符串
+fn pattern_argument(builtin: Builtin, value: &Value) -> Result<&str, BuiltinError> {
+    match string_argument(builtin, value)? {
+        "" => Err((ErrorKind::Argument, error_messages::builtin_empty_pattern(builtin.name()))),
+        pattern => Ok(pattern),
+    }
+}
+
+fn count_argument(builtin: Builtin, value: &Value) -> Result<usize, BuiltinError> {
+    match value {
+        Value::Int(n) => usize::try_from(*n).map_err(|_| (ErrorKind::Argument, error_messages::builtin_negative_argument(builtin.name(), *n))),
+        other => Err(argument_type_error(builtin, "an int", other)),
+    }
+}
+
+/// 第一个参数为字符串 `s` 的函数，`args` 是其余的参数
+fn string_function(builtin: Builtin, s: &str, args: &[Value]) -> Result<Value, BuiltinError> {
+    let value = match builtin {
+        Builtin::Substring => {
+            let (start, end) = (count_argument(builtin, &args[0])?, count_argument(builtin, &args[1])?);
+            let length = s.chars().count();
+            if start > end || end > length {
+                return Err((ErrorKind::Index, error_messages::substring_out_of_bounds(start, end, length)));
+            }
+            Value::Str(s.chars().skip(start).take(end - start).collect())
+        }
+        Builtin::Split => {
+            let separator = pattern_argument(builtin, &args[0])?;
+            Value::List(s.split(separator).map(|part| Value::Str(part.to_string())).collect())
+        }
+        Builtin::Replace => {
+            let from = pattern_argument(builtin, &args[0])?;
+            Value::Str(s.replace(from, string_argument(builtin, &args[1])?))
+        }
+        Builtin::Trim => Value::Str(s.trim().to_string()),
+        Builtin::Upper => Value::Str(s.to_uppercase()),
+        Builtin::Lower => Value::Str(s.to_lowercase()),
+        Builtin::StartsWith => Value::Bool(s.starts_with(string_argument(builtin, &args[0])?)),
+        Builtin::EndsWith => Value::Bool(s.ends_with(string_argument(builtin, &args[0])?)),
+        Builtin::Find => {
+            let index = char_index(s, string_argument(builtin, &args[0])?);
+            Value::Int(index.map_or(-1, |index| index as i64))
+        }
+        Builtin::Repeat => Value::Str(s.repeat(count_argument(builtin, &args[0])?)),
+        Builtin::Chars => Value::List(s.chars().map(|c| Value::Str(c.to_string())).collect()),
+        _ => unreachable!("{} does not take a str as its first argument", builtin.name()),
+    };
+    Ok(value)
+}
+
+fn join(builtin: Builtin, items: &Value, separator: &Value) -> Result<Value, BuiltinError> {
+    let separator = string_argument(builtin, separator)?;
+    match items {
+        Value::Array(items) | Value::List(items) => {
+            let parts: Vec<String> = items.iter().map(Value::to_string).collect();
+            Ok(Value::Str(parts.join(separator)))
+        }
+        other => Err(argument_type_error(builtin, "a list or array", other)),
+    }
+}
+
+/// 依次用参数替换模板中的 `{}`，`{{` 和 `}}` 表示花括号本身
+fn format(builtin: Builtin, args: &[Value]) -> Result<Value, BuiltinError> {
+    let template = string_argument(builtin, &args[0])?;
+    let values = &args[1..];
+    let mut result = String::new();
+    let mut used = 0;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                result.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if let Some(value) = values.get(used) {
+                    result.push_str(&value.to_string());
+                }
+                used += 1;
+            }
+            ('{' | '}', _) => return Err((ErrorKind::Argument, error_messages::format_unmatched_brace())),
+            _ => result.push(c),
+        }
+    }
+    if used != values.len() {
+        return Err((ErrorKind::Argument, error_messages::format_argument_count(used, values.len())));
+    }
+    Ok(Value::Str(result))
+}
+
+fn number_argument(builtin: Builtin, value: &Value) -> Result<f64, BuiltinError> {
+    match value {
+        Value::Int(_) | Value::Float(_) | Value::Double(_) => Ok(value.to_f64()),
+        other => Err(argument_type_error(builtin, "a number", other)),
+    }
+}
+
+/// 负数的小数次幂没有实数结果
+fn pow_in_domain(x: f64, y: f64) -> bool {
+    x >= 0.0 || y.fract() == 0.0
+}
+
+/// 0 的负数次幂与 `0 ** -1` 一样是除以 0
+fn check_zero_power(x: f64, y: f64) -> Result<(), BuiltinError> {
+    if x == 0.0 && y < 0.0 {
+        return Err((ErrorKind::Arithmetic, error_messages::division_by_zero("**")));
+    }
+    Ok(())
+}
+
+fn domain_error(builtin: Builtin, args: &[Value]) -> BuiltinError {
+    let args: Vec<String> = args.iter().map(Value::to_string).collect();
+    (ErrorKind::Math, error_messages::math_domain_error(builtin.name(), &args.join(", ")))
+}
+
+/// 参数都是整数时，abs、min、max、pow 的结果仍是整数，取整函数直接返回参数
+fn integer_function(builtin: Builtin, args: &[Value]) -> Result<Value, BuiltinError> {
+    let int = |index: usize| match args[index] {
+        Value::Int(n) => n,
+        _ => unreachable!("integer_function is only called with int arguments"),
+    };
+    let value = match builtin {
+        Builtin::Abs => Value::Int(int(0).checked_abs().ok_or_else(|| (ErrorKind::Arithmetic, error_messages::int_out_of_range(&args[0].to_string())))?),
+        Builtin::Min => Value::Int(int(0).min(int(1))),
+        Builtin::Max => Value::Int(int(0).max(int(1))),
+        // 整数的整数次幂总有实数结果，只有 0 的负数次幂需要检查
+        Builtin::Pow => {
+            check_zero_power(int(0) as f64, int(1) as f64)?;
+            integer_operation(BinaryOp::Pow, int(0), int(1)).ok_or_else(|| (ErrorKind::Arithmetic, error_messages::integer_overflow(int(0), "**", int(1))))?
+        }
+        _ => args[0].clone(),
+    };
+    Ok(value)
+}
+
+fn math_function(builtin: Builtin, args: &[Value]) -> Result<Value, BuiltinError> {
+    let x = number_argument(builtin, &args[0])?;
+    let y = match args.get(1) {
+        Some(arg) => number_argument(builtin, arg)?,
+        None => 0.0,
+    };
+    if builtin == Builtin::Pow {
+        check_zero_power(x, y)?;
+    }
+    let in_domain = match builtin {
+        Builtin::Sqrt => x >= 0.0,
+        Builtin::Ln => x > 0.0,
+        Builtin::Log => x > 0.0 && y > 0.0 && y != 1.0,
+        Builtin::Asin | Builtin::Acos => (-1.0..=1.0).contains(&x),
+        Builtin::Pow => pow_in_domain(x, y),
+        _ => true,
+    };
+    if !in_domain {
+        return Err(domain_error(builtin, args));
+    }
+
+    let result = match builtin {
+        Builtin::Sqrt => x.sqrt(),
+        Builtin::Pow => x.powf(y),
+        Builtin::Exp => x.exp(),
+        Builtin::Ln => x.ln(),
+        // 常用的底数使用更精确的实现，例如 log(1000, 10) 正好是 3
+        Builtin::Log if y == 10.0 => x.log10(),
+        Builtin::Log if y == 2.0 => x.log2(),
+        Builtin::Log => x.log(y),
+        Builtin::Sin => x.sin(),
+        Builtin::Cos => x.cos(),
+        Builtin::Tan => x.tan(),
+        Builtin::Asin => x.asin(),
+        Builtin::Acos => x.acos(),
+        Builtin::Atan => x.atan(),
+        Builtin::Atan2 => x.atan2(y),
+        Builtin::Hypot => x.hypot(y),
+        Builtin::Abs => x.abs(),
+        Builtin::Min => x.min(y),
+        Builtin::Max => x.max(y),
+        Builtin::Floor | Builtin::Ceil | Builtin::Round => {
+            let rounded = match builtin {
+                Builtin::Floor => x.floor(),
+                Builtin::Ceil => x.ceil(),
+                _ => x.round(),
+            };
+            // i64::MAX 转换为 f64 后是 2^63，不能再转换回 i64
+            if !(-(2f64.powi(63))..2f64.powi(63)).contains(&rounded) {
+                return Err((ErrorKind::Conversion, error_messages::int_out_of_range(&args[0].to_string())));
+            }
+            return Ok(Value::Int(rounded as i64));
+        }
+        _ => unreachable!("{} is not a math function", builtin.name()),
+    };
+    Ok(Value::Double(result))
+}
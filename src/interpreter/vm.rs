@@ -0,0 +1,433 @@
+//! 执行 [`Chunk`] 的栈式虚拟机。变量、函数和诊断信息与遍历语法树的解释器共用同一套实现，
+//! 两种执行引擎只在语句和表达式的调度方式上不同。字节码函数之间的调用使用虚拟机自己的调用帧，
+//! 不占用 Rust 的调用栈。
+
+use std::rc::Rc;
+use crate::ast::Position;
+use crate::bytecode::{Chunk, OpCode, PathOperand};
+use crate::environment::{Env, Environment, ScopeKind, Variable};
+use super::{bind_pattern, pattern_matches, ForItems, Function, FunctionBody, Interpreter, PathStep, RuntimeError, SourceFile, Value};
+
+impl Interpreter {
+    /// 执行一个代码块，返回函数体中 return 语句产生的返回值。
+    /// 无论执行是否成功，都恢复执行前的作用域。
+    pub(super) fn run_chunk(&mut self, chunk: &Rc<Chunk>) -> Result<Option<Value>, RuntimeError> {
+        let saved_env = Rc::clone(&self.env);
+        let result = self.execute_chunk(chunk);
+        self.env = saved_env;
+        result
+    }
+
+    /// 执行代码块中的指令。调用字节码函数时压入新的调用帧而不是递归执行，
+    /// 指令出错时交给最内层的 try 处理，当前调用帧中没有 try 时离开它交给调用方，直到代码块本身
+    fn execute_chunk(&mut self, chunk: &Rc<Chunk>) -> Result<Option<Value>, RuntimeError> {
+        let mut frames = vec![Frame::new(Rc::clone(chunk), 0, None)];
+        let mut stack = Vec::new();
+
+        loop {
+            let frame = frames.last_mut().expect("the chunk's own frame is popped only when execution ends");
+            let step = if frame.ip < frame.chunk.code.len() {
+                self.execute_instruction(frame, &mut stack)
+            } else {
+                // 代码块执行完而没有遇到 return
+                Ok(Step::Return(None))
+            };
+
+            let result = match step {
+                Ok(Step::Next) => continue,
+                Ok(Step::Call(callee)) => {
+                    frames.push(callee);
+                    continue;
+                }
+                Ok(Step::Return(return_value)) => {
+                    let frame = frames.pop().expect("a frame is executing");
+                    let Some(call) = frame.call else {
+                        return Ok(return_value);
+                    };
+                    stack.truncate(frame.stack_base);
+                    self.return_to_caller(&call);
+                    self.return_value(&call.name, &call.function.signature, return_value, &call.pos)
+                }
+                Err(error) => Err(error),
+            };
+
+            match result {
+                Ok(return_value) => stack.push(return_value),
+                Err(error) => self.handle_error(&mut frames, &mut stack, error)?,
+            }
+        }
+    }
+
+    /// 把错误交给最内层的 try：恢复进入 try 时的栈、for 循环、捕获的错误和作用域，然后跳转到 catch 或 finally。
+    /// 没有 try 的调用帧被依次离开，代码块本身也没有 try 时返回错误
+    fn handle_error(&mut self, frames: &mut Vec<Frame>, stack: &mut Vec<Value>, error: RuntimeError) -> Result<(), RuntimeError> {
+        loop {
+            let frame = frames.last_mut().expect("the chunk's own frame is popped only when execution ends");
+            if let Some(handler) = frame.handlers.pop() {
+                stack.truncate(handler.stack_len);
+                frame.for_loops.truncate(handler.for_loops_len);
+                // finally 中抛出的错误取代了等待重新抛出的错误
+                frame.errors.truncate(handler.errors_len);
+                self.env = handler.env;
+                frame.errors.push(error);
+                frame.ip = handler.target;
+                return Ok(());
+            }
+            let frame = frames.pop().expect("a frame is executing");
+            let Some(call) = &frame.call else {
+                return Err(error);
+            };
+            stack.truncate(frame.stack_base);
+            self.return_to_caller(call);
+        }
+    }
+
+    /// 离开被调用的函数，恢复调用方的作用域和源文件
+    fn return_to_caller(&mut self, call: &Call) {
+        self.call_depth -= 1;
+        self.env = Rc::clone(&call.env);
+        self.source = Rc::clone(&call.source);
+    }
+
+    /// 调用字节码函数：新建函数作用域，返回执行函数体的调用帧
+    fn enter_function(&mut self, name: Rc<str>, function: &Rc<Function>, parent: &Env, stack: &mut Vec<Value>, argc: usize, pos: &Position) -> Result<Step, RuntimeError> {
+        let FunctionBody::Bytecode(chunk) = &function.body else {
+            unreachable!("only bytecode functions get a frame");
+        };
+        self.check_call_depth(&name, pos)?;
+        let function_env = self.function_scope(&name, function, parent, stack.drain(stack.len() - argc..), pos)?;
+        self.call_depth += 1;
+        let stack_base = stack.len();
+        let call = Call {
+            name,
+            function: Rc::clone(function),
+            pos: pos.clone(),
+            env: std::mem::replace(&mut self.env, function_env),
+            source: std::mem::replace(&mut self.source, Rc::clone(&function.source)),
+        };
+        Ok(Step::Call(Frame::new(Rc::clone(chunk), stack_base, Some(call))))
+    }
+
+    /// 执行调用帧中的下一条指令
+    fn execute_instruction(&mut self, frame: &mut Frame, stack: &mut Vec<Value>) -> Result<Step, RuntimeError> {
+        let Frame { chunk, ip, for_loops, handlers, errors, .. } = frame;
+        let pos = &chunk.positions[*ip];
+        *ip += 1;
+
+        match &chunk.code[*ip - 1] {
+            OpCode::Constant(index) => stack.push(chunk.constants[*index].clone()),
+            OpCode::Pop => {
+                stack.pop();
+            }
+
+            OpCode::GetVar(name, binding) => {
+                stack.push(self.read_variable(&chunk.names[*name], *binding, pos)?);
+            }
+            OpCode::DefineVar(name, binding) => {
+                let value = pop(stack);
+                self.define_variable(&chunk.names[*name], *binding, Variable::new(value, None));
+            }
+            OpCode::DefineTyped(name, binding, var_type) => {
+                let value = pop(stack);
+                self.define_typed_variable(&chunk.names[*name], *binding, var_type, value, pos)?;
+            }
+            OpCode::DefineConst(name, binding, const_type) => {
+                let value = pop(stack);
+                self.define_constant(&chunk.names[*name], *binding, const_type.as_ref(), value, pos)?;
+            }
+            OpCode::Assign(name, binding) => {
+                let value = pop(stack);
+                self.assign_variable(&chunk.names[*name], *binding, value, pos)?;
+            }
+            OpCode::DefineArray(name, binding, element_type, sizes, count) => {
+                let values = stack.split_off(stack.len() - count);
+                self.define_array(&chunk.names[*name], *binding, element_type, sizes, values, pos)?;
+            }
+            OpCode::DefineList(name, binding, count) => {
+                let values = stack.split_off(stack.len() - count);
+                self.define_variable(&chunk.names[*name], *binding, Variable::new(Value::List(values), None));
+            }
+            OpCode::MakeList(count) => {
+                let values = stack.split_off(stack.len() - count);
+                stack.push(Value::List(values));
+            }
+            OpCode::MakeMap(count) => {
+                let keys_and_values = stack.split_off(stack.len() - count * 2);
+                stack.push(self.build_map(keys_and_values, pos)?);
+            }
+            OpCode::Index => {
+                let index_val = pop(stack);
+                let array_val = pop(stack);
+                stack.push(self.index_value(&array_val, &index_val, pos)?);
+            }
+            OpCode::IndexVar(name, binding) => {
+                let index_val = pop(stack);
+                stack.push(self.index_variable(&chunk.names[*name], *binding, &index_val, pos)?);
+            }
+            OpCode::SetPath(name, binding, path) => {
+                let value = pop(stack);
+                let depth = path.iter().filter(|operand| matches!(operand, PathOperand::Index)).count();
+                let mut indices = stack.split_off(stack.len() - depth).into_iter();
+                let steps: Vec<PathStep> = path.iter().map(|operand| match operand {
+                    PathOperand::Index => PathStep::Index(indices.next().expect("an index for each index operand")),
+                    PathOperand::Field(field) => PathStep::Field(&chunk.names[*field]),
+                }).collect();
+                self.assign_path(&chunk.names[*name], *binding, &steps, value, pos)?;
+            }
+
+            OpCode::GetField(field) => {
+                let value = pop(stack);
+                stack.push(self.field_value(value, &chunk.names[*field], pos)?);
+            }
+
+            OpCode::Binary(op) => {
+                let right = pop(stack);
+                let left = pop(stack);
+                stack.push(self.apply_binary_op(*op, left, right, pos)?);
+            }
+            OpCode::Unary(op) => {
+                let value = pop(stack);
+                stack.push(self.apply_unary_op(op, value, pos)?);
+            }
+            OpCode::Truthy => {
+                let value = pop(stack);
+                stack.push(Value::Bool(value.is_truthy()));
+            }
+            OpCode::Convert(target_type) => {
+                let value = pop(stack);
+                stack.push(self.convert_value(value, target_type, pos)?);
+            }
+
+            OpCode::Jump(target) => *ip = *target,
+            OpCode::JumpIfFalse(target) => {
+                if !pop(stack).is_truthy() {
+                    *ip = *target;
+                }
+            }
+            OpCode::JumpIfTrue(target) => {
+                if pop(stack).is_truthy() {
+                    *ip = *target;
+                }
+            }
+
+            OpCode::EnterScope => {
+                self.env = Environment::new_child(&self.env, ScopeKind::Block);
+            }
+            OpCode::ExitScope => {
+                let parent = self.env.borrow().parent();
+                if let Some(parent) = parent {
+                    self.env = parent;
+                }
+            }
+
+            OpCode::ForRange => {
+                let end = pop(stack);
+                let start = pop(stack);
+                let items = ForItems::range(start, end).map_err(|bound| self.range_bound_error(&bound, pos))?;
+                for_loops.push(ForLoop::new(items));
+            }
+            OpCode::ForEach => {
+                let value = pop(stack);
+                let items = ForItems::over(value).map_err(|bound| self.range_bound_error(&bound, pos))?;
+                for_loops.push(ForLoop::new(items));
+            }
+            OpCode::ForNext(exit) => {
+                let for_loop = for_loops.last_mut().expect("ForNext outside a for loop");
+                match for_loop.items.next() {
+                    Some(item) => {
+                        let scope = for_loop.next_scope(&self.env);
+                        scope.borrow_mut().define_slot(0, Variable::new(item, None));
+                        self.env = scope;
+                    }
+                    None => *ip = *exit,
+                }
+            }
+            OpCode::ForEnd => {
+                for_loops.pop();
+            }
+
+            OpCode::DefineFunction(name, index) => {
+                self.functions.insert(chunk.names[*name].to_string(), Rc::clone(&chunk.functions[*index]));
+            }
+            OpCode::DefineStruct(index) => {
+                let struct_type = Rc::clone(&chunk.structs[*index]);
+                self.structs.insert(struct_type.name.clone(), struct_type);
+            }
+            OpCode::DefineEnum(index) => {
+                let enum_type = Rc::clone(&chunk.enums[*index]);
+                self.enums.insert(enum_type.name.clone(), enum_type);
+            }
+            OpCode::MakeVariant(enum_name, variant, argc) => {
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(self.construct_variant(&chunk.names[*enum_name], &chunk.names[*variant], args, pos)?);
+            }
+            OpCode::MatchArm(pattern, next) => {
+                let pattern = &chunk.patterns[*pattern];
+                if stack.last().is_some_and(|value| pattern_matches(pattern, value)) {
+                    let value = pop(stack);
+                    self.env = Environment::new_child(&self.env, ScopeKind::Block);
+                    bind_pattern(&self.env, pattern, value);
+                } else {
+                    *ip = *next;
+                }
+            }
+            OpCode::NoMatch => {
+                let value = pop(stack);
+                return Err(self.no_matching_arm_error(&value, pos));
+            }
+            OpCode::CheckFunction(name) => self.check_callable(&chunk.names[*name], pos)?,
+            OpCode::Call(name, argc) => {
+                let name = &chunk.names[*name];
+                // 具名函数优先于同名的结构体和原生函数，与 call_function 相同
+                if let Some(function) = self.functions.get(&**name).filter(|function| is_bytecode(function)).cloned() {
+                    let globals = Rc::clone(&self.globals);
+                    return self.enter_function(Rc::clone(name), &function, &globals, stack, *argc, pos);
+                }
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(self.call_function(name, args, pos)?);
+            }
+            OpCode::CallInPlace(name, target, binding, argc) => {
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(self.call_in_place(&chunk.names[*name], &chunk.names[*target], *binding, args, pos)?);
+            }
+            OpCode::CallValue(argc) => {
+                // 被调用的函数值在参数之下
+                let callee = stack.remove(stack.len() - argc - 1);
+                if let Value::Function(closure) = &callee && is_bytecode(&closure.function) {
+                    return self.enter_function(closure.name(), &closure.function, &closure.env, stack, *argc, pos);
+                }
+                let args = stack.split_off(stack.len() - argc);
+                stack.push(self.call_value(callee, args, pos)?);
+            }
+            OpCode::MakeClosure(index) => {
+                stack.push(self.make_lambda(Rc::clone(&chunk.functions[*index])));
+            }
+            OpCode::PushHandler(target) => handlers.push(Handler {
+                target: *target,
+                stack_len: stack.len(),
+                for_loops_len: for_loops.len(),
+                errors_len: errors.len(),
+                env: Rc::clone(&self.env),
+            }),
+            OpCode::PopHandler => {
+                handlers.pop();
+            }
+            OpCode::EnterCatch => {
+                let error = errors.pop().expect("catch entered without a caught error");
+                self.env = Environment::new_child(&self.env, ScopeKind::Block);
+                self.env.borrow_mut().define_slot(0, Variable::new(self.error_value(error), None));
+            }
+            OpCode::Rethrow => return Err(errors.pop().expect("rethrow without a caught error")),
+            OpCode::DiscardError => {
+                errors.pop();
+            }
+            OpCode::Throw => {
+                let value = pop(stack);
+                return Err(self.throw_error(value, pos));
+            }
+            // 离开调用帧时恢复进入函数之前的作用域
+            OpCode::Return => return Ok(Step::Return(Some(pop(stack)))),
+
+            OpCode::Print => {
+                let value = pop(stack);
+                self.output_buffer.push_str(&value.to_string());
+            }
+            OpCode::Println => {
+                let value = pop(stack);
+                if !self.output_buffer.is_empty() {
+                    self.output_buffer.push_str(&value.to_string());
+                    println!("{}", self.output_buffer);
+                    self.output_buffer.clear();
+                } else {
+                    println!("{}", value);
+                }
+            }
+            OpCode::Input(name, binding) => {
+                let prompt = pop(stack);
+                self.read_input(&prompt.to_string(), &chunk.names[*name], *binding, pos)?;
+            }
+            OpCode::Import(filename) => self.handle_import(&chunk.names[*filename], pos)?,
+        }
+
+        Ok(Step::Next)
+    }
+}
+
+/// 正在执行的 try 语句：出错时恢复进入 try 时的栈、for 循环、捕获的错误和作用域，然后跳转到 catch 或 finally
+struct Handler {
+    target: usize,
+    stack_len: usize,
+    for_loops_len: usize,
+    errors_len: usize,
+    env: Env,
+}
+
+/// 正在执行的 for 循环：还没有交给循环变量的值，以及上一次迭代的循环体作用域
+struct ForLoop {
+    items: ForItems,
+    scope: Option<Env>,
+}
+
+impl ForLoop {
+    fn new(items: ForItems) -> Self {
+        Self { items, scope: None }
+    }
+
+    /// 下一次迭代的循环体作用域。上一次迭代的作用域没有被闭包捕获时清空后重新使用，
+    /// 否则新建一个，使每次迭代创建的闭包看到各自的循环变量
+    fn next_scope(&mut self, outer: &Env) -> Env {
+        let scope = match self.scope.take() {
+            Some(scope) if Rc::strong_count(&scope) == 1 => {
+                scope.borrow_mut().clear_slots();
+                scope
+            }
+            _ => Environment::new_child(outer, ScopeKind::Block),
+        };
+        self.scope = Some(Rc::clone(&scope));
+        scope
+    }
+}
+
+/// 执行完一条指令之后虚拟机要做的事
+enum Step {
+    Next,
+    /// 进入被调用函数的调用帧
+    Call(Frame),
+    /// 从当前调用帧返回，`None` 表示没有执行 return
+    Return(Option<Value>),
+}
+
+/// 一个代码块的执行状态。所有调用帧共用一个值栈，调用帧的值从 `stack_base` 开始
+struct Frame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    stack_base: usize,
+    for_loops: Vec<ForLoop>,  // 由外向内正在执行的 for 循环
+    handlers: Vec<Handler>,   // 由外向内正在执行的 try 语句
+    errors: Vec<RuntimeError>, // 已经捕获、等待 catch 使用或 finally 之后重新抛出的错误
+    call: Option<Call>,       // 被调用的函数的调用帧才有，代码块本身没有
+}
+
+impl Frame {
+    fn new(chunk: Rc<Chunk>, stack_base: usize, call: Option<Call>) -> Self {
+        Self { chunk, ip: 0, stack_base, for_loops: Vec::new(), handlers: Vec::new(), errors: Vec::new(), call }
+    }
+}
+
+/// 调用帧对应的函数调用：返回时转换返回值，并恢复调用方的作用域和源文件
+struct Call {
+    name: Rc<str>,
+    function: Rc<Function>,
+    pos: Position, // 调用的位置，返回值类型错误指向这里
+    env: Env,
+    source: Rc<SourceFile>,
+}
+
+fn is_bytecode(function: &Function) -> bool {
+    matches!(function.body, FunctionBody::Bytecode(_))
+}
+
+/// 编译器保证弹栈前栈上有足够的值
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().expect("bytecode popped an empty stack")
+}
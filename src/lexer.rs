@@ -63,16 +63,27 @@ impl Lexer {
                 Token::Comma
             }
             ':' => {
-                self.position += 1;
-                self.column += 1;
-                Token::Colon
+                // 检查是否是 :: 枚举成员路径
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == ':' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::PathSep
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Colon
+                }
             }
             '=' => {
-                // Check for '=='
+                // Check for '==' and '=>'
                 if self.position + 1 < self.input.len() && self.input[self.position + 1] == '=' {
                     self.position += 2;
                     self.column += 2;
                     Token::EqualEqual
+                } else if self.position + 1 < self.input.len() && self.input[self.position + 1] == '>' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::FatArrow
                 } else {
                     self.position += 1;
                     self.column += 1;
@@ -85,20 +96,80 @@ impl Lexer {
                 Token::Plus
             }
             '-' => {
+                // 检查是否是 -> 返回类型
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == '>' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::Arrow
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Minus
+                }
+            }
+            '*' => {
+                // 检查是否是 ** 乘方运算符
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == '*' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::Power
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Multiply
+                }
+            }
+            '^' => {
                 self.position += 1;
                 self.column += 1;
-                Token::Minus
+                Token::Power
             }
-            '*' => {
+            '%' => {
                 self.position += 1;
                 self.column += 1;
-                Token::Multiply
+                Token::Modulo
+            }
+            '~' => {
+                // 检查是否是 ~/ 整数除法运算符
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == '/' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::IntDivide
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Error(error_messages::unexpected_tilde())
+                }
             }
             '/' => {
                 self.position += 1;
                 self.column += 1;
                 Token::Divide
             }
+            '!' => {
+                // 检查是否是 != 运算符
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == '=' {
+                    self.position += 2;
+                    self.column += 2;
+                    Token::NotEqual
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Not
+                }
+            }
+            '&' | '|' => {
+                // 逻辑运算符必须成对出现：&& 和 ||
+                if self.position + 1 < self.input.len() && self.input[self.position + 1] == ch {
+                    self.position += 2;
+                    self.column += 2;
+                    if ch == '&' { Token::And } else { Token::Or }
+                } else {
+                    self.position += 1;
+                    self.column += 1;
+                    Token::Error(error_messages::unexpected_single_logical(ch))
+                }
+            }
             '.' => {
                 // 检查是否是 .. 运算符
                 if self.position + 1 < self.input.len() && self.input[self.position + 1] == '.' {
@@ -108,7 +179,7 @@ impl Lexer {
                 } else {
                     self.position += 1;
                     self.column += 1;
-                    Token::Error(error_messages::unexpected_dot())
+                    Token::Dot
                 }
             }
             '<' => {
@@ -149,7 +220,7 @@ impl Lexer {
                 self.position += 1;
                 self.read_string()
             }
-            _ if ch.is_alphabetic() => {
+            _ if ch.is_alphabetic() || ch == '_' => {
                 self.read_identifier()
             }
             _ if ch.is_numeric() => {
@@ -350,11 +421,14 @@ impl Lexer {
             "print" => Token::Print,
             "println" => Token::Println,
             "var" => Token::Var,
+            "const" => Token::Const,
             "for" => Token::For,
             "in" => Token::In,
             "if" => Token::If,
             "else" => Token::Else,
             "while" => Token::While,
+            "break" => Token::Break,
+            "continue" => Token::Continue,
             "input" => Token::Input,
             "func" => Token::Func,
             "expr" => Token::Expr,
@@ -367,6 +441,13 @@ impl Lexer {
             "float" => Token::Float,
             "double" => Token::Double,
             "import" => Token::Import,
+            "struct" => Token::Struct,
+            "enum" => Token::Enum,
+            "match" => Token::Match,
+            "try" => Token::Try,
+            "catch" => Token::Catch,
+            "finally" => Token::Finally,
+            "throw" => Token::Throw,
             _ => Token::Identifier(result),
         }
     }
@@ -385,7 +466,7 @@ impl Lexer {
             // 检查下一个字符是否也是 '.'，如果是，则不消耗这个 '.'
             if self.position + 1 < self.input.len() && self.input[self.position + 1] == '.' {
                 // 这是范围运算符的开始，不消耗这个 '.'
-                return Token::Number(result.parse().unwrap_or(0.0));
+                return Self::integer_token(&result);
             }
             
             // 否则，这是小数点
@@ -398,8 +479,18 @@ impl Lexer {
                 self.position += 1;
                 self.column += 1;
             }
+            
+            return Token::Number(result.parse().unwrap_or(0.0));
         }
         
-        Token::Number(result.parse().unwrap_or(0.0))
+        Self::integer_token(&result)
+    }
+    
+    /// 没有小数点的数字是整数字面量，超出 int 范围时报错
+    fn integer_token(digits: &str) -> Token {
+        match digits.parse() {
+            Ok(value) => Token::Integer(value),
+            Err(_) => Token::Error(error_messages::integer_literal_too_large(digits)),
+        }
     }
 }
\ No newline at end of file
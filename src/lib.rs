@@ -0,0 +1,37 @@
+//! ECL (EthernosCommandLang) 语言库
+//!
+//! 词法分析、语法分析和解释器都在这里实现，命令行程序和 REPL 只是它的客户端。
+//! 其他 Rust 程序可以通过 [`Interpreter`] 嵌入 ECL：
+//!
+//! ```
+//! use ecl::{Interpreter, Value};
+//!
+//! let mut interpreter = Interpreter::new();
+//! interpreter.set_global("base", Value::Double(40.0));
+//! interpreter.run("var answer = base + 2;").unwrap();
+//! assert_eq!(interpreter.get_global("answer"), Some(Value::Double(42.0)));
+//!
+//! // 运行时错误以诊断信息的形式返回，而不会终止宿主进程
+//! let error = interpreter.run("println(missing);").unwrap_err();
+//! assert_eq!(error.diagnostic.line, 1);
+//! ```
+
+pub mod token;
+pub mod ast;
+pub mod lexer;
+pub mod parser;
+mod resolver;
+mod checker;
+pub mod interpreter;
+mod bytecode;
+mod compiler;
+mod environment;
+pub mod error;
+
+pub use error::{CompilerError, ErrorKind, RuntimeError};
+pub use interpreter::{Engine, Interpreter, NativeFn, Value};
+
+// 嵌入文档中的 Rust 示例作为文档测试运行，保证示例与实现一致
+#[cfg(doctest)]
+#[doc = include_str!("../docs/embedding.md")]
+struct EmbeddingDocExamples;
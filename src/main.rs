@@ -1,24 +1,43 @@
-mod token;
-mod ast;
-mod lexer;
-mod parser;
-mod interpreter;
-mod error;
 mod repl;
 
 use std::env;
 use std::fs;
-use interpreter::Interpreter;
+use ecl::{Engine, Interpreter};
+use ecl::lexer::Lexer;
+use ecl::token::Token;
 use repl::Repl;
 
+/// 执行脚本的线程的栈大小。函数调用层数的上限在这个栈上总能先于栈溢出达到，
+/// 默认的主线程栈在 release 构建中只够大约 2000 层递归
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let cli = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run_cli)
+        .expect("failed to start the interpreter thread");
+    if cli.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+fn run_cli() {
+    let mut args: Vec<String> = env::args().collect();
     //版本号常量
     const VERSION: &str = "Beta0.4.0";
     
+    // --vm：先编译为字节码，再由虚拟机执行（文件模式和REPL模式都适用）
+    let engine = match args.iter().position(|arg| arg == "--vm") {
+        Some(index) => {
+            args.remove(index);
+            Engine::Vm
+        }
+        None => Engine::TreeWalker,
+    };
+    
     if args.len() < 2 {
         // 启动REPL模式
-        let mut repl = Repl::new();
+        let mut repl = Repl::new().with_engine(engine);
         println!("Welcome to the ECL REPL! Version {}", VERSION);
         repl.run();
     } else if args[1] == "--debug-lexer" && args.len() >= 3 {
@@ -37,7 +56,7 @@ fn main() {
             args[2].clone()
         };
         
-        let mut lexer = crate::lexer::Lexer::new(&input);
+        let mut lexer = Lexer::new(&input);
         
         println!("Debugging lexer for input: '{}'", input);
         loop {
@@ -45,7 +64,7 @@ fn main() {
             let token = lexer.next_token();
             println!("Position: ({}, {}), Token: {:?}", line, column, token);
             
-            if matches!(token, crate::token::Token::Eof) {
+            if matches!(token, Token::Eof) {
                 break;
             }
         }
@@ -61,8 +80,12 @@ fn main() {
             Ok(contents) => {
                 let source_lines: Vec<String> = contents.lines().map(|s| s.to_string()).collect();
                 let mut interpreter = Interpreter::new()
-                    .with_source(filename.to_string(), source_lines);
-                interpreter.run(&contents);
+                    .with_source(filename.to_string(), source_lines)
+                    .with_engine(engine);
+                if let Err(error) = interpreter.run(&contents) {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
             }
             Err(e) => {
                 eprintln!("Error reading file {}: {}", filename, e);
@@ -1,11 +1,17 @@
 use crate::token::Token;
-use crate::ast::{ASTNode, Type};
+use crate::ast::{ASTNode, Accessor, Binding, Field, Param, Pattern, Position, Type, Variant};
 use crate::error::{CompilerError, error_messages};
 
+/// 乘方运算符的优先级，也是一元负号操作数的最低优先级
+const POWER_PRECEDENCE: u8 = 8;
+
 pub struct Parser {
     lexer: crate::lexer::Lexer,
     current_token: Token,
     error_context: Option<String>,
+    error_position: Option<Position>, // error_context 对应的位置，为空时使用语句开头的位置
+    loop_depth: usize,                // 当前正在解析的循环嵌套层数，用于检查 break/continue
+    function_depth: usize,            // 当前正在解析的函数体嵌套层数，用于检查 return
 }
 
 impl Parser {
@@ -15,9 +21,14 @@ impl Parser {
             lexer,
             current_token,
             error_context: None,
+            error_position: None,
+            loop_depth: 0,
+            function_depth: 0,
         }
     }
     
+    // 诊断信息只在出错时构造一次，无需为其装箱
+    #[allow(clippy::result_large_err)]
     pub fn parse(&mut self, file_path: &str, source_lines: &[String]) -> Result<Vec<ASTNode>, CompilerError> {
         let mut statements = Vec::new();
         
@@ -69,6 +80,11 @@ impl Parser {
             let stmt = match self.parse_statement() {
                 Some(stmt) => stmt,
                 None => {
+                    // 优先使用记录下来的精确错误位置
+                    let (line, column) = match self.error_position.take() {
+                        Some(pos) => (pos.line, pos.column),
+                        None => (line, column),
+                    };
                     let source_line = source_lines.get(line - 1).unwrap_or(&String::new()).clone();
                     
                     // Check if we have custom error context first
@@ -85,10 +101,14 @@ impl Parser {
                             Token::Minus =>     error_messages::unexpected_operator("minus"),
                             Token::Multiply =>  error_messages::unexpected_operator("multiplication"),
                             Token::Divide =>    error_messages::unexpected_operator("division"),
+                            Token::Modulo =>    error_messages::unexpected_operator("modulo"),
+                            Token::IntDivide => error_messages::unexpected_operator("integer division"),
+                            Token::Power =>     error_messages::unexpected_operator("power"),
                             Token::Equal =>     error_messages::unexpected_operator("equals"),
                             Token::Print =>     error_messages::unexpected_keyword("print"),
                             Token::Println =>   error_messages::unexpected_keyword("println"),
                             Token::Var =>       error_messages::unexpected_var(),
+                            Token::Const =>     error_messages::const_requires_initializer(),
                             Token::For =>       error_messages::unexpected_loop_keyword("for"),
                             Token::In =>        error_messages::unexpected_loop_keyword("in"),
                             Token::If =>        error_messages::unexpected_conditional_keyword("if"),
@@ -99,6 +119,17 @@ impl Parser {
                             Token::Expr =>      error_messages::unexpected_expr(),
                             Token::Return =>    error_messages::unexpected_return(),
                             Token::Import =>    error_messages::unexpected_import(),
+                            Token::Struct =>    error_messages::unexpected_keyword("struct"),
+                            Token::Enum =>      error_messages::unexpected_keyword("enum"),
+                            Token::Match =>     error_messages::unexpected_keyword("match"),
+                            Token::Try =>       error_messages::unexpected_keyword("try"),
+                            Token::Catch =>     error_messages::catch_without_try("catch"),
+                            Token::Finally =>   error_messages::catch_without_try("finally"),
+                            Token::Throw =>     error_messages::unexpected_keyword("throw"),
+                            Token::Dot =>       error_messages::unexpected_operator("dot"),
+                            Token::PathSep =>   error_messages::unexpected_operator("path separator"),
+                            Token::FatArrow =>  error_messages::unexpected_operator("fat arrow"),
+                            Token::Arrow =>     error_messages::unexpected_operator("arrow"),
                             Token::True =>      error_messages::unexpected_boolean("true"),
                             Token::False =>     error_messages::unexpected_boolean("false"),
                             Token::LeftParen => error_messages::unexpected_paren("left"),
@@ -107,13 +138,15 @@ impl Parser {
                             Token::RightBrace =>error_messages::unexpected_brace("right"),
                             Token::Comma =>     error_messages::unexpected_comma(),
                             Token::Colon =>     error_messages::unexpected_colon(),
-                            Token::Less =>      error_messages::unexpected_comparison_op("less than"),
-                            Token::Greater =>   error_messages::unexpected_comparison_op("greater than"),
                             Token::LessEqual => error_messages::unexpected_comparison_op("less than or equal"),
                             Token::GreaterEqual => error_messages::unexpected_comparison_op("greater than or equal"),
                             Token::Range =>     error_messages::unexpected_range(),
                             Token::LessThan =>  error_messages::unexpected_comparison_op("less than"),
                             Token::GreaterThan => error_messages::unexpected_comparison_op("greater than"),
+                            Token::NotEqual =>  error_messages::unexpected_comparison_op("not equal"),
+                            Token::And =>       error_messages::unexpected_operator("logical and"),
+                            Token::Or =>        error_messages::unexpected_operator("logical or"),
+                            Token::Not =>       error_messages::unexpected_operator("logical not"),
                             Token::Int =>       error_messages::unexpected_type("int"),
                             Token::Str =>       error_messages::unexpected_type("str"),
                             Token::Bool =>      error_messages::unexpected_type("bool"),
@@ -146,6 +179,7 @@ impl Parser {
     fn parse_statement(&mut self) -> Option<ASTNode> {
         // Clear any previous error context
         self.error_context = None;
+        self.error_position = None;
         
         // Check for lexer errors first
         if let Token::Error(_) = &self.current_token {
@@ -158,31 +192,15 @@ impl Parser {
                 if let Token::Identifier(name) = &self.current_token.clone() {
                     let func_name = name.clone();
                     self.advance();
+                    let pos = self.current_position();
                     
                     if !matches!(self.current_token, Token::LeftParen) {
                         return None;
                     }
-                    self.advance();
                     
                     // Parse parameters
-                    let mut params = Vec::new();
-                    while !matches!(self.current_token, Token::RightParen) {
-                        if let Token::Identifier(param) = &self.current_token.clone() {
-                            params.push(param.clone());
-                            self.advance();
-                            
-                            if matches!(self.current_token, Token::Comma) {
-                                self.advance();
-                            }
-                        } else {
-                            return None;
-                        }
-                    }
-                    
-                    if !matches!(self.current_token, Token::RightParen) {
-                        return None;
-                    }
-                    self.advance();
+                    let params = self.parse_parameters()?;
+                    let return_type = self.parse_return_type()?;
                     
                     // Parse function body
                     if !matches!(self.current_token, Token::LeftBrace) {
@@ -190,10 +208,15 @@ impl Parser {
                     }
                     self.advance();
                     
+                    // break/continue 不能跨越函数体作用于外层的循环
+                    let saved_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                    self.function_depth += 1;
                     let mut body = Vec::new();
                     while !matches!(self.current_token, Token::RightBrace) {
                         // Check if we have error context from previous parsing failure
                         if self.error_context.is_some() {
+                            self.loop_depth = saved_loop_depth;
+                            self.function_depth -= 1;
                             return None; // Propagate the error by returning None
                         }
                         
@@ -212,12 +235,15 @@ impl Parser {
                         }
                     }
                     
-                    if !matches!(self.current_token, Token::RightBrace) {
+                    self.loop_depth = saved_loop_depth;
+                    self.function_depth -= 1;
+                    
+                    if self.error_context.is_some() || !matches!(self.current_token, Token::RightBrace) {
                         return None;
                     }
                     self.advance();
                     
-                    Some(ASTNode::Function(func_name, params, body))
+                    Some(ASTNode::Function(func_name, params, return_type, body, pos))
                 } else {
                     None
                 }
@@ -270,10 +296,15 @@ impl Parser {
                     }
                     self.advance();
                     
+                    // break/continue 不能跨越函数体作用于外层的循环
+                    let saved_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                    self.function_depth += 1;
                     let mut body = Vec::new();
                     while !matches!(self.current_token, Token::RightBrace) {
                         // Check if we have error context from previous parsing failure
                         if self.error_context.is_some() {
+                            self.loop_depth = saved_loop_depth;
+                            self.function_depth -= 1;
                             return None; // Propagate the error by returning None
                         }
                         
@@ -292,7 +323,10 @@ impl Parser {
                         }
                     }
                     
-                    if !matches!(self.current_token, Token::RightBrace) {
+                    self.loop_depth = saved_loop_depth;
+                    self.function_depth -= 1;
+                    
+                    if self.error_context.is_some() || !matches!(self.current_token, Token::RightBrace) {
                         return None;
                     }
                     self.advance();
@@ -302,15 +336,42 @@ impl Parser {
                     None
                 }
             }
+            Token::Struct => self.parse_struct_declaration(),
+            Token::Enum => self.parse_enum_declaration(),
+            Token::Const => self.parse_const_declaration(),
+            Token::Match => self.parse_match_statement(),
+            Token::Try => self.parse_try_statement(),
+            Token::Throw => {
+                let pos = self.current_position();
+                self.advance();
+                
+                let value = self.parse_expression()?;
+                if matches!(self.current_token, Token::Semicolon) {
+                    self.advance();
+                }
+                Some(ASTNode::Throw(Box::new(value), pos))
+            }
             Token::Return => {
+                let pos = self.current_position();
                 self.advance();
+                
+                if self.function_depth == 0 {
+                    self.error_context = Some(format!(
+                        "{}\n  = {}",
+                        error_messages::return_outside_function(),
+                        error_messages::help_return_outside_function()
+                    ));
+                    self.error_position = Some(pos);
+                    return None;
+                }
+                
                 let expr = self.parse_expression()?;
                 
                 if matches!(self.current_token, Token::Semicolon) {
                     self.advance();
                 }
                 
-                Some(ASTNode::Return(Box::new(expr)))
+                Some(ASTNode::Return(Box::new(expr), pos))
             }
             Token::Var => {
                 self.advance();
@@ -343,55 +404,53 @@ impl Parser {
                 if let Token::Identifier(name) = &self.current_token.clone() {
                     let var_name = name.clone();
                     self.advance();
+                    let pos = self.current_position();
                     
                     // Check if this is an array declaration: name[size]
                     if matches!(self.current_token, Token::LeftBracket) {
                         // This is an array declaration: var <type>name[size] = {init_values}
-                        if let None = var_type {
-                            return None; // Array must have a type
-                        }
+                        let element_type = var_type.clone()?; // Array must have a type
                         
-                        self.advance(); // consume '['
-                        
-                        // Parse the size expression
-                        let size_expr = self.parse_expression()?;
-                        
-                        if !matches!(self.current_token, Token::RightBracket) {
-                            return None;
-                        }
-                        self.advance(); // consume ']'
-                        
-                        if !matches!(self.current_token, Token::Equal) {
-                            return None;
+                        // 多维数组的每一维依次写作 [size]，由外向内
+                        let mut sizes = Vec::new();
+                        while matches!(self.current_token, Token::LeftBracket) {
+                            self.advance(); // consume '['
+                            
+                            // 长度必须是非负的整数字面量，数组的形状在执行前就能确定
+                            let size_pos = self.current_position();
+                            let size = match self.parse_expression()? {
+                                ASTNode::Integer(n) => usize::try_from(n).ok(),
+                                _ => None,
+                            };
+                            let Some(size) = size else {
+                                return self.array_size_error(size_pos);
+                            };
+                            
+                            if !matches!(self.current_token, Token::RightBracket) {
+                                return None;
+                            }
+                            self.advance(); // consume ']'
+                            sizes.push(size);
                         }
-                        self.advance(); // consume '='
                         
-                        // Parse initialization expression - should be a list of values
-                        let init_expr = self.parse_expression()?;
+                        // 没有初始值时所有元素都是元素类型的默认值
+                        let init_values = if matches!(self.current_token, Token::Equal) {
+                            self.advance(); // consume '='
+                            
+                            // 列表字面量中的值依次初始化数组元素
+                            match self.parse_expression()? {
+                                ASTNode::ListLiteral(elements) => elements,
+                                init_expr => vec![init_expr], // If it's a single value, use it to initialize all elements
+                            }
+                        } else {
+                            Vec::new()
+                        };
                         
                         if matches!(self.current_token, Token::Semicolon) {
                             self.advance();
                         }
                         
-                        // Extract the size value from the expression
-                        let size_val = match size_expr {
-                            ASTNode::Number(n) => n as usize,
-                            _ => 0, // Default to 0 if not a number (error case)
-                        };
-                        
-                        // For array initialization, we look for the array_init function call
-                        let init_values = match init_expr {
-                            ASTNode::FunctionCall(ref func_name, ref args) if func_name == "array_init" => {
-                                args.clone()  // Get the values from the array initialization
-                            }
-                            _ => vec![init_expr], // If it's a single value, use it to initialize all elements
-                        };
-                        
-                        if let Some(t) = var_type {
-                            Some(ASTNode::ArrayDecl(var_name, t, size_val, init_values))
-                        } else {
-                            None // Should not happen since we checked earlier
-                        }
+                        Some(ASTNode::ArrayDecl(var_name, Binding::Unresolved, element_type, sizes, init_values, pos))
                     } else {
                         if !matches!(self.current_token, Token::Equal) {
                             return None;
@@ -401,21 +460,17 @@ impl Parser {
                         let expr = self.parse_expression()?;
                         
                         // Check if this is a list initialization: var name = []
-                        let result = match &expr {
-                            ASTNode::FunctionCall(func_name, args) if func_name == "list_init" && args.is_empty() => {
+                        let result = match expr {
+                            ASTNode::ListLiteral(elements) => {
                                 // This is a list initialization
-                                ASTNode::ListDecl(var_name, vec![])
-                            }
-                            ASTNode::FunctionCall(func_name, args) if func_name == "array_init" => {
-                                // This is a list initialization with values
-                                ASTNode::ListDecl(var_name, args.clone())
+                                ASTNode::ListDecl(var_name, Binding::Unresolved, elements, pos)
                             }
                             _ => {
                                 // Regular variable
                                 if let Some(t) = var_type {
-                                    ASTNode::TypedVar(var_name, t, Box::new(expr))
+                                    ASTNode::TypedVar(var_name, Binding::Unresolved, t, Box::new(expr), pos)
                                 } else {
-                                    ASTNode::Var(var_name, Box::new(expr))
+                                    ASTNode::Var(var_name, Binding::Unresolved, Box::new(expr), pos)
                                 }
                             }
                         };
@@ -439,14 +494,17 @@ impl Parser {
                     // 检查 'in' 关键字
                     if !matches!(self.current_token, Token::In) {
                         // Set error context for missing 'in' keyword
-                        self.error_context = Some(format!("for loop requires 'in' keyword\n  = help: use syntax: for variable in range {{ ... }}\n  = example: for i in 1..5 {{ print(i); }}"));
+                        self.error_context = Some("for loop requires 'in' keyword\n  = help: use syntax: for variable in range { ... }\n  = example: for i in 1..5 { print(i); }".to_string());
                         return None;
                     }
                     self.advance();
                     
                     // 解析范围表达式
                     let range_expr = self.parse_expression()?;
+                    let (line, column) = self.lexer.get_position();
+                    let range_pos = crate::ast::Position::new(line, column);
                     
+                    self.loop_depth += 1;
                     let body = if matches!(self.current_token, Token::LeftBrace) {
                         self.advance();
                         let mut statements = Vec::new();
@@ -456,6 +514,10 @@ impl Parser {
                                     statements.push(stmt);
                                 }
                                 None => {
+                                    // 语句块内的错误（例如循环外的 break）需要传递给外层报告
+                                    if self.error_context.is_some() {
+                                        return None;
+                                    }
                                     break;
                                 }
                             }
@@ -470,12 +532,13 @@ impl Parser {
                             None => vec![]
                         }
                     };
+                    self.loop_depth -= 1;
                     
-                    Some(ASTNode::For(var_name, Box::new(range_expr), body))
+                    Some(ASTNode::For(var_name, Box::new(range_expr), body, range_pos))
                 } else {
                     // Set error context for missing loop variable
-                    self.error_context = Some(format!("for loop requires a loop variable\n  = help: use syntax: for variable in range {{ ... }}\n  = example: for i in 1..5 {{ print(i); }}"));
-                    return None;
+                    self.error_context = Some("for loop requires a loop variable\n  = help: use syntax: for variable in range { ... }\n  = example: for i in 1..5 { print(i); }".to_string());
+                    None
                 }
             }
             Token::If => {
@@ -484,7 +547,7 @@ impl Parser {
                 // Check if condition has parentheses
                 if !matches!(self.current_token, Token::LeftParen) {
                     // Set error context for missing parentheses
-                    self.error_context = Some(format!("if statement requires parentheses around the condition\n  = help: use syntax: if(condition) {{ ... }}\n  = example: if(x > 0) {{ print(x); }}"));
+                    self.error_context = Some("if statement requires parentheses around the condition\n  = help: use syntax: if(condition) { ... }\n  = example: if(x > 0) { print(x); }".to_string());
                     return None;
                 }
                 self.advance();
@@ -505,6 +568,10 @@ impl Parser {
                                 statements.push(stmt);
                             }
                             None => {
+                                // 语句块内的错误（例如循环外的 break）需要传递给外层报告
+                                if self.error_context.is_some() {
+                                    return None;
+                                }
                                 break;
                             }
                         }
@@ -531,6 +598,10 @@ impl Parser {
                                     statements.push(stmt);
                                 }
                                 None => {
+                                    // 语句块内的错误（例如循环外的 break）需要传递给外层报告
+                                    if self.error_context.is_some() {
+                                        return None;
+                                    }
                                     break;
                                 }
                             }
@@ -540,10 +611,7 @@ impl Parser {
                         }
                         Some(statements)
                     } else {
-                        match self.parse_statement() {
-                            Some(stmt) => Some(vec![stmt]),
-                            None => None
-                        }
+                        self.parse_statement().map(|stmt| vec![stmt])
                     }
                 } else {
                     None
@@ -556,7 +624,7 @@ impl Parser {
                 
                 if !matches!(self.current_token, Token::LeftParen) {
                     // Set error context for missing parentheses - this will be picked up by the main parse loop
-                    self.error_context = Some(format!("while loop requires parentheses around the condition\n  = help: use syntax: while(condition) {{ ... }}\n  = example: while(x < 3) {{ x = x + 1; }}"));
+                    self.error_context = Some("while loop requires parentheses around the condition\n  = help: use syntax: while(condition) { ... }\n  = example: while(x < 3) { x = x + 1; }".to_string());
                     return None;
                 }
                 self.advance();
@@ -568,6 +636,7 @@ impl Parser {
                 }
                 self.advance();
                 
+                self.loop_depth += 1;
                 let body = if matches!(self.current_token, Token::LeftBrace) {
                     self.advance();
                     let mut statements = Vec::new();
@@ -577,6 +646,10 @@ impl Parser {
                                 statements.push(stmt);
                             }
                             None => {
+                                // 语句块内的错误（例如循环外的 break）需要传递给外层报告
+                                if self.error_context.is_some() {
+                                    return None;
+                                }
                                 break;
                             }
                         }
@@ -591,14 +664,40 @@ impl Parser {
                         None => vec![]
                     }
                 };
+                self.loop_depth -= 1;
                 
                 Some(ASTNode::While(Box::new(condition), body))
             }
+            Token::Break | Token::Continue => {
+                let keyword = if matches!(self.current_token, Token::Break) { "break" } else { "continue" };
+                let pos = self.current_position();
+                self.advance();
+                
+                if self.loop_depth == 0 {
+                    self.error_context = Some(format!(
+                        "{}\n  = {}",
+                        error_messages::loop_control_outside_loop(keyword),
+                        error_messages::help_loop_control(keyword)
+                    ));
+                    self.error_position = Some(pos);
+                    return None;
+                }
+                
+                if matches!(self.current_token, Token::Semicolon) {
+                    self.advance();
+                }
+                
+                if keyword == "break" {
+                    Some(ASTNode::Break(pos))
+                } else {
+                    Some(ASTNode::Continue(pos))
+                }
+            }
             Token::Input => {
                 self.advance();
                 if !matches!(self.current_token, Token::LeftParen) {
                     // Set error context for missing parentheses
-                    self.error_context = Some(format!("input statement requires parentheses\n  = help: use syntax: input(prompt, variable)\n  = example: input(\"Enter name: \", name)"));
+                    self.error_context = Some("input statement requires parentheses\n  = help: use syntax: input(prompt, variable)\n  = example: input(\"Enter name: \", name)".to_string());
                     return None;
                 }
                 self.advance();
@@ -613,6 +712,7 @@ impl Parser {
                 if let Token::Identifier(var_name) = &self.current_token.clone() {
                     let var = var_name.clone();
                     self.advance();
+                    let pos = self.current_position();
                     
                     if !matches!(self.current_token, Token::RightParen) {
                         return None;
@@ -623,7 +723,7 @@ impl Parser {
                         self.advance();
                     }
                     
-                    Some(ASTNode::Input(Box::new(prompt), var))
+                    Some(ASTNode::Input(Box::new(prompt), var, Binding::Unresolved, pos))
                 } else {
                     None
                 }
@@ -632,7 +732,7 @@ impl Parser {
                 self.advance();
                 if !matches!(self.current_token, Token::LeftParen) {
                     // Set error context for missing parentheses
-                    self.error_context = Some(format!("print statement requires parentheses\n  = help: use syntax: print(expression)\n  = example: print(\"Hello World\")"));
+                    self.error_context = Some("print statement requires parentheses\n  = help: use syntax: print(expression)\n  = example: print(\"Hello World\")".to_string());
                     return None;
                 }
                 self.advance();
@@ -654,7 +754,7 @@ impl Parser {
                 self.advance();
                 if !matches!(self.current_token, Token::LeftParen) {
                     // Set error context for missing parentheses
-                    self.error_context = Some(format!("println statement requires parentheses\n  = help: use syntax: println(expression)\n  = example: println(\"Hello World\")"));
+                    self.error_context = Some("println statement requires parentheses\n  = help: use syntax: println(expression)\n  = example: println(\"Hello World\")".to_string());
                     return None;
                 }
                 self.advance();
@@ -716,6 +816,7 @@ impl Parser {
                 if let Token::Identifier(name) = &self.current_token.clone() {
                     let var_name = name.clone();
                     self.advance();
+                    let pos = self.current_position();
                     
                     if !matches!(self.current_token, Token::Equal) {
                         return None;
@@ -728,7 +829,7 @@ impl Parser {
                         self.advance();
                     }
                     
-                    Some(ASTNode::TypedVar(var_name, var_type, Box::new(expr)))
+                    Some(ASTNode::TypedVar(var_name, Binding::Unresolved, var_type, Box::new(expr), pos))
                 } else {
                     None
                 }
@@ -736,50 +837,31 @@ impl Parser {
             Token::Identifier(name) => {
                 let var_name = name.clone();
                 self.advance();
+                let name_pos = self.current_position();
                 
-                // Check for index access: name[index]
-                if matches!(self.current_token, Token::LeftBracket) {
-                    // This is an array/list index access: name[index]
-                    let identifier_node = {
-                        let (line, column) = self.lexer.get_position();
-                        let pos = crate::ast::Position::new(line, column);
-                        ASTNode::Identifier(var_name.clone(), pos)
-                    };
-                    
-                    self.advance(); // consume '['
+                // 下标访问、字段访问或给其中的元素或字段赋值：name[i][j]、name.a.b 或 name.items[0].x = value
+                if matches!(self.current_token, Token::LeftBracket | Token::Dot) {
+                    let (line, column) = self.lexer.get_position();
+                    let identifier_node = ASTNode::Identifier(var_name.clone(), Binding::Unresolved, Position::new(line, column));
+                    // 每一层访问的位置在它的 ']' 或字段名之后
+                    let access = self.parse_postfix(identifier_node)?;
                     
-                    let index_expr = self.parse_expression()?;
+                    if !matches!(self.current_token, Token::Equal) {
+                        return Some(access);
+                    }
+                    let path = self.assignment_path(access)?;
+                    self.advance(); // consume '='
+                    // Get position right after the '=' to mark the assignment location
+                    let (line, column) = self.lexer.get_position();
+                    let pos = Position::new(line, column);
+                    let value_expr = self.parse_expression()?;
                     
-                    if !matches!(self.current_token, Token::RightBracket) {
-                        return None;
+                    if matches!(self.current_token, Token::Semicolon) {
+                        self.advance();
                     }
-                    self.advance(); // consume ']'
                     
-                    // Check if this is an assignment: name[index] = value
-                        if matches!(self.current_token, Token::Equal) {
-                            self.advance(); // consume '='
-                            // Get position right after the '=' to mark the assignment location
-                            let (line, column) = self.lexer.get_position();
-                            let pos = crate::ast::Position::new(line, column);
-                            let value_expr = self.parse_expression()?;
-                            
-                            if matches!(self.current_token, Token::Semicolon) {
-                                self.advance();
-                            }
-                            
-                            Some(ASTNode::IndexAssign(Box::new(identifier_node), Box::new(index_expr), Box::new(value_expr), pos))
-                        } else {
-
-                            // Just index access - need to get the position for the ']'
-
-                            let (line, column) = self.lexer.get_position();
-
-                            let pos = crate::ast::Position::new(line, column);
-
-                            Some(ASTNode::IndexAccess(Box::new(identifier_node), Box::new(index_expr), pos))
-
-                        }
-                    } else if matches!(self.current_token, Token::Equal) {
+                    Some(ASTNode::PathAssign(var_name, Binding::Unresolved, path, Box::new(value_expr), pos))
+                } else if matches!(self.current_token, Token::Equal) {
                     self.advance();
                     let expr = self.parse_expression()?;
                     
@@ -787,7 +869,7 @@ impl Parser {
                         self.advance();
                     }
                     
-                    Some(ASTNode::Assign(var_name, Box::new(expr)))
+                    Some(ASTNode::Assign(var_name, Binding::Unresolved, Box::new(expr), name_pos))
                 } else {
                     // Check for function call: func_name(arg1, arg2)
                     if matches!(self.current_token, Token::LeftParen) {
@@ -808,11 +890,22 @@ impl Parser {
                         }
                         self.advance(); // consume ')'
                         
-                        Some(ASTNode::FunctionCall(var_name, args))
+                        // f(1)(2) 或 f(1).field
+                        let call = self.parse_postfix(ASTNode::FunctionCall(var_name, args, name_pos.clone()))?;
+                        if matches!(self.current_token, Token::Equal) {
+                            return self.unsupported_assignment_target(name_pos);
+                        }
+                        
+                        // 与其他语句一样消耗语句末尾的分号，否则语句块会在分号处提前结束
+                        if matches!(self.current_token, Token::Semicolon) {
+                            self.advance();
+                        }
+                        
+                        Some(call)
                     } else {
                         let (line, column) = self.lexer.get_position();
                         let pos = crate::ast::Position::new(line, column);
-                        Some(ASTNode::Identifier(var_name, pos))
+                        Some(ASTNode::Identifier(var_name, Binding::Unresolved, pos))
                     }
                 }
             }
@@ -821,25 +914,78 @@ impl Parser {
     }
     
     fn parse_expression(&mut self) -> Option<ASTNode> {
-        self.parse_binary_expression()
+        self.parse_binary_expression(0)
+    }
+    
+    /// 优先级爬升：只有优先级不低于 `min_precedence` 的运算符才会被当前调用消耗。
+    /// 右操作数以更高的最低优先级解析，因此同级运算符左结合，`10 - 3 - 2` 为 `(10 - 3) - 2`；
+    /// 乘方是例外，`2 ** 3 ** 2` 为 `2 ** (3 ** 2)`。
+    fn parse_binary_expression(&mut self, min_precedence: u8) -> Option<ASTNode> {
+        let mut left = self.parse_operand()?;
+        
+        while let Some(op) = self.get_binary_op() {
+            let precedence = Self::binary_precedence(&op);
+            if precedence < min_precedence {
+                break;
+            }
+            self.advance();
+            let next_precedence = if op == "**" { precedence } else { precedence + 1 };
+            let right = self.parse_binary_expression(next_precedence)?;
+            let (line, column) = self.lexer.get_position();
+            let pos = crate::ast::Position::new(line, column);
+            left = ASTNode::BinaryOp(Box::new(left), op, Box::new(right), pos);
+        }
+        
+        Some(left)
+    }
+    
+    /// 二元运算符的优先级，数值越大结合越紧密：
+    ///
+    /// | 优先级 | 运算符 |
+    /// |---|---|
+    /// | 1 | `..` |
+    /// | 2 | `\|\|` |
+    /// | 3 | `&&` |
+    /// | 4 | `==` `!=` |
+    /// | 5 | `<` `<=` `>` `>=` |
+    /// | 6 | `+` `-` |
+    /// | 7 | `*` `/` `%` `~/` |
+    /// | 8 | `**`（右结合） |
+    ///
+    /// 一元运算符 `-` 和 `!` 在 `parse_primary` 中处理，结合得比其他二元运算符都紧，
+    /// 但一元负号的操作数包含乘方：`-2 ** 2` 为 `-(2 ** 2)`。
+    fn binary_precedence(op: &str) -> u8 {
+        match op {
+            ".." => 1,
+            "||" => 2,
+            "&&" => 3,
+            "==" | "!=" => 4,
+            "<" | "<=" | ">" | ">=" => 5,
+            "+" | "-" => 6,
+            "*" | "/" | "%" | "~/" => 7,
+            "**" => POWER_PRECEDENCE,
+            _ => 0,
+        }
     }
     
-    fn parse_binary_expression(&mut self) -> Option<ASTNode> {
-        let mut left = self.parse_primary()?;
+    /// 解析二元运算的一个操作数，包括 `16 x2` 这样的表达式函数调用
+    fn parse_operand(&mut self) -> Option<ASTNode> {
+        let left = self.parse_primary()?;
         
         // Check for expr function call syntax: arg1 func_name arg2
         // This should be detected before regular binary operations
         match &left {
-            ASTNode::Number(arg1_val) => {
+            ASTNode::Integer(_) | ASTNode::Number(_) => {
                 // Pattern: Number Identifier (e.g., "16 x2")
                 if let Token::Identifier(func_name) = &self.current_token.clone() {
                     let fname = func_name.clone();
                     self.advance(); // consume function name
+                    let call_pos = self.current_position();
                     
-                    let mut args = vec![ASTNode::Number(*arg1_val)]; // First argument
+                    let mut args = vec![left.clone()]; // First argument
                     
                     // Parse additional arguments
-                    while matches!(&self.current_token, Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
+                    while matches!(&self.current_token, Token::Integer(_) | Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
                         if let Some(arg) = self.parse_primary() {
                             args.push(arg);
                         } else {
@@ -847,10 +993,10 @@ impl Parser {
                         }
                     }
                     
-                    return Some(ASTNode::FunctionCall(fname, args));
+                    return Some(ASTNode::FunctionCall(fname, args, call_pos));
                 }
             }
-            ASTNode::Identifier(arg1_name, _) => {
+            ASTNode::Identifier(arg1_name, _, arg1_pos) => {
                 // Pattern: Identifier Identifier (e.g., "negate x2") or Identifier Number (e.g., "negate 16")
                 if let Token::Identifier(func_name) = &self.current_token.clone() {
                     let fname = func_name.clone();
@@ -858,10 +1004,10 @@ impl Parser {
                     
                     let (line, column) = self.lexer.get_position();
                     let pos = crate::ast::Position::new(line, column);
-                    let mut args = vec![ASTNode::Identifier(arg1_name.clone(), pos)]; // First argument
+                    let mut args = vec![ASTNode::Identifier(arg1_name.clone(), Binding::Unresolved, pos.clone())]; // First argument
                     
                     // Parse additional arguments
-                    while matches!(&self.current_token, Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
+                    while matches!(&self.current_token, Token::Integer(_) | Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
                         if let Some(arg) = self.parse_primary() {
                             args.push(arg);
                         } else {
@@ -869,15 +1015,15 @@ impl Parser {
                         }
                     }
                     
-                    return Some(ASTNode::FunctionCall(fname, args));
-                } else if let Token::Number(_) = &self.current_token {
+                    return Some(ASTNode::FunctionCall(fname, args, pos));
+                } else if matches!(self.current_token, Token::Integer(_) | Token::Number(_)) {
                     // Pattern: Identifier Number (e.g., "negate 16")
                     // This might be an expr function call where the first argument is an identifier
                     // and the function name is actually the identifier we just parsed
                     let mut args = Vec::new();
                     
                     // Parse arguments (starting with the number after the function name)
-                    while matches!(&self.current_token, Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
+                    while matches!(&self.current_token, Token::Integer(_) | Token::Number(_) | Token::Identifier(_) | Token::LeftParen) {
                         if let Some(arg) = self.parse_primary() {
                             args.push(arg);
                         } else {
@@ -887,21 +1033,13 @@ impl Parser {
                     
                     // If we have arguments, this is likely an expr call with the function name being the first identifier
                     if !args.is_empty() {
-                        return Some(ASTNode::FunctionCall(arg1_name.clone(), args));
+                        return Some(ASTNode::FunctionCall(arg1_name.clone(), args, arg1_pos.clone()));
                     }
                 }
             }
             _ => {}
         }
         
-        while let Some(op) = self.get_binary_op() {
-            self.advance();
-            let right = self.parse_expression()?;
-            let (line, column) = self.lexer.get_position();
-            let pos = crate::ast::Position::new(line, column);
-            left = ASTNode::BinaryOp(Box::new(left), op, Box::new(right), pos);
-        }
-        
         Some(left)
     }
     
@@ -969,6 +1107,11 @@ impl Parser {
 
             }
 
+            Token::Integer(n) => {
+                let value = *n;
+                self.advance();
+                Some(ASTNode::Integer(value))
+            }
             Token::Number(n) => {
 
                 let value = *n;
@@ -1032,20 +1175,32 @@ impl Parser {
                 
                 Some(ASTNode::IfExpr(Box::new(condition), Box::new(then_expr), Box::new(else_expr)))
             }
+            Token::Match => self.parse_match_expression(),
+            Token::Func => self.parse_lambda(),
+            Token::Not => {
+                // 逻辑非：!expr
+                self.advance();
+                let pos = self.current_position();
+                let expr = self.parse_primary()?;
+                Some(ASTNode::UnaryOp("!".to_string(), Box::new(expr), pos))
+            }
             Token::Minus => {
                 // Handle unary minus (negative numbers)
                 self.advance();
-                let expr = self.parse_primary()?;
+                let expr = self.parse_binary_expression(POWER_PRECEDENCE)?;
                 let (line, column) = self.lexer.get_position();
                 let pos = crate::ast::Position::new(line, column);
-                Some(ASTNode::BinaryOp(Box::new(ASTNode::Number(0.0)), "-".to_string(), Box::new(expr), pos))
+                Some(ASTNode::BinaryOp(Box::new(ASTNode::Integer(0)), "-".to_string(), Box::new(expr), pos))
             }
             Token::Identifier(name) => {
                 let func_name = name.clone();
                 self.advance();
+                let name_pos = self.current_position();
                 
                 // Check if this is a function call
-                if matches!(self.current_token, Token::LeftParen) {
+                let node = if matches!(self.current_token, Token::PathSep) {
+                    self.parse_enum_variant(func_name, name_pos)
+                } else if matches!(self.current_token, Token::LeftParen) {
                     self.advance(); // consume '('
                     
                     let mut args = Vec::new();
@@ -1063,40 +1218,14 @@ impl Parser {
                     }
                     self.advance(); // consume ')'
                     
-                    Some(ASTNode::FunctionCall(func_name, args))
-                } else if matches!(self.current_token, Token::LeftBracket) {
-                    // This is an array/list index access: name[index]
-                    let identifier_node = {
-                        let (line, column) = self.lexer.get_position();
-                        let pos = crate::ast::Position::new(line, column);
-                        ASTNode::Identifier(func_name, pos)
-                    };
-                    
-                    self.advance(); // consume '['
-                    
-                    let index_expr = self.parse_expression()?;
-                    
-                    if !matches!(self.current_token, Token::RightBracket) {
-                        return None;
-                    }
-                    self.advance(); // consume ']'
-
-                    
-
-                    // Get position for the index access
-
-                    let (line, column) = self.lexer.get_position();
-
-                    let pos = crate::ast::Position::new(line, column);
-
-                    
-
-                    Some(ASTNode::IndexAccess(Box::new(identifier_node), Box::new(index_expr), pos))
+                    Some(ASTNode::FunctionCall(func_name, args, name_pos))
                 } else {
                     let (line, column) = self.lexer.get_position();
                     let pos = crate::ast::Position::new(line, column);
-                    Some(ASTNode::Identifier(func_name, pos))
-                }
+                    Some(ASTNode::Identifier(func_name, Binding::Unresolved, pos))
+                }?;
+                
+                self.parse_postfix(node)
             }
             Token::LeftParen => {
                 self.advance(); // consume '('
@@ -1110,6 +1239,7 @@ impl Parser {
                 if let Token::Identifier(func_name) = &self.current_token.clone() {
                     let fname = func_name.clone();
                     self.advance();
+                    let call_pos = self.current_position();
                     
                     // Check if this is followed by another expression (for syntax like (2)multiply(8))
                     if matches!(self.current_token, Token::LeftParen) {
@@ -1132,22 +1262,39 @@ impl Parser {
                         }
                         self.advance(); // consume ')'
                         
-                        Some(ASTNode::FunctionCall(fname, args))
+                        Some(ASTNode::FunctionCall(fname, args, call_pos))
                     } else {
                         // Simple expr call: (expr)func_name
-                        Some(ASTNode::FunctionCall(fname, vec![expr]))
+                        Some(ASTNode::FunctionCall(fname, vec![expr], call_pos))
                     }
                 } else {
-                    Some(expr)
+                    // (func (x) => x * 2)(3) 或 (p).x
+                    self.parse_postfix(expr)
                 }
             }
             Token::LeftBrace => {
                 // Parse array/list initialization: {value1, value2, ...}
+                // or a map literal: {key1: value1, key2: value2, ...}
                 self.advance(); // consume '{'
+                let pos = self.current_position();
+                
+                // {:} 是空映射，{} 仍然是空列表
+                if matches!(self.current_token, Token::Colon) {
+                    self.advance(); // consume ':'
+                    if !matches!(self.current_token, Token::RightBrace) {
+                        return None;
+                    }
+                    self.advance(); // consume '}'
+                    return Some(ASTNode::MapLiteral(Vec::new(), pos));
+                }
                 
                 let mut elements = Vec::new();
                 while !matches!(self.current_token, Token::RightBrace) {
                     let element = self.parse_expression()?;
+                    // 第一个元素后面是冒号时按映射字面量解析
+                    if elements.is_empty() && matches!(self.current_token, Token::Colon) {
+                        return self.parse_map_entries(element, pos);
+                    }
                     elements.push(element);
                     
                     if matches!(self.current_token, Token::Comma) {
@@ -1165,9 +1312,8 @@ impl Parser {
                 }
                 self.advance(); // consume '}'
                 
-                // Return a special node that represents an array/list initialization
-                // We'll handle the distinction in the context where it's used
-                Some(ASTNode::FunctionCall("array_init".to_string(), elements))
+                // 在变量声明中用作数组或列表的初始值，其他位置求值为列表
+                Some(ASTNode::ListLiteral(elements))
             }
             Token::LeftBracket => {
                 // Parse an empty list: []
@@ -1179,7 +1325,7 @@ impl Parser {
                 self.advance(); // consume ']'
                 
                 // Return an empty list
-                Some(ASTNode::FunctionCall("list_init".to_string(), vec![]))
+                Some(ASTNode::ListLiteral(Vec::new()))
             }
             _ => {
                 // Handle undefined identifiers and syntax errors
@@ -1199,9 +1345,11 @@ impl Parser {
 
             Token::Divide => Some("/".to_string()),
 
-            Token::Less => Some("<".to_string()),
+            Token::Modulo => Some("%".to_string()),
+
+            Token::IntDivide => Some("~/".to_string()),
 
-            Token::Greater => Some(">".to_string()),
+            Token::Power => Some("**".to_string()),
 
             Token::LessEqual => Some("<=".to_string()),
 
@@ -1213,6 +1361,12 @@ impl Parser {
 
             Token::EqualEqual => Some("==".to_string()),
 
+            Token::NotEqual => Some("!=".to_string()),
+
+            Token::And => Some("&&".to_string()),
+
+            Token::Or => Some("||".to_string()),
+
             Token::Range => Some("..".to_string()),
 
             _ => None,
@@ -1220,6 +1374,685 @@ impl Parser {
         }
     }
     
+    /// 解析 struct Name { <type>field, field, ... }，字段类型可以省略
+    fn parse_struct_declaration(&mut self) -> Option<ASTNode> {
+        self.advance(); // consume 'struct'
+        let pos = self.current_position();
+        
+        let Token::Identifier(name) = self.current_token.clone() else {
+            return None;
+        };
+        self.advance();
+        
+        if !matches!(self.current_token, Token::LeftBrace) {
+            return None;
+        }
+        self.advance(); // consume '{'
+        
+        let mut fields: Vec<(String, Option<Type>)> = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            let field_type = if matches!(self.current_token, Token::LessThan) {
+                Some(self.parse_type_annotation()?)
+            } else {
+                None
+            };
+            
+            let field_pos = self.current_position();
+            let Token::Identifier(field) = self.current_token.clone() else {
+                self.error_context = Some(format!(
+                    "{}\n  = {}",
+                    error_messages::unexpected_token(&format!("{:?}", self.current_token)),
+                    error_messages::help_struct_declaration()
+                ));
+                self.error_position = Some(field_pos);
+                return None;
+            };
+            self.advance();
+            
+            if fields.iter().any(|(existing, _)| *existing == field) {
+                self.error_context = Some(error_messages::duplicate_struct_field(&name, &field));
+                self.error_position = Some(self.current_position());
+                return None;
+            }
+            fields.push((field, field_type));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance(); // consume ','
+            } else if !matches!(self.current_token, Token::RightBrace) {
+                self.error_context = Some(format!(
+                    "{}\n  = {}",
+                    error_messages::unexpected_token(&format!("{:?}", self.current_token)),
+                    error_messages::help_struct_declaration()
+                ));
+                self.error_position = Some(self.current_position());
+                return None;
+            }
+        }
+        self.advance(); // consume '}'
+        
+        Some(ASTNode::StructDecl(name, fields, pos))
+    }
+    
+    /// 解析 const <type>NAME = value;，类型可以省略，常量必须有初始值
+    fn parse_const_declaration(&mut self) -> Option<ASTNode> {
+        self.advance(); // consume 'const'
+        let const_type = if matches!(self.current_token, Token::LessThan) {
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+        
+        let pos = self.current_position();
+        let Token::Identifier(name) = self.current_token.clone() else {
+            return None;
+        };
+        self.advance();
+        
+        if !matches!(self.current_token, Token::Equal) {
+            self.error_context = Some(format!(
+                "{}\n  = {}\n  = {}",
+                error_messages::const_requires_initializer(),
+                error_messages::help_const_initializer(),
+                error_messages::example_const_initializer()
+            ));
+            self.error_position = Some(self.current_position());
+            return None;
+        }
+        self.advance(); // consume '='
+        
+        let value = self.parse_expression()?;
+        if matches!(self.current_token, Token::Semicolon) {
+            self.advance();
+        }
+        Some(ASTNode::ConstDecl(name, Binding::Unresolved, const_type, Box::new(value), pos))
+    }
+    
+    /// 解析 try { ... } catch (e) { ... } finally { ... }，catch 和 finally 可以省略其中一个
+    fn parse_try_statement(&mut self) -> Option<ASTNode> {
+        let pos = self.current_position();
+        self.advance(); // consume 'try'
+        if !matches!(self.current_token, Token::LeftBrace) {
+            return self.try_statement_error(error_messages::try_requires_block(), pos);
+        }
+        let body = self.parse_braced_block()?;
+        
+        let catch = if matches!(self.current_token, Token::Catch) {
+            let catch_pos = self.current_position();
+            self.advance(); // consume 'catch'
+            if !matches!(self.current_token, Token::LeftParen) {
+                return self.try_statement_error(error_messages::catch_requires_variable(), catch_pos);
+            }
+            self.advance(); // consume '('
+            let Token::Identifier(name) = self.current_token.clone() else {
+                return self.try_statement_error(error_messages::catch_requires_variable(), catch_pos);
+            };
+            self.advance();
+            if !matches!(self.current_token, Token::RightParen) {
+                return self.try_statement_error(error_messages::catch_requires_variable(), catch_pos);
+            }
+            self.advance(); // consume ')'
+            if !matches!(self.current_token, Token::LeftBrace) {
+                return self.try_statement_error(error_messages::try_requires_block(), catch_pos);
+            }
+            Some((name, self.parse_braced_block()?))
+        } else {
+            None
+        };
+        
+        let finally = if matches!(self.current_token, Token::Finally) {
+            let finally_pos = self.current_position();
+            self.advance(); // consume 'finally'
+            if !matches!(self.current_token, Token::LeftBrace) {
+                return self.try_statement_error(error_messages::try_requires_block(), finally_pos);
+            }
+            Some(self.parse_braced_block()?)
+        } else {
+            None
+        };
+        
+        if catch.is_none() && finally.is_none() {
+            return self.try_statement_error(error_messages::try_without_handler(), pos);
+        }
+        Some(ASTNode::Try(body, catch, finally, pos))
+    }
+    
+    fn array_size_error(&mut self, pos: Position) -> Option<ASTNode> {
+        self.error_context = Some(format!(
+            "{}\n  = {}\n  = {}",
+            error_messages::invalid_array_size(),
+            error_messages::help_array_size(),
+            error_messages::example_array_size()
+        ));
+        self.error_position = Some(pos);
+        None
+    }
+    
+    /// 把赋值目标 name[i].field 拆分为由外向内的下标和字段，目标中不能有函数调用
+    fn assignment_path(&mut self, target: ASTNode) -> Option<Vec<Accessor>> {
+        let mut path = Vec::new();
+        let mut node = target;
+        loop {
+            node = match node {
+                ASTNode::IndexAccess(inner, index_expr, _) => {
+                    path.push(Accessor::Index(*index_expr));
+                    *inner
+                }
+                ASTNode::FieldAccess(inner, field, _) => {
+                    path.push(Accessor::Field(field));
+                    *inner
+                }
+                ASTNode::Call(_, _, pos) => return self.unsupported_assignment_target(pos),
+                _ => break,
+            };
+        }
+        path.reverse();
+        Some(path)
+    }
+    
+    fn unsupported_assignment_target<T>(&mut self, pos: Position) -> Option<T> {
+        self.error_context = Some(format!(
+            "{}\n  = {}\n  = {}",
+            error_messages::unsupported_assignment_target(),
+            error_messages::help_assignment_target(),
+            error_messages::example_assignment_target()
+        ));
+        self.error_position = Some(pos);
+        None
+    }
+    
+    fn try_statement_error(&mut self, message: String, pos: Position) -> Option<ASTNode> {
+        self.error_context = Some(format!(
+            "{}\n  = {}\n  = {}",
+            message,
+            error_messages::help_try_syntax(),
+            error_messages::example_try_syntax()
+        ));
+        self.error_position = Some(pos);
+        None
+    }
+    
+    /// 解析 enum Name { Variant, Variant(<type>field, field), ... }，成员的字段与结构体字段的写法相同
+    fn parse_enum_declaration(&mut self) -> Option<ASTNode> {
+        self.advance(); // consume 'enum'
+        let pos = self.current_position();
+        
+        let Token::Identifier(name) = self.current_token.clone() else {
+            return None;
+        };
+        self.advance();
+        
+        if !matches!(self.current_token, Token::LeftBrace) {
+            return None;
+        }
+        self.advance(); // consume '{'
+        
+        let mut variants: Vec<Variant> = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            let Token::Identifier(variant) = self.current_token.clone() else {
+                return self.enum_declaration_error();
+            };
+            self.advance();
+            
+            if variants.iter().any(|(existing, _)| *existing == variant) {
+                self.error_context = Some(error_messages::duplicate_enum_variant(&name, &variant));
+                self.error_position = Some(self.current_position());
+                return None;
+            }
+            
+            let mut fields: Vec<Field> = Vec::new();
+            if matches!(self.current_token, Token::LeftParen) {
+                self.advance(); // consume '('
+                while !matches!(self.current_token, Token::RightParen) {
+                    let field_type = if matches!(self.current_token, Token::LessThan) {
+                        Some(self.parse_type_annotation()?)
+                    } else {
+                        None
+                    };
+                    let Token::Identifier(field) = self.current_token.clone() else {
+                        return self.enum_declaration_error();
+                    };
+                    self.advance();
+                    fields.push((field, field_type));
+                    
+                    if matches!(self.current_token, Token::Comma) {
+                        self.advance(); // consume ','
+                    } else if !matches!(self.current_token, Token::RightParen) {
+                        return self.enum_declaration_error();
+                    }
+                }
+                self.advance(); // consume ')'
+            }
+            variants.push((variant, fields));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance(); // consume ','
+            } else if !matches!(self.current_token, Token::RightBrace) {
+                return self.enum_declaration_error();
+            }
+        }
+        self.advance(); // consume '}'
+        
+        Some(ASTNode::EnumDecl(name, variants, pos))
+    }
+    
+    fn enum_declaration_error(&mut self) -> Option<ASTNode> {
+        self.error_context = Some(format!(
+            "{}\n  = {}",
+            error_messages::unexpected_token(&format!("{:?}", self.current_token)),
+            error_messages::help_enum_declaration()
+        ));
+        self.error_position = Some(self.current_position());
+        None
+    }
+    
+    /// 解析 Name::Variant 或 Name::Variant(args)，当前 token 是 ::
+    fn parse_enum_variant(&mut self, enum_name: String, pos: Position) -> Option<ASTNode> {
+        self.advance(); // consume '::'
+        let Token::Identifier(variant) = self.current_token.clone() else {
+            return None;
+        };
+        self.advance();
+        
+        let mut args = Vec::new();
+        if matches!(self.current_token, Token::LeftParen) {
+            self.advance(); // consume '('
+            while !matches!(self.current_token, Token::RightParen) {
+                args.push(self.parse_expression()?);
+                if matches!(self.current_token, Token::Comma) {
+                    self.advance();
+                } else if !matches!(self.current_token, Token::RightParen) {
+                    return None;
+                }
+            }
+            self.advance(); // consume ')'
+        }
+        
+        Some(ASTNode::EnumVariant(enum_name, variant, args, pos))
+    }
+    
+    /// 解析 match (value) { pattern => { body } ... }，分支体也可以是单条语句
+    fn parse_match_statement(&mut self) -> Option<ASTNode> {
+        let (subject, pos) = self.parse_match_subject()?;
+        
+        let mut arms = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            let pattern = self.parse_match_arm_pattern()?;
+            
+            let body = if matches!(self.current_token, Token::LeftBrace) {
+                self.parse_braced_block()?
+            } else {
+                vec![self.parse_statement()?]
+            };
+            arms.push((pattern, body));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance(); // consume ','
+            }
+        }
+        self.advance(); // consume '}'
+        
+        Some(ASTNode::Match(Box::new(subject), arms, pos))
+    }
+    
+    /// 解析 match (value) { pattern => expr, ... }，每个分支是一个表达式
+    fn parse_match_expression(&mut self) -> Option<ASTNode> {
+        let (subject, pos) = self.parse_match_subject()?;
+        
+        let mut arms = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            let pattern = self.parse_match_arm_pattern()?;
+            let value = self.parse_expression()?;
+            arms.push((pattern, value));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance(); // consume ','
+            } else if !matches!(self.current_token, Token::RightBrace) {
+                return None;
+            }
+        }
+        self.advance(); // consume '}'
+        
+        Some(ASTNode::MatchExpr(Box::new(subject), arms, pos))
+    }
+    
+    /// 解析 match (value) {，返回被匹配的表达式和 match 关键字之后的位置
+    fn parse_match_subject(&mut self) -> Option<(ASTNode, Position)> {
+        self.advance(); // consume 'match'
+        let pos = self.current_position();
+        
+        if !matches!(self.current_token, Token::LeftParen) {
+            self.error_context = Some("match requires parentheses around the value\n  = help: use syntax: match (value) { pattern => ... }\n  = example: match (x) { 1 => println(\"one\"), _ => println(\"other\") }".to_string());
+            return None;
+        }
+        self.advance(); // consume '('
+        
+        let subject = self.parse_expression()?;
+        
+        if !matches!(self.current_token, Token::RightParen) {
+            return None;
+        }
+        self.advance(); // consume ')'
+        
+        if !matches!(self.current_token, Token::LeftBrace) {
+            return None;
+        }
+        self.advance(); // consume '{'
+        
+        Some((subject, pos))
+    }
+    
+    /// 解析分支的模式和后面的 =>
+    fn parse_match_arm_pattern(&mut self) -> Option<Pattern> {
+        let pattern = self.parse_pattern()?;
+        
+        if !matches!(self.current_token, Token::FatArrow) {
+            self.error_context = Some(format!(
+                "{}\n  = {}",
+                error_messages::missing_fat_arrow(),
+                error_messages::help_match_arm()
+            ));
+            self.error_position = Some(self.current_position());
+            return None;
+        }
+        self.advance(); // consume '=>'
+        Some(pattern)
+    }
+    
+    /// 解析一个模式：_、字面量、start..end 或 Enum::Variant(bindings)
+    fn parse_pattern(&mut self) -> Option<Pattern> {
+        if let Token::Identifier(name) = self.current_token.clone() {
+            self.advance();
+            let pos = self.current_position();
+            if name == "_" {
+                return Some(Pattern::Wildcard);
+            }
+            if !matches!(self.current_token, Token::PathSep) {
+                return self.pattern_error(pos);
+            }
+            self.advance(); // consume '::'
+            
+            let Token::Identifier(variant) = self.current_token.clone() else {
+                return self.pattern_error(self.current_position());
+            };
+            self.advance();
+            
+            let mut bindings = Vec::new();
+            if matches!(self.current_token, Token::LeftParen) {
+                self.advance(); // consume '('
+                while !matches!(self.current_token, Token::RightParen) {
+                    let Token::Identifier(binding) = self.current_token.clone() else {
+                        return self.pattern_error(self.current_position());
+                    };
+                    self.advance();
+                    bindings.push((binding, Binding::Unresolved));
+                    
+                    if matches!(self.current_token, Token::Comma) {
+                        self.advance(); // consume ','
+                    } else if !matches!(self.current_token, Token::RightParen) {
+                        return self.pattern_error(self.current_position());
+                    }
+                }
+                self.advance(); // consume ')'
+            }
+            return Some(Pattern::Variant(name, variant, bindings, pos));
+        }
+        
+        let start = self.parse_pattern_literal()?;
+        if !matches!(self.current_token, Token::Range) {
+            return Some(Pattern::Literal(start));
+        }
+        self.advance(); // consume '..'
+        
+        let end = self.parse_pattern_literal()?;
+        // 范围的两端必须是数字
+        if [&start, &end].iter().any(|bound| !matches!(bound, ASTNode::Integer(_) | ASTNode::Number(_))) {
+            return self.pattern_error(self.current_position());
+        }
+        Some(Pattern::Range(start, end))
+    }
+    
+    /// 模式中的字面量，数字前面可以有负号
+    fn parse_pattern_literal(&mut self) -> Option<ASTNode> {
+        let negative = matches!(self.current_token, Token::Minus);
+        if negative {
+            self.advance(); // consume '-'
+        }
+        
+        let literal = match self.current_token.clone() {
+            Token::Integer(n) => ASTNode::Integer(if negative { -n } else { n }),
+            Token::Number(n) => ASTNode::Number(if negative { -n } else { n }),
+            Token::String(s) if !negative => ASTNode::String(s),
+            Token::True if !negative => ASTNode::Bool(true),
+            Token::False if !negative => ASTNode::Bool(false),
+            _ => return self.pattern_error(self.current_position()),
+        };
+        self.advance();
+        Some(literal)
+    }
+    
+    fn pattern_error<T>(&mut self, pos: Position) -> Option<T> {
+        self.error_context = Some(format!(
+            "{}\n  = {}",
+            error_messages::invalid_pattern(),
+            error_messages::help_match_arm()
+        ));
+        self.error_position = Some(pos);
+        None
+    }
+    
+    /// 解析 <type>，只接受基本类型
+    fn parse_type_annotation(&mut self) -> Option<Type> {
+        self.advance(); // consume '<'
+        let field_type = match self.current_token {
+            Token::Int => Type::Int,
+            Token::Str => Type::Str,
+            Token::Bool => Type::Bool,
+            Token::Float => Type::Float,
+            Token::Double => Type::Double,
+            _ => return None,
+        };
+        self.advance(); // consume type name
+        
+        if !matches!(self.current_token, Token::GreaterThan) {
+            return None;
+        }
+        self.advance(); // consume '>'
+        Some(field_type)
+    }
+    
+    /// 解析表达式后面的 .field 字段访问、(args) 调用和 [index] 索引，可以连续访问嵌套的字段、调用返回的函数或多维数组的元素
+    fn parse_postfix(&mut self, node: ASTNode) -> Option<ASTNode> {
+        let mut node = node;
+        loop {
+            if matches!(self.current_token, Token::Dot) {
+                self.advance(); // consume '.'
+                let Token::Identifier(field) = self.current_token.clone() else {
+                    self.error_context = Some(error_messages::expected_field_name());
+                    self.error_position = Some(self.current_position());
+                    return None;
+                };
+                self.advance();
+                let pos = self.current_position();
+                node = ASTNode::FieldAccess(Box::new(node), field, pos);
+            } else if matches!(self.current_token, Token::LeftParen) {
+                let pos = self.current_position();
+                let args = self.parse_call_arguments()?;
+                node = ASTNode::Call(Box::new(node), args, pos);
+            } else if matches!(self.current_token, Token::LeftBracket) {
+                // 数组/列表/映射索引访问：name[index]，可以连续写作 name[i][j]
+                self.advance(); // consume '['
+                let index_expr = self.parse_expression()?;
+                if !matches!(self.current_token, Token::RightBracket) {
+                    return None;
+                }
+                self.advance(); // consume ']'
+                let pos = self.current_position();
+                node = ASTNode::IndexAccess(Box::new(node), Box::new(index_expr), pos);
+            } else {
+                return Some(node);
+            }
+        }
+    }
+    
+    /// 解析 (arg1, arg2, ...)
+    fn parse_call_arguments(&mut self) -> Option<Vec<ASTNode>> {
+        self.advance(); // consume '('
+        let mut args = Vec::new();
+        while !matches!(self.current_token, Token::RightParen) {
+            args.push(self.parse_expression()?);
+            if matches!(self.current_token, Token::Comma) {
+                self.advance();
+            } else if !matches!(self.current_token, Token::RightParen) {
+                return None;
+            }
+        }
+        self.advance(); // consume ')'
+        Some(args)
+    }
+    
+    /// 解析匿名函数 func (a, b) { body }，或函数体只有一个返回值的 func (a, b) => expr
+    fn parse_lambda(&mut self) -> Option<ASTNode> {
+        self.advance(); // consume 'func'
+        let pos = self.current_position();
+        
+        if !matches!(self.current_token, Token::LeftParen) {
+            return None;
+        }
+        let params = self.parse_parameters()?;
+        let return_type = self.parse_return_type()?;
+        
+        // 与具名函数相同：break/continue 不能跨越函数体，return 只结束 lambda 本身
+        let saved_loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+        self.function_depth += 1;
+        let body = self.parse_lambda_body();
+        self.loop_depth = saved_loop_depth;
+        self.function_depth -= 1;
+        
+        Some(ASTNode::Lambda(params, return_type, body?, pos))
+    }
+    
+    /// 解析 (a, <double>b, ...)，当前 token 是左括号。参数的类型可以省略
+    fn parse_parameters(&mut self) -> Option<Vec<Param>> {
+        self.advance(); // consume '('
+        let mut params = Vec::new();
+        while !matches!(self.current_token, Token::RightParen) {
+            let param_type = if matches!(self.current_token, Token::LessThan) {
+                Some(self.parse_type_annotation()?)
+            } else {
+                None
+            };
+            let Token::Identifier(param) = self.current_token.clone() else {
+                return None;
+            };
+            params.push((param, param_type));
+            self.advance();
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance();
+            } else if !matches!(self.current_token, Token::RightParen) {
+                return None;
+            }
+        }
+        self.advance(); // consume ')'
+        Some(params)
+    }
+    
+    /// 解析参数列表后面可选的 -> type。外层的 `Option` 表示是否出错，内层表示是否声明了返回类型
+    fn parse_return_type(&mut self) -> Option<Option<Type>> {
+        if !matches!(self.current_token, Token::Arrow) {
+            return Some(None);
+        }
+        self.advance(); // consume '->'
+        let return_type = match self.current_token {
+            Token::Int => Type::Int,
+            Token::Str => Type::Str,
+            Token::Bool => Type::Bool,
+            Token::Float => Type::Float,
+            Token::Double => Type::Double,
+            _ => {
+                self.error_context = Some(format!(
+                    "{}\n  = {}\n  = {}",
+                    error_messages::invalid_return_type(),
+                    error_messages::help_return_type_syntax(),
+                    error_messages::example_return_type_syntax()
+                ));
+                self.error_position = Some(self.current_position());
+                return None;
+            }
+        };
+        self.advance(); // consume type name
+        Some(Some(return_type))
+    }
+    
+    fn parse_lambda_body(&mut self) -> Option<Vec<ASTNode>> {
+        if matches!(self.current_token, Token::FatArrow) {
+            let pos = self.current_position();
+            self.advance(); // consume '=>'
+            let expr = self.parse_expression()?;
+            return Some(vec![ASTNode::Return(Box::new(expr), pos)]);
+        }
+        
+        if !matches!(self.current_token, Token::LeftBrace) {
+            return None;
+        }
+        self.parse_braced_block()
+    }
+    
+    /// 解析 { statements }，当前 token 是左花括号。语句块内的任何错误（例如循环外的 break）都传递给外层报告
+    fn parse_braced_block(&mut self) -> Option<Vec<ASTNode>> {
+        self.advance(); // consume '{'
+        let mut statements = Vec::new();
+        while !matches!(self.current_token, Token::RightBrace) {
+            if matches!(self.current_token, Token::Semicolon) {
+                self.advance();
+                continue;
+            }
+            statements.push(self.parse_statement()?);
+        }
+        self.advance(); // consume '}'
+        Some(statements)
+    }
+    
+    /// 解析映射字面量中第一个键之后的部分，直到右花括号
+    fn parse_map_entries(&mut self, first_key: ASTNode, pos: Position) -> Option<ASTNode> {
+        let mut entries = Vec::new();
+        let mut key = first_key;
+        loop {
+            if !matches!(self.current_token, Token::Colon) {
+                self.error_context = Some(format!(
+                    "{}\n  = {}",
+                    error_messages::map_entry_missing_colon(),
+                    error_messages::help_map_literal()
+                ));
+                self.error_position = Some(self.current_position());
+                return None;
+            }
+            self.advance(); // consume ':'
+            
+            let value = self.parse_expression()?;
+            entries.push((key, value));
+            
+            if matches!(self.current_token, Token::Comma) {
+                self.advance(); // consume ','
+            } else if !matches!(self.current_token, Token::RightBrace) {
+                return None;
+            }
+            // 允许最后一个键值对后面有逗号
+            if matches!(self.current_token, Token::RightBrace) {
+                break;
+            }
+            key = self.parse_expression()?;
+        }
+        self.advance(); // consume '}'
+        
+        Some(ASTNode::MapLiteral(entries, pos))
+    }
+    
+    fn current_position(&self) -> Position {
+        let (line, column) = self.lexer.get_position();
+        Position::new(line, column)
+    }
+    
     fn advance(&mut self) {
         self.current_token = self.lexer.next_token();
     }
@@ -1,5 +1,5 @@
 use std::io::{self, Write};
-use crate::interpreter::Interpreter;
+use ecl::{Engine, Interpreter};
 
 pub struct Repl {
     interpreter: Interpreter,
@@ -20,6 +20,11 @@ impl Repl {
         }
     }
     
+    pub fn with_engine(mut self, engine: Engine) -> Self {
+        self.interpreter.set_engine(engine);
+        self
+    }
+    
     pub fn run(&mut self) {
         println!("╔═══════════════════════════════════════╗");
         println!("║     ECL (ECL Command Language)       ║");
@@ -149,14 +154,15 @@ impl Repl {
     }
     
     fn execute_code(&mut self, code: &str) {
-        // 为REPL模式设置特殊的文件路径和源代码
-        let source_lines: Vec<String> = vec![code.to_string()];
-        self.interpreter = Interpreter::new()
-            .with_source("<repl>".to_string(), source_lines);
+        // 为REPL模式设置特殊的文件路径和源代码，变量和函数在多次输入之间保留
+        let source_lines: Vec<String> = code.lines().map(|s| s.to_string()).collect();
+        self.interpreter.set_source("<repl>".to_string(), source_lines);
         
         println!("📝 Executing: {}", code);
         println!("─────────────────────────────────");
-        self.interpreter.run(code);
+        if let Err(error) = self.interpreter.run(code) {
+            eprintln!("{}", error);
+        }
         println!("─────────────────────────────────");
     }
     
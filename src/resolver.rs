@@ -0,0 +1,620 @@
+// 与语法分析器一样，解析错误直接以 CompilerError 返回
+#![allow(clippy::result_large_err)]
+
+use std::collections::{HashMap, HashSet};
+use crate::ast::{ASTNode, Accessor, Binding, Param, Pattern, Position};
+use crate::environment::{GlobalSlots, ScopeKind};
+use crate::error::{create_undefined_identifier_error, error_messages, CompilerError};
+use crate::interpreter::builtins::Builtin;
+
+/// 解析时的一层局部作用域：按声明顺序记录变量名，名称的下标就是运行时的槽位
+struct Scope {
+    kind: ScopeKind,
+    names: Vec<String>,
+    constants: HashMap<usize, String>, // 常量的槽位和声明位置
+}
+
+impl Scope {
+    fn new(kind: ScopeKind) -> Self {
+        Self { kind, names: Vec::new(), constants: HashMap::new() }
+    }
+
+    /// 在同一作用域中重复声明时复用原来的槽位
+    fn declare(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.names.iter().position(|existing| existing == name) {
+            return slot;
+        }
+        self.names.push(name.to_string());
+        self.names.len() - 1
+    }
+}
+
+/// 在执行之前为每个变量名确定绑定，并报告未定义的变量。
+///
+/// 作用域规则与解释器相同：每个语句块是一层新的作用域，函数体只能看到自己的局部变量和全局变量，
+/// 给不存在的变量赋值会在当前作用域中声明它。
+pub struct Resolver<'a> {
+    file_path: &'a str,
+    source_lines: &'a [String],
+    scopes: Vec<Scope>,              // 当前函数或顶层代码中由外向内的局部作用域
+    in_function: bool,
+    globals: HashSet<String>,         // 执行到当前位置时已经声明的全局变量
+    program_globals: HashSet<String>, // 程序中所有的全局变量，函数体可以使用在函数之后声明的全局变量
+    has_imports: bool,                // 导入的文件可能定义任意全局变量，这时全局变量只能在运行时检查
+    enums: HashMap<String, Vec<(String, usize)>>, // 已知的枚举：成员名和成员携带的值的个数
+    functions: HashSet<String>,       // 已知的具名函数，函数名可以作为值使用
+    constants: HashMap<String, Option<String>>, // 已经声明的全局常量和声明位置，内置常量没有声明位置
+    program_constants: HashMap<String, String>,   // 程序中所有的全局常量，函数体中不能给之后声明的常量赋值
+    global_slots: &'a mut GlobalSlots,  // 解释器中全局变量名对应的槽位
+}
+
+impl<'a> Resolver<'a> {
+    /// `known_globals` 是执行之前已经存在的全局变量，例如宿主程序设置的变量或 REPL 中之前定义的变量；
+    /// 程序中出现的全局变量名在 `global_slots` 中分配槽位
+    pub fn new(file_path: &'a str, source_lines: &'a [String], known_globals: impl IntoIterator<Item = String>, global_slots: &'a mut GlobalSlots) -> Self {
+        let globals: HashSet<String> = known_globals.into_iter().collect();
+        Self {
+            file_path,
+            source_lines,
+            scopes: Vec::new(),
+            in_function: false,
+            program_globals: globals.clone(),
+            globals,
+            has_imports: false,
+            enums: HashMap::new(),
+            functions: HashSet::new(),
+            constants: HashMap::new(),
+            program_constants: HashMap::new(),
+            global_slots,
+        }
+    }
+    
+    /// 执行之前已经定义的具名函数，例如 REPL 中之前定义的函数
+    pub fn with_functions(mut self, functions: impl IntoIterator<Item = String>) -> Self {
+        self.functions.extend(functions);
+        self
+    }
+    
+    /// 执行之前已经存在的全局常量和声明位置，例如 PI 或 REPL 中之前声明的常量
+    pub fn with_constants(mut self, constants: impl IntoIterator<Item = (String, Option<String>)>) -> Self {
+        self.constants.extend(constants);
+        self
+    }
+
+    /// 执行之前已经声明的枚举，例如 REPL 中之前定义的枚举
+    pub fn with_enums(mut self, enums: impl IntoIterator<Item = (String, Vec<(String, usize)>)>) -> Self {
+        self.enums.extend(enums);
+        self
+    }
+
+    /// 解析一段顶层程序，填写其中所有变量名的绑定
+    pub fn resolve(mut self, program: &mut [ASTNode]) -> Result<(), CompilerError> {
+        self.has_imports = contains_import(program);
+        // 枚举在整个程序中可见，函数体可以使用在函数之后声明的枚举
+        collect_enums(program, &mut self.enums);
+        collect_functions(program, &mut self.functions);
+        for node in program.iter() {
+            if let Some(name) = declared_global(node) {
+                self.program_globals.insert(name.to_string());
+                // 程序可以声明与内置常量同名的全局变量
+                if !matches!(node, ASTNode::Assign(..) | ASTNode::Input(..))
+                    && matches!(self.constants.get(name), Some(None))
+                {
+                    self.constants.remove(name);
+                }
+            }
+            if let ASTNode::ConstDecl(name, _, _, _, pos) = node {
+                self.program_constants.insert(name.clone(), self.location(pos));
+            }
+        }
+
+        for node in program.iter_mut() {
+            self.resolve_statement(node)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_statement(&mut self, node: &mut ASTNode) -> Result<(), CompilerError> {
+        match node {
+            ASTNode::Print(expr) | ASTNode::Println(expr) | ASTNode::Return(expr, _) => {
+                self.resolve_expression(expr)?;
+            }
+            ASTNode::Var(name, binding, expr, pos) | ASTNode::TypedVar(name, binding, _, expr, pos) => {
+                // 初始值中的同名变量指的是外层的变量
+                self.resolve_expression(expr)?;
+                self.check_redeclaration(name, pos)?;
+                *binding = self.declare(name);
+            }
+            ASTNode::ArrayDecl(name, binding, _, _, init_values, pos) | ASTNode::ListDecl(name, binding, init_values, pos) => {
+                for init in init_values.iter_mut() {
+                    self.resolve_expression(init)?;
+                }
+                self.check_redeclaration(name, pos)?;
+                *binding = self.declare(name);
+            }
+            ASTNode::ConstDecl(name, binding, _, expr, pos) => {
+                self.resolve_expression(expr)?;
+                self.check_redeclaration(name, pos)?;
+                *binding = self.declare(name);
+                let location = self.location(pos);
+                match *binding {
+                    Binding::Local { slot, .. } => {
+                        if let Some(scope) = self.scopes.last_mut() {
+                            scope.constants.insert(slot, location);
+                        }
+                    }
+                    _ => {
+                        self.constants.insert(name.clone(), Some(location));
+                    }
+                }
+            }
+            ASTNode::Assign(name, binding, expr, pos) => {
+                self.resolve_expression(expr)?;
+                *binding = self.resolve_assignment(name);
+                self.check_not_constant(name, *binding, pos)?;
+            }
+            ASTNode::PathAssign(name, binding, path, value_expr, pos) => {
+                // 只能修改已有变量中的元素或字段
+                for accessor in path.iter_mut() {
+                    if let Accessor::Index(index_expr) = accessor {
+                        self.resolve_expression(index_expr)?;
+                    }
+                }
+                self.resolve_expression(value_expr)?;
+                *binding = self.resolve_read(name, pos)?;
+                self.check_not_constant(name, *binding, pos)?;
+            }
+            ASTNode::Input(prompt, name, binding, pos) => {
+                self.resolve_expression(prompt)?;
+                *binding = self.resolve_assignment(name);
+                self.check_not_constant(name, *binding, pos)?;
+            }
+            ASTNode::For(var, range_expr, body, _) => {
+                self.resolve_expression(range_expr)?;
+                self.scopes.push(Scope::new(ScopeKind::Block));
+                self.declare(var);
+                let result = self.resolve_statements(body);
+                self.scopes.pop();
+                result?;
+            }
+            ASTNode::While(condition, body) => {
+                self.resolve_expression(condition)?;
+                self.resolve_block(body)?;
+            }
+            ASTNode::Try(body, catch, finally, _) => {
+                self.resolve_block(body)?;
+                if let Some((name, handler)) = catch {
+                    // 错误值是 catch 语句块作用域中的第一个变量
+                    self.scopes.push(Scope::new(ScopeKind::Block));
+                    self.declare(name);
+                    let result = self.resolve_statements(handler);
+                    self.scopes.pop();
+                    result?;
+                }
+                if let Some(cleanup) = finally {
+                    self.resolve_block(cleanup)?;
+                }
+            }
+            ASTNode::Throw(expr, _) => self.resolve_expression(expr)?,
+            ASTNode::If(condition, then_branch, else_branch) => {
+                self.resolve_expression(condition)?;
+                self.resolve_block(then_branch)?;
+                if let Some(else_stmts) = else_branch {
+                    self.resolve_block(else_stmts)?;
+                }
+            }
+            ASTNode::Function(name, params, _, body, _) => {
+                self.resolve_function(name, params.iter().map(|(param, _)| param), body)?;
+            }
+            ASTNode::Expr(name, typed_params, body) => {
+                self.resolve_function(name, typed_params.iter().map(|(_, param)| param), body)?;
+            }
+            ASTNode::FunctionCall(..) | ASTNode::Call(..) => self.resolve_expression(node)?,
+            ASTNode::Match(subject, arms, pos) => {
+                self.resolve_expression(subject)?;
+                self.check_match(arms.iter().map(|(pattern, _)| pattern), pos)?;
+                for (pattern, body) in arms.iter_mut() {
+                    self.scopes.push(Scope::new(ScopeKind::Block));
+                    self.declare_pattern(pattern);
+                    let result = self.resolve_statements(body);
+                    self.scopes.pop();
+                    result?;
+                }
+            }
+            // 其他表达式作为语句时不会执行
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn resolve_statements(&mut self, statements: &mut [ASTNode]) -> Result<(), CompilerError> {
+        statements.iter_mut().try_for_each(|stmt| self.resolve_statement(stmt))
+    }
+
+    /// 在新的块作用域中解析语句块
+    fn resolve_block(&mut self, statements: &mut [ASTNode]) -> Result<(), CompilerError> {
+        self.scopes.push(Scope::new(ScopeKind::Block));
+        let result = self.resolve_statements(statements);
+        self.scopes.pop();
+        result
+    }
+
+    /// 函数体从一个只包含参数的新作用域开始，看不到定义它的位置的局部变量
+    fn resolve_function<'p>(&mut self, name: &str, params: impl Iterator<Item = &'p String>, body: &mut [ASTNode]) -> Result<(), CompilerError> {
+        let mut function_scope = Scope::new(ScopeKind::Function(name.into()));
+        for param in params {
+            function_scope.declare(param);
+        }
+
+        let saved_scopes = std::mem::replace(&mut self.scopes, vec![function_scope]);
+        let saved_in_function = std::mem::replace(&mut self.in_function, true);
+        let result = self.resolve_statements(body);
+        self.scopes = saved_scopes;
+        self.in_function = saved_in_function;
+        result
+    }
+
+    /// lambda 的函数作用域位于定义它的位置的作用域之内，所以函数体可以使用外层的局部变量
+    fn resolve_lambda(&mut self, params: &[Param], body: &mut [ASTNode]) -> Result<(), CompilerError> {
+        let mut function_scope = Scope::new(ScopeKind::Function("<lambda>".into()));
+        for (param, _) in params {
+            function_scope.declare(param);
+        }
+        
+        self.scopes.push(function_scope);
+        // lambda 可能在之后才被调用，与函数体一样可以使用之后声明的全局变量
+        let saved_in_function = std::mem::replace(&mut self.in_function, true);
+        let result = self.resolve_statements(body);
+        self.in_function = saved_in_function;
+        self.scopes.pop();
+        result
+    }
+    
+    fn resolve_expression(&mut self, node: &mut ASTNode) -> Result<(), CompilerError> {
+        // 局部变量中保存的函数按值调用，局部变量遮蔽同名的具名函数
+        if let ASTNode::FunctionCall(name, args, pos) = node && let Some(binding) = self.lookup_local(name) {
+            let callee = ASTNode::Identifier(std::mem::take(name), binding, pos.clone());
+            *node = ASTNode::Call(Box::new(callee), std::mem::take(args), pos.clone());
+        }
+        
+        match node {
+            ASTNode::Identifier(name, binding, pos) => {
+                *binding = self.resolve_read(name, pos)?;
+            }
+            ASTNode::IndexAccess(array_expr, index_expr, _) => {
+                self.resolve_expression(array_expr)?;
+                self.resolve_expression(index_expr)?;
+            }
+            ASTNode::FunctionCall(name, args, pos) => {
+                for arg in args.iter_mut() {
+                    self.resolve_expression(arg)?;
+                }
+                // push(xs, 1) 等内置函数会修改第一个参数中的变量
+                if Builtin::lookup(name).is_some_and(Builtin::mutates)
+                    && !self.functions.contains(name.as_str())
+                    && let Some(ASTNode::Identifier(target, binding, _)) = args.first()
+                {
+                    self.check_not_constant(target, *binding, pos)?;
+                }
+            }
+            ASTNode::Call(callee, args, _) => {
+                self.resolve_expression(callee)?;
+                for arg in args.iter_mut() {
+                    self.resolve_expression(arg)?;
+                }
+            }
+            ASTNode::Lambda(params, _, body, _) => self.resolve_lambda(params, body)?,
+            ASTNode::BinaryOp(left, _, right, _) => {
+                self.resolve_expression(left)?;
+                self.resolve_expression(right)?;
+            }
+            ASTNode::UnaryOp(_, expr, _) | ASTNode::TypeConversion(_, expr, _) | ASTNode::FieldAccess(expr, _, _) => {
+                self.resolve_expression(expr)?;
+            }
+            ASTNode::ListLiteral(elements) => {
+                for element in elements.iter_mut() {
+                    self.resolve_expression(element)?;
+                }
+            }
+            ASTNode::MapLiteral(entries, _) => {
+                for (key, value) in entries.iter_mut() {
+                    self.resolve_expression(key)?;
+                    self.resolve_expression(value)?;
+                }
+            }
+            ASTNode::IfExpr(condition, then_expr, else_expr) => {
+                self.resolve_expression(condition)?;
+                self.resolve_expression(then_expr)?;
+                self.resolve_expression(else_expr)?;
+            }
+            ASTNode::EnumVariant(enum_name, variant, args, pos) => {
+                self.check_variant(enum_name, variant, args.len(), pos)?;
+                for arg in args.iter_mut() {
+                    self.resolve_expression(arg)?;
+                }
+            }
+            ASTNode::MatchExpr(subject, arms, pos) => {
+                self.resolve_expression(subject)?;
+                self.check_match(arms.iter().map(|(pattern, _)| pattern), pos)?;
+                for (pattern, arm_expr) in arms.iter_mut() {
+                    self.scopes.push(Scope::new(ScopeKind::Block));
+                    self.declare_pattern(pattern);
+                    let result = self.resolve_expression(arm_expr);
+                    self.scopes.pop();
+                    result?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 由内向外查找局部变量
+    fn lookup_local(&self, name: &str) -> Option<Binding> {
+        self.scopes.iter().rev().enumerate().find_map(|(depth, scope)| {
+            let slot = scope.names.iter().position(|existing| existing == name)?;
+            Some(Binding::Local { depth, slot })
+        })
+    }
+
+    /// 函数可能在之后才被调用，所以函数体中可以使用程序中任意位置声明的全局变量；
+    /// 顶层代码只能使用已经声明过的全局变量
+    fn is_global(&self, name: &str) -> bool {
+        if self.in_function {
+            self.program_globals.contains(name)
+        } else {
+            self.globals.contains(name)
+        }
+    }
+
+    fn resolve_read(&mut self, name: &str, pos: &Position) -> Result<Binding, CompilerError> {
+        if let Some(binding) = self.lookup_local(name) {
+            return Ok(binding);
+        }
+        if self.has_imports || self.is_global(name) || self.functions.contains(name) {
+            return Ok(self.global(name));
+        }
+        Err(self.undefined_identifier_error(name, pos))
+    }
+
+    /// 给已有的变量赋值，变量不存在时在当前作用域中声明
+    fn resolve_assignment(&mut self, name: &str) -> Binding {
+        match self.lookup_local(name) {
+            Some(binding) => binding,
+            None if self.is_global(name) => self.global(name),
+            None => self.declare(name),
+        }
+    }
+
+    /// 在当前作用域中声明变量，顶层代码中声明的是全局变量
+    fn declare(&mut self, name: &str) -> Binding {
+        match self.scopes.last_mut() {
+            Some(scope) => Binding::Local { depth: 0, slot: scope.declare(name) },
+            None => {
+                self.globals.insert(name.to_string());
+                self.global(name)
+            }
+        }
+    }
+
+    fn global(&mut self, name: &str) -> Binding {
+        Binding::Global { slot: self.global_slots.slot(name) }
+    }
+
+    /// 绑定指向的常量的声明位置，内置常量的声明位置是 `None`；不是常量时返回 `None`
+    fn constant_origin(&self, name: &str, binding: Binding) -> Option<Option<&str>> {
+        match binding {
+            Binding::Local { depth, slot } => {
+                let scope = &self.scopes[self.scopes.len() - 1 - depth];
+                scope.constants.get(&slot).map(|origin| Some(origin.as_str()))
+            }
+            Binding::Global { .. } => match self.constants.get(name) {
+                Some(origin) => Some(origin.as_deref()),
+                None if self.in_function => self.program_constants.get(name).map(|origin| Some(origin.as_str())),
+                None => None,
+            },
+            Binding::Unresolved => None,
+        }
+    }
+
+    /// 常量在声明之后不能被赋值或修改
+    fn check_not_constant(&self, name: &str, binding: Binding, pos: &Position) -> Result<(), CompilerError> {
+        let Some(origin) = self.constant_origin(name, binding) else {
+            return Ok(());
+        };
+        let note = match origin {
+            Some(location) => error_messages::constant_declared_at(name, location),
+            None => error_messages::builtin_constant(name),
+        };
+        Err(self.error_at(error_messages::assign_to_constant(name), pos)
+            .with_help(error_messages::help_assign_to_constant())
+            .with_note(note))
+    }
+
+    /// 同一作用域中已经声明的常量不能再次声明
+    fn check_redeclaration(&self, name: &str, pos: &Position) -> Result<(), CompilerError> {
+        let origin = match self.scopes.last() {
+            Some(scope) => scope.names.iter().position(|existing| existing == name)
+                .and_then(|slot| scope.constants.get(&slot))
+                .map(String::as_str),
+            None => self.constants.get(name).and_then(Option::as_deref),
+        };
+        match origin {
+            Some(location) => Err(self.error_at(error_messages::redeclare_constant(name), pos)
+                .with_note(error_messages::constant_declared_at(name, location))),
+            None => Ok(()),
+        }
+    }
+
+    /// 声明位置的 `文件:行:列` 形式
+    fn location(&self, pos: &Position) -> String {
+        format!("{}:{}:{}", self.file_path, pos.line, pos.column)
+    }
+
+    /// 在分支的作用域中声明模式绑定的变量，名称为 _ 的值不占用槽位
+    fn declare_pattern(&mut self, pattern: &mut Pattern) {
+        if let Pattern::Variant(_, _, bindings, _) = pattern {
+            for (name, binding) in bindings.iter_mut().filter(|(name, _)| name != "_") {
+                *binding = self.declare(name);
+            }
+        }
+    }
+    
+    /// 检查枚举成员是否存在以及携带的值的个数。导入的文件可能声明任意枚举，这时未知的枚举留到运行时检查
+    fn check_variant(&self, enum_name: &str, variant: &str, count: usize, pos: &Position) -> Result<(), CompilerError> {
+        let Some(variants) = self.enums.get(enum_name) else {
+            if self.has_imports {
+                return Ok(());
+            }
+            return Err(self.error_at(error_messages::undefined_enum(enum_name), pos));
+        };
+        let Some((_, expected)) = variants.iter().find(|(name, _)| name == variant) else {
+            let names: Vec<&str> = variants.iter().map(|(name, _)| name.as_str()).collect();
+            return Err(self.error_at(error_messages::unknown_variant(enum_name, variant), pos)
+                .with_help(error_messages::help_enum_variants(enum_name, &names.join(", "))));
+        };
+        if *expected != count {
+            return Err(self.error_at(error_messages::variant_payload_count(enum_name, variant, *expected, count), pos));
+        }
+        Ok(())
+    }
+    
+    /// 检查 match 的模式，没有 _ 分支时要求分支覆盖所有可能的值：
+    /// 枚举的每个成员，或布尔值的 true 和 false。只有其他字面量和范围时无法覆盖所有值
+    fn check_match<'p>(&self, patterns: impl Iterator<Item = &'p Pattern>, pos: &Position) -> Result<(), CompilerError> {
+        let mut has_wildcard = false;
+        let mut matched_enum: Option<&str> = None;
+        let mut covered_variants: Vec<&str> = Vec::new();
+        let mut covered_bools: Vec<bool> = Vec::new();
+        let mut has_other_literals = false;
+        
+        for pattern in patterns {
+            match pattern {
+                Pattern::Wildcard => has_wildcard = true,
+                Pattern::Variant(enum_name, variant, bindings, variant_pos) => {
+                    self.check_variant(enum_name, variant, bindings.len(), variant_pos)?;
+                    if let Some(expected) = matched_enum && expected != enum_name {
+                        return Err(self.error_at(error_messages::mixed_enum_patterns(expected, enum_name), variant_pos));
+                    }
+                    matched_enum = Some(enum_name);
+                    covered_variants.push(variant);
+                }
+                Pattern::Literal(ASTNode::Bool(b)) => covered_bools.push(*b),
+                Pattern::Literal(_) | Pattern::Range(..) => has_other_literals = true,
+            }
+        }
+        if has_wildcard {
+            return Ok(());
+        }
+        
+        let missing: Vec<String> = match matched_enum {
+            // 未知的枚举来自导入的文件，无法检查
+            Some(enum_name) => match self.enums.get(enum_name) {
+                Some(variants) => variants.iter()
+                    .filter(|(variant, _)| !covered_variants.contains(&variant.as_str()))
+                    .map(|(variant, _)| format!("{}::{}", enum_name, variant))
+                    .collect(),
+                None => Vec::new(),
+            },
+            None if !covered_bools.is_empty() && !has_other_literals => [true, false].into_iter()
+                .filter(|b| !covered_bools.contains(b))
+                .map(|b| b.to_string())
+                .collect(),
+            None => {
+                return Err(self.error_at(error_messages::non_exhaustive_literal_match(), pos)
+                    .with_help(error_messages::help_non_exhaustive_match()));
+            }
+        };
+        if missing.is_empty() {
+            return Ok(());
+        }
+        Err(self.error_at(error_messages::non_exhaustive_match(&missing.join(", ")), pos)
+            .with_help(error_messages::help_non_exhaustive_match()))
+    }
+    
+    fn error_at(&self, message: String, pos: &Position) -> CompilerError {
+        let source_line = pos.line.checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .cloned()
+            .unwrap_or_default();
+        CompilerError::new(message, pos.line, pos.column, self.file_path.to_string(), source_line)
+    }
+    
+    fn undefined_identifier_error(&self, name: &str, pos: &Position) -> CompilerError {
+        let source_line = pos.line.checked_sub(1)
+            .and_then(|index| self.source_lines.get(index))
+            .map(String::as_str)
+            .unwrap_or("");
+
+        // 与运行时的诊断信息一样，由内向外列出查找过的作用域
+        let mut searched: Vec<String> = self.scopes.iter().rev().map(|scope| scope.kind.describe()).collect();
+        searched.push(ScopeKind::Global.describe());
+
+        create_undefined_identifier_error(name, pos.line, pos.column, self.file_path, source_line)
+            .with_note(format!("searched {}", searched.join(" -> ")))
+    }
+}
+
+/// 顶层语句声明的全局变量
+fn declared_global(node: &ASTNode) -> Option<&str> {
+    match node {
+        ASTNode::Var(name, ..)
+        | ASTNode::TypedVar(name, ..)
+        | ASTNode::ArrayDecl(name, ..)
+        | ASTNode::ListDecl(name, ..)
+        | ASTNode::ConstDecl(name, ..)
+        | ASTNode::Assign(name, ..)
+        | ASTNode::Input(_, name, ..) => Some(name),
+        _ => None,
+    }
+}
+
+/// 语句中直接包含的语句块：循环体、函数体、if 的分支和 match 语句的分支
+pub(crate) fn nested_blocks(node: &ASTNode) -> Vec<&[ASTNode]> {
+    match node {
+        ASTNode::For(_, _, body, _) | ASTNode::While(_, body) | ASTNode::Function(_, _, _, body, _) | ASTNode::Expr(_, _, body) => {
+            vec![body]
+        }
+        ASTNode::If(_, then_branch, else_branch) => {
+            std::iter::once(then_branch.as_slice()).chain(else_branch.as_deref()).collect()
+        }
+        ASTNode::Match(_, arms, _) => arms.iter().map(|(_, body)| body.as_slice()).collect(),
+        ASTNode::Try(body, catch, finally, _) => std::iter::once(body.as_slice())
+            .chain(catch.iter().map(|(_, handler)| handler.as_slice()))
+            .chain(finally.as_deref())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 程序中是否有 import 语句，包括函数体和语句块中的
+fn contains_import(nodes: &[ASTNode]) -> bool {
+    nodes.iter().any(|node| {
+        matches!(node, ASTNode::Import(..)) || nested_blocks(node).into_iter().any(contains_import)
+    })
+}
+
+/// 程序中定义的所有具名函数，包括函数体和语句块中的
+fn collect_functions(nodes: &[ASTNode], functions: &mut HashSet<String>) {
+    for node in nodes {
+        if let ASTNode::Function(name, ..) | ASTNode::Expr(name, ..) = node {
+            functions.insert(name.clone());
+        }
+        for block in nested_blocks(node) {
+            collect_functions(block, functions);
+        }
+    }
+}
+
+/// 程序中声明的所有枚举，包括函数体和语句块中的
+fn collect_enums(nodes: &[ASTNode], enums: &mut HashMap<String, Vec<(String, usize)>>) {
+    for node in nodes {
+        if let ASTNode::EnumDecl(name, variants, _) = node {
+            let variants = variants.iter().map(|(variant, fields)| (variant.clone(), fields.len())).collect();
+            enums.insert(name.clone(), variants);
+        }
+        for block in nested_blocks(node) {
+            collect_enums(block, enums);
+        }
+    }
+}
@@ -3,16 +3,26 @@ pub enum Token {
     Print,
     Println,
     Var,
+    Const,
     For,
     In,
     If,
     Else,
     While,
+    Break,
+    Continue,
     Input,
     Func,
     Expr,
     Return,
     Import,
+    Struct,
+    Enum,
+    Match,
+    Try,
+    Catch,
+    Finally,
+    Throw,
     True,
     False,
     LeftParen,
@@ -24,17 +34,26 @@ pub enum Token {
     Semicolon,
     Comma,
     Colon,
+    PathSep,        // :: 枚举成员路径，例如 Shape::Circle
+    FatArrow,       // => match 分支
+    Arrow,          // -> 函数的返回类型
     Equal,
     EqualEqual,
+    NotEqual,       // !=
+    And,            // &&
+    Or,             // ||
+    Not,            // !
     Plus,
     Minus,
     Multiply,
     Divide,
-    Less,           // 已使用，用于比较操作
-    Greater,        // 已使用，用于比较操作
+    Modulo,         // %
+    IntDivide,      // ~/ 整数除法（// 已用于注释）
+    Power,          // ** 或 ^
     LessEqual,      // <=
     GreaterEqual,   // >=
     Range,  // .. 运算符
+    Dot,            // . 结构体字段访问
     LessThan,       // 已使用，用于类型声明 <type>name
     GreaterThan,    // 已使用，用于类型声明 <type>name
     // 类型关键字
@@ -44,7 +63,8 @@ pub enum Token {
     Float,
     Double,
     Identifier(String),
-    Number(f64),
+    Integer(i64),   // 整数字面量
+    Number(f64),    // 浮点数字面量
     String(String),
     Error(String),
     Eof,
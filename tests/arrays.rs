@@ -0,0 +1,128 @@
+//! 定长数组：多维数组的声明与默认值，嵌套下标的读写与元素类型转换，越界、数组长度和初始值个数的诊断
+
+mod common;
+
+use ecl::Value;
+use common::{run_both, run_err_both, str};
+
+#[test]
+fn multidimensional_arrays_are_read_and_written_by_nested_indices() {
+    let source = "
+        var <int>grid[3][4] = 0;
+        for i in 0..3 {
+            for j in 0..4 {
+                grid[i][j] = i * 4 + j;
+            }
+        }
+        grid[0][0] = \"42\";
+        var corner = grid[2][3];
+    ";
+    run_both(source)
+        .assert_text("grid", "[[42, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11]]")
+        .assert_global("corner", Value::Int(11));
+}
+
+#[test]
+fn nested_list_literals_initialize_each_dimension() {
+    let cases = [
+        ("var <double>a[2][2] = {{1, 2}, {3, 4}};", "[[1, 2], [3, 4]]"),
+        ("var <int>a[3][2] = {{1, 2}, 5};", "[[1, 2], [5, 5], [0, 0]]"),
+        ("var <int>a[2][3] = {{7}};", "[[7, 7, 7], [7, 7, 7]]"),
+        ("var <str>a[2][2][2] = {{{\"a\"}, {\"b\", \"c\"}}};", "[[[a, a], [b, c]], [[a, a], [b, c]]]"),
+    ];
+    for (source, expected) in cases {
+        run_both(source).assert_text("a", expected);
+    }
+}
+
+#[test]
+fn arrays_without_initializers_hold_default_values() {
+    let cases = [
+        ("var <int>a[3];", "[0, 0, 0]"),
+        ("var <int>a[3][4];", "[[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]"),
+        ("var <str>a[2]\nvar <bool>b[1];", "[, ]"),
+        ("var <double>a[2][1];\na[1][0] = 2;", "[[0], [2]]"),
+    ];
+    for (source, expected) in cases {
+        run_both(source).assert_text("a", expected);
+    }
+}
+
+#[test]
+fn array_sizes_and_initial_value_counts_are_diagnosed() {
+    let cases = [
+        ("var n = 3;\nvar <int>a[n];", "Array size must be a non-negative int literal"),
+        ("var <int>a[-2];", "Array size must be a non-negative int literal"),
+        ("var <int>a[2][1.5] = 0;", "Array size must be a non-negative int literal"),
+        ("var <int>b[3] = {1, 2, 3, 4, 5};", "Too many initial values: array of length 3 got 5 values"),
+        ("var <int>b[2][2] = {{1, 2, 3}, 4};", "Too many initial values: array of length 2 got 3 values"),
+        ("var row = {1, 2, 3};\nvar <int>b[2][2] = {row};", "Too many initial values: array of length 2 got 3 values"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert!(error.diagnostic.message.starts_with(message), "{}: {}", source, error.diagnostic.message);
+        assert_eq!(error.diagnostic.line, source.lines().count(), "{}", source);
+    }
+}
+
+#[test]
+fn nested_lists_and_maps_are_assigned_in_place() {
+    let source = "
+        var nested = {{1, 2}, {3, {4, 5}}};
+        nested[1][1][0] = \"x\";
+        var table = {\"a\": {\"b\": 1}};
+        table[\"a\"][\"b\"] = 2;
+        table[\"a\"][\"c\"] = 3;
+        var result = <str>nested + \" \" + <str>table;
+    ";
+    run_both(source).assert_text("result", "[[1, 2], [3, [x, 5]]] {a: {b: 2, c: 3}}");
+}
+
+#[test]
+fn invalid_index_writes_raise_the_same_errors_as_reads() {
+    let cases = [
+        ("var <int>a[3] = 0;\na[5] = 9;", "IndexError", "Index out of bounds: index 5 is out of range for array of length 3"),
+        ("var <int>g[2][2] = 0;\ng[5][0] = 1;", "IndexError", "Index out of bounds: index 5 is out of range for array of length 2"),
+        ("var <int>g[2][2] = 0;\ng[0][-1] = 1;", "IndexError", "Index out of bounds: index -1 is out of range for array of length 2"),
+        ("var xs = {1, 2};\nxs[2] = 3;", "IndexError", "Index out of bounds: index 2 is out of range for list of length 2"),
+        ("var xs = {1, 2};\nxs[\"a\"] = 3;", "TypeError", "Type error: index of list must be an int, got 'str'"),
+        ("var n = 5;\nn[0] = 1;", "TypeError", "Type error: cannot assign to an index of value of type 'int'"),
+        ("var xs = {1, 2};\nxs[0][1] = 3;", "TypeError", "Type error: cannot assign to an index of value of type 'int'"),
+        ("var m = {\"a\": 1};\nm[\"b\"][\"c\"] = 2;", "KeyError", "Key not found: map has no entry for key \"b\""),
+    ];
+    for (source, kind, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.kind.name(), kind, "{}", source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, 2, "{}", source);
+    }
+}
+
+#[test]
+fn rows_are_replaced_by_sequences_of_the_same_length() {
+    let source = "
+        var <int>grid[2][3] = 0;
+        grid[1] = {1, \"2\", 3};
+        var short = {1, 2};
+        var message = \"\";
+        try { grid[0] = short; } catch (e) { message = e.message; }
+    ";
+    run_both(source)
+        .assert_text("grid", "[[0, 0, 0], [1, 2, 3]]")
+        .assert_global("message", str("Type error in array assignment: cannot assign value of type 'list' to array of type 'int[3]'"));
+}
+
+#[test]
+fn element_types_of_nested_indices_are_checked_before_execution() {
+    let cases = [
+        ("var <int>grid[2][2] = 0;\ngrid[0][1] = {1};", "Type error in array assignment: cannot assign value of type 'list' to array of type 'int'"),
+        ("var <int>grid[2][2] = 0;\ngrid[0] = 5;", "Type error in array assignment: cannot assign value of type 'int' to array of type 'int[2]'"),
+        ("var <str>names[2][2] = \"a\";\nvar y = names[0][1] - 1;", "Type error: cannot perform operation '-' between 'str' and 'int'"),
+        ("var <int>grid[2][2] = {{1, 2}, {3, \"four\"}};", "Type error: cannot assign to 'grid' of type 'int': Cannot convert string 'four' to int"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, source.lines().count(), "{}", source);
+    }
+}
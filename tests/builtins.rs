@@ -0,0 +1,134 @@
+//! 列表和查询类内置函数：push/pop/insert/remove 原地修改变量，len/contains/index_of/range 的结果，
+//! 出错时的用法提示，以及同名的脚本函数、原生函数和全局变量中的函数值优先于内置函数
+
+mod common;
+
+use ecl::{Engine, Interpreter, Value};
+use common::{run_both, run_err_both};
+
+fn list(items: &[i64]) -> Value {
+    Value::List(items.iter().map(|n| Value::Int(*n)).collect())
+}
+
+#[test]
+fn list_builtins_modify_the_variable_in_place() {
+    let source = "
+        var xs = [];
+        var length = push(xs, 1);
+        push(xs, 2);
+        insert(xs, 0, 5);
+        insert(xs, 3, 7);
+        var removed = remove(xs, 1);
+        var last = pop(xs);
+        func add_to_global() { push(xs, 9); }
+        add_to_global();
+    ";
+    run_both(source)
+        .assert_global("length", Value::Int(1))
+        .assert_global("removed", Value::Int(1))
+        .assert_global("last", Value::Int(7))
+        .assert_global("xs", list(&[5, 2, 9]));
+}
+
+#[test]
+fn query_builtins_work_on_strings_lists_arrays_and_maps() {
+    let source = "
+        var <int>arr[3] = {4, 5, 6};
+        var names = {\"ann\", \"bob\"};
+        var ages = {\"ann\": 30};
+        var lengths = {len(\"héllo\"), len(arr), len(names), len(ages)};
+        var found = {contains(arr, 5), contains(names, \"eve\"), contains(ages, \"ann\"), contains(\"héllo\", \"ll\")};
+        var positions = {index_of(arr, 6), index_of(names, \"eve\"), index_of(\"héllo\", \"llo\")};
+    ";
+    run_both(source)
+        .assert_global("lengths", list(&[5, 3, 2, 1]))
+        .assert_text("found", "[true, false, true, true]")
+        .assert_global("positions", list(&[2, -1, 2]));
+}
+
+#[test]
+fn range_builds_lists_that_for_loops_iterate() {
+    let source = "
+        var up = range(0, 10, 3);
+        var down = range(5, 0, -2);
+        var empty = range(3, 3, 1);
+        var total = 0;
+        for n in range(1, 5, 1) { total = total + n; }
+        for word in {\"a\", \"b\"} { total = total + len(word); }
+    ";
+    run_both(source)
+        .assert_global("up", list(&[0, 3, 6, 9]))
+        .assert_global("down", list(&[5, 3, 1]))
+        .assert_global("empty", list(&[]))
+        .assert_global("total", Value::Int(12));
+}
+
+#[test]
+fn list_literals_are_values_outside_declarations() {
+    let source = "
+        func pair(a, b) { return {a, b}; }
+        var p = pair(1, 2);
+        var q = [];
+        q = {3};
+        var count = len({7, 8, 9}) + len([]);
+    ";
+    run_both(source)
+        .assert_global("p", list(&[1, 2]))
+        .assert_global("q", list(&[3]))
+        .assert_global("count", Value::Int(3));
+}
+
+#[test]
+fn builtin_errors_point_at_the_call_with_usage_help() {
+    let cases = [
+        ("var n = len(5);", "Type error: 'len' expects a str, array, list or map, got 'int'"),
+        ("var <int>a[2] = {1, 2};\npush(a, 3);", "Type error: 'push' expects a list, got 'array'"),
+        ("var xs = [];\npop(xs);", "Cannot pop from an empty list"),
+        ("var xs = {1};\ninsert(xs, 5, 0);", "Index out of bounds: index 5 is out of range for list of length 1"),
+        ("var xs = {1};\nremove(xs, \"0\");", "Type error: 'remove' expects an int index, got 'str'"),
+        ("var r = range(0, 5, 0);", "Invalid argument: the step of 'range' cannot be 0"),
+        ("var r = range(0, 5.5, 1);", "Type error: 'range' expects int arguments, got 'double'"),
+        ("func make() { return []; }\npush(make(), 1);", "'push' modifies its first argument, which must be a list variable"),
+        ("var xs = [];\npush(xs);", "Function 'push' expects 2 arguments, got 1"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, source.lines().count(), "{}", source);
+        assert!(error.diagnostic.help.is_some(), "{}", source);
+    }
+}
+
+#[test]
+fn script_functions_and_natives_take_precedence_over_builtins() {
+    let source = "
+        func contains(s, part) { return \"script\"; }
+        func push(list, value) { return len(list) + value; }
+        var xs = {1, 2};
+        var a = contains(xs, 1);
+        var b = push(xs, 10);
+    ";
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = Interpreter::new().with_engine(engine);
+        interpreter.register_native("len", 1, |_| Ok(Value::Int(100)));
+        interpreter.run(source).unwrap();
+        assert_eq!(interpreter.get_global("a"), Some(Value::Str("script".to_string())), "{:?}", engine);
+        assert_eq!(interpreter.get_global("b"), Some(Value::Int(110)), "{:?}", engine);
+        assert_eq!(interpreter.get_global("xs"), Some(list(&[1, 2])), "{:?}", engine);
+    }
+}
+
+#[test]
+fn global_function_values_take_precedence_over_builtins() {
+    let source = "
+        var max = func (a, b) { return -1; };
+        var push = func (list, value) => value * 2;
+        var xs = {1, 2};
+        var a = max(3, 4);
+        var b = push(xs, 10);
+    ";
+    run_both(source)
+        .assert_global("a", Value::Int(-1))
+        .assert_global("b", Value::Int(20))
+        .assert_global("xs", list(&[1, 2]));
+}
@@ -0,0 +1,133 @@
+//! 函数值：具名函数的存储、传递和返回，lambda 的两种函数体，闭包共享捕获的变量，调用非函数值的错误
+
+mod common;
+
+use ecl::{Interpreter, Value};
+use common::{run_both, run_err_both, str};
+
+#[test]
+fn named_functions_can_be_stored_passed_and_returned() {
+    let source = "
+        func square(x) { return x * x; }
+        func apply(f, x) { return f(x); }
+        func pick() { return square; }
+        var sq = square;
+        var a = sq(3);
+        var b = apply(square, 4);
+        var c = pick()(5);
+        var shown = <str>sq;
+    ";
+    run_both(source)
+        .assert_global("a", Value::Int(9))
+        .assert_global("b", Value::Int(16))
+        .assert_global("c", Value::Int(25))
+        .assert_global("shown", str("<func square>"));
+}
+
+#[test]
+fn lambdas_support_block_and_arrow_bodies() {
+    let source = "
+        var add = func (a, b) => a + b;
+        var sign = func (n) {
+            if (n < 0) { return -1; }
+            return 1;
+        };
+        var a = add(2, 3);
+        var b = sign(-7);
+        var c = (func (x) => x * 10)(4);
+    ";
+    run_both(source)
+        .assert_global("a", Value::Int(5))
+        .assert_global("b", Value::Int(-1))
+        .assert_global("c", Value::Int(40));
+}
+
+#[test]
+fn closures_share_and_update_captured_variables() {
+    let source = "
+        func make_counter() {
+            var count = 0;
+            return func () {
+                count = count + 1;
+                return count;
+            };
+        }
+        var first = make_counter();
+        var second = make_counter();
+        first();
+        first();
+        var a = first();
+        var b = second();
+    ";
+    run_both(source)
+        .assert_global("a", Value::Int(3))
+        .assert_global("b", Value::Int(1));
+}
+
+#[test]
+fn lambdas_capture_each_loop_iteration_separately() {
+    let source = "
+        func collect() {
+            var fs = {0, 0, 0};
+            for i in 0..3 {
+                fs[i] = func () => i * 10;
+            }
+            return fs[0]() + fs[1]() + fs[2]();
+        }
+        var total = collect();
+    ";
+    run_both(source).assert_global("total", Value::Int(30));
+}
+
+#[test]
+fn lambdas_captured_in_some_iterations_keep_their_loop_variable() {
+    let source = "
+        var fs = {0, 0};
+        for i in 0..6 {
+            var doubled = i * 2;
+            if (i == 1) { fs[0] = func () => doubled; }
+            if (i == 4) { fs[1] = func () => doubled; }
+        }
+        var first = fs[0]();
+        var second = fs[1]();
+    ";
+    run_both(source)
+        .assert_global("first", Value::Int(2))
+        .assert_global("second", Value::Int(8));
+}
+
+#[test]
+fn local_function_values_shadow_named_functions() {
+    let source = "
+        func twice(x) { return x * 2; }
+        func run() {
+            var twice = func (x) => x * 100;
+            return twice(1);
+        }
+        var local = run();
+        var named = twice(1);
+        expr triple(r a) { return a * 3; }
+        var infix = 5 triple;
+    ";
+    run_both(source)
+        .assert_global("local", Value::Int(100))
+        .assert_global("named", Value::Int(2))
+        .assert_global("infix", Value::Int(15));
+}
+
+#[test]
+fn calling_a_non_function_reports_its_type() {
+    let error = run_err_both("var n = 1;\nvar m = n(2);");
+    assert_eq!(error.diagnostic.message, "Type error: value of type 'int' is not callable");
+    assert_eq!(error.diagnostic.line, 2);
+
+    let error = run_err_both("var f = func (a, b) => a;\nf(1);");
+    assert_eq!(error.diagnostic.message, "Function '<lambda>' expects 2 arguments, got 1");
+}
+
+#[test]
+fn host_can_call_function_values_stored_in_globals() {
+    let mut interpreter = Interpreter::new();
+    interpreter.run("func make(n) { return func (x) => x + n; }\nvar add5 = make(5);").unwrap();
+    assert_eq!(interpreter.call("add5", &[Value::Int(1)]).unwrap(), Value::Int(6));
+}
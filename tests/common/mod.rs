@@ -0,0 +1,72 @@
+//! 集成测试共用的辅助函数。每个用例都在语法树解释器和字节码虚拟机两种执行引擎下运行，
+//! 以确认两者的行为一致。每个测试文件只用到其中的一部分
+
+#![allow(dead_code)]
+
+use ecl::{Engine, Interpreter, RuntimeError, Value};
+
+/// 用指定的执行引擎运行程序，同时返回解释器以便检查全局变量
+pub fn run(source: &str, engine: Engine) -> (Interpreter, Result<(), RuntimeError>) {
+    let mut interpreter = Interpreter::new().with_engine(engine);
+    let result = interpreter.run(source);
+    (interpreter, result)
+}
+
+/// 在两种执行引擎下运行程序，程序出错时测试失败。返回值用来检查两种引擎下的全局变量
+pub fn run_both(source: &str) -> Globals<'_> {
+    let interpreters = [Engine::TreeWalker, Engine::Vm].map(|engine| {
+        let (interpreter, result) = run(source, engine);
+        result.unwrap_or_else(|error| panic!("{:?} failed: {}\n{}", engine, source, error));
+        (engine, interpreter)
+    });
+    Globals { source, interpreters }
+}
+
+/// 两种执行引擎运行同一个程序之后的全局变量，检查失败时指出执行引擎和程序
+pub struct Globals<'a> {
+    source: &'a str,
+    interpreters: [(Engine, Interpreter); 2],
+}
+
+impl Globals<'_> {
+    /// 两种引擎下全局变量 `name` 的值都是 `expected`
+    pub fn assert_global(&self, name: &str, expected: Value) -> &Self {
+        for (engine, interpreter) in &self.interpreters {
+            assert_eq!(interpreter.get_global(name), Some(expected.clone()), "{:?}: '{}' in {}", engine, name, self.source);
+        }
+        self
+    }
+
+    /// 两种引擎下都没有全局变量 `name`
+    pub fn assert_undefined(&self, name: &str) -> &Self {
+        for (engine, interpreter) in &self.interpreters {
+            assert_eq!(interpreter.get_global(name), None, "{:?}: '{}' in {}", engine, name, self.source);
+        }
+        self
+    }
+
+    /// 两种引擎下全局变量 `name` 的字符串形式都是 `expected`，用于列表、映射和结构体等较长的值
+    pub fn assert_text(&self, name: &str, expected: &str) -> &Self {
+        for (engine, interpreter) in &self.interpreters {
+            let text = interpreter.get_global(name).map(|value| value.to_string());
+            assert_eq!(text.as_deref(), Some(expected), "{:?}: '{}' in {}", engine, name, self.source);
+        }
+        self
+    }
+}
+
+/// 在两种执行引擎下运行程序，两者都应该报告同样的错误（种类和完整的诊断信息），
+/// 返回语法树解释器报告的错误以便检查
+pub fn run_err_both(source: &str) -> RuntimeError {
+    let [tree_walker, vm] = [Engine::TreeWalker, Engine::Vm].map(|engine| match run(source, engine).1 {
+        Ok(()) => panic!("{:?} succeeded, expected an error: {}", engine, source),
+        Err(error) => error,
+    });
+    assert_eq!(tree_walker.kind, vm.kind, "TreeWalker and Vm report different error kinds: {}", source);
+    assert_eq!(tree_walker.to_string(), vm.to_string(), "TreeWalker and Vm report different errors: {}", source);
+    tree_walker
+}
+
+pub fn str(s: &str) -> Value {
+    Value::Str(s.to_string())
+}
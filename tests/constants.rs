@@ -0,0 +1,113 @@
+//! const 声明：初始值的类型转换，执行前拒绝修改和重复声明常量，导入的常量在运行时受到保护
+
+mod common;
+
+use ecl::{Engine, Interpreter, Value};
+use common::{run, run_both, run_err_both};
+
+#[test]
+fn constants_hold_their_converted_value() {
+    let source = "
+        const LIMIT = 3;
+        const <double>RATE = 2;
+        const NAMES = {\"ann\", \"bob\"};
+        func scaled(n) {
+            const FACTOR = 10;
+            return n * FACTOR * RATE;
+        }
+        var total = scaled(LIMIT) + len(NAMES);
+    ";
+    run_both(source)
+        .assert_global("RATE", Value::Double(2.0))
+        .assert_global("total", Value::Double(62.0));
+}
+
+#[test]
+fn modifying_a_constant_is_rejected_before_execution() {
+    let cases = [
+        ("const MAX = 1;\nprintln(MAX);\nMAX = 2;", 3),
+        ("const XS = {1, 2};\nprintln(XS);\nXS[0] = 5;", 3),
+        ("struct Point { x, y }\nconst ORIGIN = Point(0, 0);\nprintln(1);\nORIGIN.x = 1;", 4),
+        ("const XS = [];\nprintln(XS);\npush(XS, 1);", 3),
+        ("const K = 1;\nprintln(K);\ninput(\"k? \", K);", 3),
+        ("func reset() {\n    MAX = 0;\n}\nconst MAX = 1;", 2),
+        ("func f() {\n    const LOCAL = 1;\n    if (true) { LOCAL = 2; }\n}", 3),
+    ];
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        for (source, line) in cases {
+            let (interpreter, result) = run(&format!("var started = true;\n{}", source), engine);
+            let error = result.unwrap_err();
+            assert!(error.diagnostic.message.starts_with("Cannot assign to constant"), "{:?}: {}", engine, source);
+            assert_eq!(error.diagnostic.line, line + 1, "{:?}: {}", engine, source);
+            assert!(error.diagnostic.help.is_some(), "{:?}: {}", engine, source);
+            // 错误在执行之前报告，程序中的语句都没有执行
+            assert_eq!(interpreter.get_global("started"), None, "{:?}: {}", engine, source);
+        }
+    }
+}
+
+#[test]
+fn errors_point_at_the_constant_declaration() {
+    let error = run_err_both("var x = 1;\nconst <int>MAX = 1;\nMAX = 2;");
+    assert_eq!(error.diagnostic.message, "Cannot assign to constant 'MAX'");
+    assert_eq!(error.diagnostic.note.as_deref(), Some("'MAX' is declared as a constant at <unknown>:2:15"));
+
+    let error = run_err_both("PI = 3;");
+    assert_eq!(error.diagnostic.note.as_deref(), Some("'PI' is a built-in constant"));
+}
+
+#[test]
+fn constants_cannot_be_redeclared_in_the_same_scope() {
+    let cases = [
+        ("const A = 1;\nvar A = 2;", 2),
+        ("const A = 1;\nconst A = 2;", 2),
+        ("func f() {\n    const A = 1;\n    var <int>A[2] = {1, 2};\n}", 3),
+    ];
+    for (source, line) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, "Cannot redeclare constant 'A' in the same scope", "{}", source);
+        assert_eq!(error.diagnostic.line, line, "{}", source);
+    }
+
+    // 内层作用域可以声明同名的变量
+    run_both("const A = 1;\nfunc f() { var A = 2; return A; }\nvar b = f();").assert_global("b", Value::Int(2));
+}
+
+#[test]
+fn imported_constants_are_protected_at_runtime() {
+    let dir = std::env::temp_dir().join(format!("ecl_const_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("limits.ecl"), "const <int>LIMIT = 10;\n").unwrap();
+    let main = dir.join("main.ecl").display().to_string();
+    let source = "import \"limits\";\nvar before = LIMIT;\nLIMIT = 3;";
+
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let lines = source.lines().map(String::from).collect();
+        let mut interpreter = Interpreter::new().with_engine(engine).with_source(main.clone(), lines);
+        let error = interpreter.run(source).unwrap_err();
+        assert_eq!(error.diagnostic.message, "Cannot assign to constant 'LIMIT'", "{:?}", engine);
+        assert_eq!(error.diagnostic.line, 3, "{:?}", engine);
+        let note = error.diagnostic.note.clone().unwrap_or_default();
+        assert!(note.ends_with("limits.ecl:1:17"), "{:?}: {}", engine, note);
+        assert_eq!(interpreter.get_global("before"), Some(Value::Int(10)), "{:?}", engine);
+        assert_eq!(interpreter.get_global("LIMIT"), Some(Value::Int(10)), "{:?}", engine);
+    }
+
+    // input 在读取之前拒绝导入的常量
+    let source = "import \"limits\";\ninput(\"limit? \", LIMIT);";
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let lines = source.lines().map(String::from).collect();
+        let mut interpreter = Interpreter::new().with_engine(engine).with_source(main.clone(), lines);
+        let error = interpreter.run(source).unwrap_err();
+        assert_eq!(error.diagnostic.message, "Cannot assign to constant 'LIMIT'", "{:?}", engine);
+        assert_eq!(error.diagnostic.line, 2, "{:?}", engine);
+        assert_eq!(interpreter.get_global("LIMIT"), Some(Value::Int(10)), "{:?}", engine);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn const_without_initializer_is_a_syntax_error() {
+    let error = run_err_both("const LIMIT;");
+    assert!(error.diagnostic.message.starts_with("Constant declaration requires an initializer"), "{}", error.diagnostic.message);
+}
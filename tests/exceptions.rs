@@ -0,0 +1,259 @@
+//! 异常处理：运行时错误的种类、信息和位置，错误沿调用栈传递到最近的 try，
+//! finally 在每条退出路径上执行，throw 和重新抛出的错误
+
+mod common;
+
+use ecl::{Engine, ErrorKind, Interpreter, Value};
+use common::{run, run_both, run_err_both, str};
+
+#[test]
+fn runtime_errors_are_caught_with_kind_message_and_position() {
+    let cases = [
+        ("var xs = {1, 2, 3};\nvar x = xs[5];", "IndexError", "Index out of bounds: index 5 is out of range for list of length 3"),
+        ("var xs = {1, 2, 3};\nxs[7] = 1;", "IndexError", "Index out of bounds: index 7 is out of range for list of length 3"),
+        ("var <int>grid[2][2] = 0;\ngrid[1][2] = 1;", "IndexError", "Index out of bounds: index 2 is out of range for array of length 2"),
+        ("var x = <int>\"abc\";", "ConversionError", "Type conversion failed: Cannot convert string 'abc' to int"),
+        ("var x = 1 / 0;", "ArithmeticError", "Runtime error: division by zero in '/' operation"),
+        ("var m = {\"a\": 1};\nvar x = m[\"b\"];", "KeyError", "Key not found: map has no entry for key \"b\""),
+        ("var x = sqrt(-1);", "MathError", "Math domain error: sqrt(-1) is not defined"),
+        ("var s = \"a\";\nvar x = s + 1;", "TypeError", "Type error: cannot perform operation '+' between 'str' and 'int'"),
+        // 种类在创建错误时确定，与信息的前缀无关
+        ("var x = round(10.0 ** 30);", "ConversionError", "Runtime error: 1000000000000000000000000000000 is out of range for int"),
+        ("var m = -9223372036854775807 - 1;\nvar x = abs(m);", "ArithmeticError", "Runtime error: -9223372036854775808 is out of range for int"),
+        ("func f(a) { return a; }\nvar x = f(1, 2);", "ArgumentError", "Function 'f' expects 1 arguments, got 2"),
+    ];
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        for (statements, kind, message) in cases {
+            let source = format!("var kind = \"\";\nvar error = 0;\ntry {{\n{}\n}} catch (e) {{\n    kind = e.kind;\n    error = e;\n}}", statements);
+            let (interpreter, result) = run(&source, engine);
+            result.unwrap();
+            assert_eq!(interpreter.get_global("kind"), Some(str(kind)), "{:?}: {}", engine, statements);
+            let Some(Value::Struct(error_type, fields)) = interpreter.get_global("error") else {
+                panic!("{:?}: {} did not produce an error value", engine, statements);
+            };
+            assert_eq!(error_type.name, "Error", "{:?}", engine);
+            assert_eq!(fields[0], str(message), "{:?}: {}", engine, statements);
+            // 出错的语句是 try 语句块中的最后一行
+            let line = 3 + statements.lines().count() as i64;
+            assert_eq!(fields[2], Value::Int(line), "{:?}: {}", engine, statements);
+        }
+    }
+}
+
+#[test]
+fn errors_in_called_functions_unwind_to_the_nearest_try() {
+    let source = "
+        var log = [];
+        func inner(xs) { push(log, \"inner\"); return xs[10]; }
+        func outer(xs) {
+            var total = 0;
+            for x in xs { total = total + inner(xs); }
+            return total;
+        }
+        var recovered = 0;
+        for attempt in range(0, 3, 1) {
+            try {
+                outer({1, 2});
+                push(log, \"unreachable\");
+            } catch (e) {
+                recovered = recovered + 1;
+            }
+        }
+    ";
+    run_both(source)
+        .assert_global("recovered", Value::Int(3))
+        .assert_text("log", "[inner, inner, inner]");
+}
+
+#[test]
+fn errors_leave_each_function_through_its_finally() {
+    let source = "
+        var trace = [];
+        func risky(n) { try { return 10 / n; } finally { push(trace, n); } }
+        func wrapper(n) { var doubled = n * 2; return doubled + risky(n); }
+        var ok = wrapper(5);
+        var line = 0;
+        try { wrapper(0); } catch (e) { line = e.line; }
+        var after = wrapper(2);
+    ";
+    run_both(source)
+        .assert_global("ok", Value::Int(12))
+        .assert_global("line", Value::Int(3))
+        .assert_global("after", Value::Int(9))
+        .assert_text("trace", "[5, 0, 2]");
+}
+
+#[test]
+fn finally_runs_on_every_exit_path() {
+    let source = "
+        var trace = [];
+        func divide(a, b) {
+            try {
+                return a / b;
+            } catch (e) {
+                return -1;
+            } finally {
+                push(trace, \"div\");
+            }
+        }
+        var results = {divide(6, 3), divide(1, 0)};
+        var i = 0;
+        while (i < 5) {
+            i = i + 1;
+            try {
+                if (i == 2) { continue; }
+                if (i == 4) { break; }
+                push(trace, i);
+            } finally {
+                push(trace, format(\"f{}\", i));
+            }
+        }
+        try {
+            try { throw \"inner\"; } finally { push(trace, \"cleanup\"); }
+        } catch (e) {
+            push(trace, e.message);
+        }
+    ";
+    run_both(source)
+        .assert_global("results", Value::List(vec![Value::Int(2), Value::Int(-1)]))
+        .assert_text("trace", "[div, div, 1, f1, f2, 3, f3, f4, cleanup, inner]");
+}
+
+#[test]
+fn leaving_a_finally_with_break_or_continue_discards_the_error() {
+    let source = "
+        var trace = [];
+        var i = 0;
+        while (i < 3) {
+            i = i + 1;
+            try { throw i; } finally { continue; }
+        }
+        while (true) {
+            try {
+                try { throw \"first\"; } finally { throw \"second\"; }
+            } catch (e) {
+                push(trace, e.message);
+            } finally {
+                break;
+            }
+        }
+        try { throw \"last\"; } catch (e) { push(trace, e.message); }
+    ";
+    run_both(source)
+        .assert_global("i", Value::Int(3))
+        .assert_text("trace", "[second, last]");
+}
+
+#[test]
+fn thrown_values_become_errors() {
+    let source = "
+        func check(age) {
+            if (age < 0) {
+                throw \"age must not be negative\";
+            }
+            return age;
+        }
+        var caught = {};
+        try { check(-1); } catch (e) { caught = {e.message, e.kind, e.line}; }
+        var number = \"\";
+        try { throw 42; } catch (e) { number = e.message; }
+        var rethrown = 0;
+        try {
+            try { var xs = {1}; var x = xs[3]; } catch (e) { throw e; }
+        } catch (again) {
+            rethrown = again.kind;
+        }
+    ";
+    run_both(source)
+        .assert_text("caught", "[age must not be negative, Error, 4]")
+        .assert_global("number", str("42"))
+        .assert_global("rethrown", str("IndexError"));
+}
+
+#[test]
+fn rethrown_errors_keep_the_original_diagnostic() {
+    let uncaught = "var xs = {1};\nvar x = xs[3];";
+    let rethrown = "try {\n    var xs = {1};\n    var x = xs[3];\n} catch (e) {\n    throw e;\n}";
+    let modified = "try {\n    var xs = {1};\n    var x = xs[3];\n} catch (e) {\n    e.message = \"changed\";\n    throw e;\n}";
+    let original = run_err_both(uncaught);
+    let error = run_err_both(rethrown);
+    assert_eq!(error.kind, original.kind);
+    assert_eq!(error.diagnostic.message, original.diagnostic.message);
+    assert_eq!(error.diagnostic.help, original.diagnostic.help);
+    assert_eq!((error.diagnostic.line, error.diagnostic.column), (3, original.diagnostic.column + 4));
+
+    let error = run_err_both(modified);
+    assert_eq!(error.diagnostic.message, "changed");
+    assert_eq!(error.diagnostic.line, 3);
+    assert!(error.diagnostic.help.is_none());
+}
+
+#[test]
+fn edited_error_locations_outside_the_file_fall_back_to_the_throw() {
+    let cases = [
+        "try {\n    throw 1;\n} catch (e) {\n    e.line = -5;\n    throw e;\n}",
+        "try {\n    throw 1;\n} catch (e) {\n    e.line = 99;\n    throw e;\n}",
+        "try {\n    throw 1;\n} catch (e) {\n    e.column = 80;\n    throw e;\n}",
+    ];
+    for source in cases {
+        let error = run_err_both(source);
+        assert_eq!((error.diagnostic.line, error.diagnostic.column), (5, 10), "{}", source);
+        assert_eq!(error.diagnostic.source_line, "    throw e;", "{}", source);
+    }
+
+    // 修改为当前文件中存在的位置时使用新的位置
+    let error = run_err_both("try {\n    throw 1;\n} catch (e) {\n    e.line = 1;\n    e.column = 3;\n    throw e;\n}");
+    assert_eq!((error.diagnostic.line, error.diagnostic.column), (1, 3));
+}
+
+#[test]
+fn edited_errors_from_imported_files_keep_their_location() {
+    let dir = std::env::temp_dir().join(format!("ecl_rethrow_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("checks.ecl"), "func check(n) {\n    return n / 0;\n}\n").unwrap();
+    let main = dir.join("main.ecl").display().to_string();
+    let source = "import \"checks\";\ntry {\n    check(1);\n} catch (e) {\n    e.message = \"check failed\";\n    throw e;\n}";
+
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let lines = source.lines().map(String::from).collect();
+        let mut interpreter = Interpreter::new().with_engine(engine).with_source(main.clone(), lines);
+        let error = interpreter.run(source).unwrap_err();
+        let diagnostic = &error.diagnostic;
+        assert_eq!(diagnostic.message, "check failed", "{:?}", engine);
+        assert_eq!(error.kind, ErrorKind::Arithmetic, "{:?}", engine);
+        assert!(diagnostic.file_path.ends_with("checks.ecl"), "{:?}: {}", engine, diagnostic.file_path);
+        assert_eq!((diagnostic.line, diagnostic.source_line.as_str()), (2, "    return n / 0;"), "{:?}", engine);
+    }
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn uncaught_errors_keep_their_diagnostics() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let (interpreter, result) = run("var cleaned = false;\ntry {\n    throw \"boom\";\n} finally {\n    cleaned = true;\n}", engine);
+        let error = result.unwrap_err();
+        assert_eq!(error.diagnostic.message, "boom", "{:?}", engine);
+        assert_eq!(error.diagnostic.line, 3, "{:?}", engine);
+        assert_eq!(error.kind, ErrorKind::Thrown, "{:?}", engine);
+        assert_eq!(interpreter.get_global("cleaned"), Some(Value::Bool(true)), "{:?}", engine);
+    }
+
+    // 在 catch 中再次抛出的错误保留原来的位置
+    let error = run_err_both("var xs = [];\ntry {\n    pop(xs);\n} catch (e) {\n    throw e;\n}");
+    assert_eq!(error.diagnostic.message, "Cannot pop from an empty list");
+    assert_eq!(error.diagnostic.line, 3);
+    assert_eq!(error.kind, ErrorKind::Index);
+}
+
+#[test]
+fn malformed_try_statements_are_syntax_errors() {
+    let cases = [
+        ("try { var x = 1; }", "try must be followed by catch, finally or both"),
+        ("try { var x = 1; } catch { }", "catch requires a variable name in parentheses for the caught error"),
+        ("try var x = 1;", "try, catch and finally must be followed by a block in braces"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert!(error.diagnostic.message.starts_with(message), "{}: {}", source, error.diagnostic.message);
+    }
+}
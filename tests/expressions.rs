@@ -0,0 +1,230 @@
+//! 表达式解析的回归测试：运算符优先级与结合性
+
+mod common;
+
+use ecl::{ErrorKind, Interpreter, Value};
+use common::{run_both, run_err_both};
+
+/// 执行 `var result = <expr>;` 并返回 `result` 的值
+fn eval(expr: &str) -> Value {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run(&format!("var result = {};", expr))
+        .unwrap_or_else(|error| panic!("failed to evaluate `{}`:\n{}", expr, error));
+    interpreter.get_global("result").expect("result should be defined")
+}
+
+fn assert_number(expr: &str, expected: f64) {
+    assert_eq!(eval(expr).to_f64(), expected, "`{}`", expr);
+}
+
+fn assert_bool(expr: &str, expected: bool) {
+    assert_eq!(eval(expr), Value::Bool(expected), "`{}`", expr);
+}
+
+#[test]
+fn multiplicative_binds_tighter_than_additive() {
+    assert_number("2 * 3 + 4", 10.0);
+    assert_number("4 + 2 * 3", 10.0);
+    assert_number("2 + 3 * 4 - 5", 9.0);
+    assert_number("20 / 4 + 1", 6.0);
+    assert_number("1 + 20 / 4", 6.0);
+}
+
+#[test]
+fn same_level_operators_are_left_associative() {
+    assert_number("10 - 3 - 2", 5.0);
+    assert_number("100 / 10 / 5", 2.0);
+    assert_number("10 - 4 + 3", 9.0);
+    assert_number("12 / 3 * 2", 8.0);
+}
+
+#[test]
+fn parentheses_override_precedence() {
+    assert_number("2 * (3 + 4)", 14.0);
+    assert_number("10 - (3 - 2)", 9.0);
+    assert_number("(1 + 2) * (3 + 4)", 21.0);
+}
+
+#[test]
+fn unary_minus_binds_tightest() {
+    assert_number("-2 * 3", -6.0);
+    assert_number("2 * -3", -6.0);
+    assert_number("-2 + 5", 3.0);
+    assert_number("10 - -2", 12.0);
+}
+
+#[test]
+fn comparison_binds_looser_than_arithmetic() {
+    assert_bool("2 * 3 > 5", true);
+    assert_bool("1 + 1 == 2", true);
+    assert_bool("10 - 3 - 2 == 5", true);
+    assert_bool("3 <= 1 + 2", true);
+    assert_bool("3 >= 2 * 2", false);
+    assert_bool("1 + 2 != 3", false);
+}
+
+#[test]
+fn logical_operators_bind_looser_than_comparison() {
+    assert_bool("1 < 2 && 3 < 4", true);
+    assert_bool("1 > 2 || 3 < 4", true);
+    assert_bool("1 < 2 && 3 > 4", false);
+    assert_bool("2 * 3 == 6 && 10 - 3 - 2 == 5", true);
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_bool("true || false && false", true);
+    assert_bool("false && true || true", true);
+    assert_bool("false && (true || true)", false);
+}
+
+#[test]
+fn logical_not_applies_to_its_operand_only() {
+    assert_bool("!false && false", false);
+    assert_bool("!(false && false)", true);
+    assert_bool("!true || true", true);
+}
+
+#[test]
+fn string_concatenation_chains() {
+    assert_eq!(eval("\"a\" + \"b\" + \"c\""), Value::Str("abc".to_string()));
+    assert_bool("\"a\" + \"b\" == \"ab\"", true);
+}
+
+#[test]
+fn range_bounds_accept_arithmetic() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run("var n = 3; var sum = 0; for i in n - 2..n * 2 { sum = sum + i; }")
+        .unwrap();
+    // 1 + 2 + 3 + 4 + 5
+    assert_eq!(interpreter.get_global("sum").map(|value| value.to_f64()), Some(15.0));
+}
+
+#[test]
+fn ranges_cover_the_full_int_range() {
+    let source = "var count = 0; var last = 0; for i in 2147483640..2147483650 { count = count + 1; last = i; }";
+    run_both(source)
+        .assert_global("count", Value::Int(10))
+        .assert_global("last", Value::Int(2147483649));
+}
+
+#[test]
+fn range_bounds_must_be_int() {
+    let cases = [
+        // 类型已知时由检查器报告，否则在运行时报告
+        "for i in 0..2.5 { }",
+        "func half(n) { return n / 2.0; }\nfor i in 0..half(5) { }",
+        "func half(n) { return n / 2.0; }\nfor i in half(5) { }",
+        "for c in \"abc\" { }",
+    ];
+    for source in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.kind, ErrorKind::Type, "{}", source);
+        assert!(error.diagnostic.message.contains("bounds of a for loop range must be int"), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn ranges_outside_for_loops_are_errors() {
+    for source in ["var r = 1..3;", "println(1.5..3);"] {
+        let error = run_err_both(source);
+        assert_eq!(error.kind, ErrorKind::Type, "{}", source);
+        assert!(error.diagnostic.message.contains("can only be used as the range of a for loop"), "{}: {}", source, error);
+    }
+}
+
+#[test]
+fn while_condition_with_combined_comparisons() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run("var i = 0; var j = 10; while (i < 5 && j > 7) { i = i + 1; j = j - 1; }")
+        .unwrap();
+    assert_eq!(interpreter.get_global("i").map(|value| value.to_f64()), Some(3.0));
+    assert_eq!(interpreter.get_global("j").map(|value| value.to_f64()), Some(7.0));
+}
+
+#[test]
+fn remainder_and_integer_division_share_multiplicative_level() {
+    assert_number("17 % 5", 2.0);
+    assert_number("-17 % 5", -2.0);
+    assert_number("17 ~/ 5", 3.0);
+    assert_number("-17 ~/ 5", -3.0);
+    assert_number("1 + 17 % 5 * 2", 5.0);
+    assert_number("20 ~/ 3 ~/ 2", 3.0);
+}
+
+#[test]
+fn power_is_right_associative_and_binds_tighter_than_unary_minus() {
+    assert_number("2 ** 10", 1024.0);
+    assert_number("2 ^ 10", 1024.0);
+    assert_number("2 ** 3 ** 2", 512.0);
+    assert_number("2 * 3 ** 2", 18.0);
+    assert_number("-2 ** 2", -4.0);
+    assert_number("2 ** -1", 0.5);
+}
+
+#[test]
+fn integer_operands_keep_integer_results() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run("var <int>a = 17; var <int>b = 5; var <int>e = 3; var r = a % b; var q = a ~/ b; var p = b ** e;")
+        .unwrap();
+    assert_eq!(interpreter.get_global("r"), Some(Value::Int(2)));
+    assert_eq!(interpreter.get_global("q"), Some(Value::Int(3)));
+    assert_eq!(interpreter.get_global("p"), Some(Value::Int(125)));
+}
+
+#[test]
+fn division_by_zero_is_reported() {
+    for expr in ["1 / 0", "1 % 0", "1 ~/ 0", "0 ** -1", "0.0 ** -2"] {
+        let error = run_err_both(&format!("var result = {};", expr));
+        assert!(error.diagnostic.message.contains("division by zero"), "`{}`: {}", expr, error);
+    }
+}
+
+#[test]
+fn integer_literals_stay_integers() {
+    assert_eq!(eval("2 * 3 + 4"), Value::Int(10));
+    assert_eq!(eval("10 - 3 - 2"), Value::Int(5));
+    assert_eq!(eval("-7"), Value::Int(-7));
+    assert_eq!(eval("2.5"), Value::Double(2.5));
+}
+
+#[test]
+fn arithmetic_follows_numeric_promotion() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .run("var <int>i = 3; var <float>f = 1.5; var ii = i + 1; var fi = i * f; var ff = f + f; var di = i + 0.5; var df = f + 0.5;")
+        .unwrap();
+    assert_eq!(interpreter.get_global("ii"), Some(Value::Int(4)));
+    assert_eq!(interpreter.get_global("fi"), Some(Value::Float(4.5)));
+    assert_eq!(interpreter.get_global("ff"), Some(Value::Float(3.0)));
+    assert_eq!(interpreter.get_global("di"), Some(Value::Double(3.5)));
+    assert_eq!(interpreter.get_global("df"), Some(Value::Double(2.0)));
+}
+
+#[test]
+fn division_of_integers_stays_integer() {
+    assert_eq!(eval("7 / 2"), Value::Int(3));
+    assert_eq!(eval("-7 / 2"), Value::Int(-3));
+    assert_eq!(eval("7 ~/ 2"), Value::Int(3));
+    assert_eq!(eval("7 / 2.0"), Value::Double(3.5));
+}
+
+#[test]
+fn integer_overflow_is_reported() {
+    for expr in ["9223372036854775807 + 1", "-9223372036854775807 - 2", "4294967296 * 4294967296", "2 ** 64"] {
+        let mut interpreter = Interpreter::new();
+        let error = interpreter.run(&format!("var result = {};", expr)).unwrap_err();
+        assert!(error.diagnostic.message.contains("integer overflow"), "`{}`: {}", expr, error);
+    }
+}
+
+#[test]
+fn oversized_integer_literal_is_rejected() {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.run("var result = 9223372036854775808;").unwrap_err();
+    assert!(error.diagnostic.message.contains("too large"), "{}", error);
+}
@@ -0,0 +1,172 @@
+//! 函数调用：return 立即返回到自己的调用者，带类型的参数和返回值的转换，签名不匹配的诊断
+
+mod common;
+
+use ecl::{Engine, ErrorKind, Interpreter, Value};
+use common::{run_both, run_err_both, str};
+
+#[test]
+fn return_inside_loops_leaves_the_function_immediately() {
+    let source = "
+        var steps = 0;
+        func find_first(limit) {
+            var i = 0;
+            while (i < limit) {
+                for j in 0..limit {
+                    steps = steps + 1;
+                    if (i * j == 6) {
+                        return i * 10 + j;
+                    }
+                }
+                i = i + 1;
+            }
+            return -1;
+        }
+        var found = find_first(5);
+    ";
+    run_both(source)
+        .assert_global("found", Value::Int(23))
+        .assert_global("steps", Value::Int(14));
+}
+
+#[test]
+fn return_value_does_not_leak_into_later_calls() {
+    let source = "
+        func pick(flag) {
+            if (flag) {
+                return 1;
+            }
+            var unused = 2;
+        }
+        var first = pick(true);
+        var second = pick(false);
+    ";
+    run_both(source)
+        .assert_global("first", Value::Int(1))
+        .assert_global("second", Value::Double(0.0));
+}
+
+#[test]
+fn nested_calls_return_to_their_own_caller() {
+    let source = "
+        func inner(x) {
+            for i in 0..10 {
+                if (i == x) { return i; }
+            }
+            return 0;
+        }
+        func outer(x) {
+            var total = 0;
+            for i in 0..3 {
+                total = total + inner(x + i);
+            }
+            return total;
+        }
+        var result = outer(2);
+    ";
+    run_both(source).assert_global("result", Value::Int(9));
+}
+
+#[test]
+fn top_level_return_is_a_syntax_error() {
+    let mut interpreter = Interpreter::new();
+    let error = interpreter.run("var before = 1;\nif (before > 0) {\n    return before;\n}").unwrap_err();
+    assert!(error.diagnostic.message.contains("'return'"), "{}", error);
+    assert_eq!(error.diagnostic.line, 3);
+    assert_eq!(interpreter.get_global("before"), None);
+}
+
+#[test]
+fn typed_parameters_and_returns_are_converted() {
+    let source = "
+        func area(<double>w, <double>h) -> double {
+            return w * h;
+        }
+        func label(<int>n) -> str {
+            return n;
+        }
+        func nothing() -> int {
+            var unused = 1;
+        }
+        var scale = func (<int>k) -> int => k * 2;
+        var a = area(2, 3);
+        var l = label(4) + \"!\";
+        var s = scale(\"21\");
+        var z = nothing();
+    ";
+    run_both(source)
+        .assert_global("a", Value::Double(6.0))
+        .assert_global("l", str("4!"))
+        .assert_global("s", Value::Int(42))
+        .assert_global("z", Value::Int(0));
+}
+
+#[test]
+fn runtime_signature_mismatches_point_at_the_call() {
+    let cases = [
+        (
+            "func area(<double>w, <double>h) {\n    return w * h;\n}\nvar word = \"abc\";\nvar a = area(word, 1);",
+            "Type error: parameter 'w' of 'area' expects 'double': Cannot convert string 'abc' to double",
+        ),
+        (
+            "func count(x) -> int {\n    return x;\n}\nvar word = \"abc\";\nvar n = count(word);",
+            "Type error: 'count' must return 'int': Cannot convert string 'abc' to int",
+        ),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.kind, ErrorKind::Type, "{}", source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, 5, "{}", source);
+        assert!(error.to_string().contains("is declared at"), "{}", error);
+    }
+}
+
+#[test]
+fn impossible_signatures_are_reported_before_execution() {
+    let cases = [
+        ("var started = true;\nfunc count() -> int {\n    return \"no\";\n}", 3, "Type error: 'count' must return 'int': Cannot convert string 'no' to int"),
+        ("var started = true;\nfunc g(<int>n) { return n; }\ng(\"abc\");", 3, "Type error: parameter 'n' of 'g' expects 'int': Cannot convert string 'abc' to int"),
+        ("var started = true;\nfunc g(<int>n) { return n; }\ng({1});", 3, "Type error: parameter 'n' of 'g' expects 'int': Cannot convert a value of type 'list' to int"),
+    ];
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        for (source, line, message) in cases {
+            let mut interpreter = Interpreter::new().with_engine(engine);
+            let error = interpreter.run(source).unwrap_err();
+            assert_eq!(error.diagnostic.message, message, "{:?}: {}", engine, source);
+            assert_eq!(error.diagnostic.line, line, "{:?}: {}", engine, source);
+            assert_eq!(interpreter.get_global("started"), None, "{:?}: {}", engine, source);
+        }
+    }
+}
+
+#[test]
+fn missing_return_type_is_a_syntax_error() {
+    let error = Interpreter::new().run("func f() -> {\n    return 1;\n}").unwrap_err();
+    assert!(error.diagnostic.message.starts_with("Syntax error: expected a return type after '->'"), "{}", error);
+    assert_eq!(error.diagnostic.line, 1);
+}
+
+#[test]
+fn unbounded_recursion_is_a_catchable_error() {
+    // 与命令行一样在栈足够大的线程中执行，调试构建的每一层递归占用的栈比 release 构建大得多
+    let test = std::thread::Builder::new().stack_size(256 * 1024 * 1024).spawn(|| {
+        let source = "
+            func depth(n) { if (n == 0) { return 0; } return 1 + depth(n - 1); }
+            func forever(n) { return forever(n + 1); }
+            var deep = depth(2000);
+            var message = \"\";
+            try { forever(0); } catch (e) { message = e.message; }
+            var again = depth(2000);
+        ";
+        run_both(source)
+            .assert_global("deep", Value::Int(2000))
+            .assert_global("message", str("Runtime error: calling 'forever' exceeds the maximum call depth of 3000"))
+            .assert_global("again", Value::Int(2000));
+
+        let error = run_err_both("var f = func (n) => f(n) + 1;\nf(0);");
+        assert_eq!(error.kind, ErrorKind::Runtime);
+        assert_eq!((error.diagnostic.line, error.diagnostic.column), (1, 23));
+    });
+    test.unwrap().join().unwrap();
+}
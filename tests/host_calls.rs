@@ -0,0 +1,71 @@
+//! 宿主程序通过 `Interpreter::call` 调用脚本中的函数：返回值和参数转换，未定义的函数、参数个数不符和不可调用的全局变量的诊断
+
+use ecl::{Engine, ErrorKind, Interpreter, Value};
+
+/// 执行定义了几个函数的程序之后的解释器
+fn interpreter(engine: Engine) -> Interpreter {
+    let mut interpreter = Interpreter::new().with_engine(engine);
+    interpreter
+        .run("
+            var limit = 10;
+            func clamp(n) {
+                if (n > limit) { return limit; }
+                return n;
+            }
+            func area(<double>w, <double>h) -> double {
+                return w * h;
+            }
+            func nothing() {
+                var x = 1;
+            }
+            func fail(n) {
+                return n / 0;
+            }
+        ")
+        .unwrap_or_else(|error| panic!("{:?} failed:\n{}", engine, error));
+    interpreter
+}
+
+#[test]
+fn host_calls_return_the_function_result() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = interpreter(engine);
+        assert_eq!(interpreter.call("clamp", &[Value::Int(3)]).unwrap(), Value::Int(3), "{:?}", engine);
+        assert_eq!(interpreter.call("clamp", &[Value::Int(30)]).unwrap(), Value::Int(10), "{:?}", engine);
+        // 参数和返回值转换为声明的类型
+        assert_eq!(interpreter.call("area", &[Value::Int(2), Value::Int(3)]).unwrap(), Value::Double(6.0), "{:?}", engine);
+        // 没有执行 return 的函数返回 0
+        assert_eq!(interpreter.call("nothing", &[]).unwrap(), Value::Double(0.0), "{:?}", engine);
+    }
+}
+
+#[test]
+fn host_call_errors_have_no_source_location() {
+    let cases = [
+        ("missing", vec![], ErrorKind::Name, "Function 'missing' is not defined"),
+        ("clamp", vec![Value::Int(1), Value::Int(2)], ErrorKind::Argument, "Function 'clamp' expects 1 arguments, got 2"),
+        ("limit", vec![], ErrorKind::Type, "Type error: value of type 'int' is not callable"),
+    ];
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = interpreter(engine);
+        for (name, args, kind, message) in &cases {
+            let error = interpreter.call(name, args).unwrap_err();
+            assert_eq!(error.kind, *kind, "{:?}: {}", engine, name);
+            assert_eq!(error.diagnostic.message, *message, "{:?}: {}", engine, name);
+            assert_eq!(error.diagnostic.line, 0, "{:?}: {}", engine, name);
+            // 诊断信息中不显示不存在的 `:0:0` 位置
+            let rendered = error.to_string();
+            assert!(!rendered.contains("-->") && !rendered.contains(":0:0"), "{:?}: {}", engine, rendered);
+        }
+    }
+}
+
+#[test]
+fn errors_inside_called_functions_point_at_the_script() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let error = interpreter(engine).call("fail", &[Value::Int(1)]).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Arithmetic, "{:?}", engine);
+        assert_eq!(error.diagnostic.line, 14, "{:?}", engine);
+        assert!(error.to_string().contains("return n / 0;"), "{:?}: {}", engine, error);
+    }
+}
@@ -0,0 +1,41 @@
+//! lib/ 中随发行版提供的库文件：每个文件都能被导入，导入后其中的函数可以调用
+
+mod common;
+
+use std::fs;
+use ecl::Value;
+use common::run_both;
+
+#[test]
+fn every_library_file_can_be_imported() {
+    let mut files: Vec<String> = fs::read_dir("lib")
+        .expect("lib directory should exist")
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".ecl"))
+        .collect();
+    files.sort();
+    assert!(!files.is_empty(), "no library files found");
+
+    for file in &files {
+        run_both(&format!("import \"{}\";", file));
+    }
+}
+
+#[test]
+fn library_functions_are_callable_after_import() {
+    let source = "
+        import \"math\";
+        import \"string\";
+        var f = factorial(5);
+        var prime = is_prime(97);
+        var d = distance(0, 0, 3, 4);
+        var sum = add(2, 3);
+        var n = strlen(\"hello\");
+    ";
+    run_both(source)
+        .assert_global("f", Value::Int(120))
+        .assert_global("prime", Value::Bool(true))
+        .assert_global("d", Value::Double(5.0))
+        .assert_global("sum", Value::Int(5))
+        .assert_global("n", Value::Int(5));
+}
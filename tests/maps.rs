@@ -0,0 +1,69 @@
+//! 映射：字面量的插入顺序与重复键，按键读写和遍历，缺少的键和不支持的键类型的诊断
+
+mod common;
+
+use ecl::{Engine, Value};
+use common::{run, run_both, run_err_both, str};
+
+#[test]
+fn literal_keeps_insertion_order_and_last_duplicate() {
+    let expected = Value::Map(vec![(str("b"), Value::Int(3)), (str("a"), Value::Int(2))]);
+    run_both("var m = {\"b\": 1, \"a\": 2, \"b\": 3,};").assert_global("m", expected);
+}
+
+#[test]
+fn empty_braces_stay_a_list_and_colon_braces_make_a_map() {
+    run_both("var l = {};\nvar m = {:};")
+        .assert_global("l", Value::List(Vec::new()))
+        .assert_global("m", Value::Map(Vec::new()));
+}
+
+#[test]
+fn index_assignment_updates_or_appends() {
+    let source = "
+        var m = {\"x\": 1};
+        m[\"x\"] = m[\"x\"] + 10;
+        m[2] = \"two\";
+        var text = <str>m;
+    ";
+    run_both(source).assert_global("text", str("{x: 11, 2: two}"));
+}
+
+#[test]
+fn for_loop_visits_keys_present_when_it_starts() {
+    let source = "
+        var m = {\"a\": 1, \"b\": 2, \"c\": 3};
+        var sum = 0;
+        var visited = 0;
+        for k in m {
+            sum = sum + m[k];
+            visited = visited + 1;
+            m[visited] = 0;   // 循环中添加的键不参与本次遍历
+        }
+    ";
+    run_both(source)
+        .assert_global("sum", Value::Int(6))
+        .assert_global("visited", Value::Int(3));
+}
+
+#[test]
+fn missing_key_is_reported() {
+    let error = run_err_both("var m = {\"a\": 1};\nvar v = m[\"b\"];");
+    assert!(error.diagnostic.message.contains("\"b\""), "{}", error);
+    assert_eq!(error.diagnostic.line, 2);
+}
+
+#[test]
+fn unsupported_key_types_are_rejected() {
+    let error = run_err_both("var m = {1.5: \"x\"};");
+    assert!(error.diagnostic.message.contains("'double'"), "{}", error);
+    let error = run_err_both("var m = {:};\nm[{:}] = 1;");
+    assert!(error.diagnostic.message.contains("'map'"), "{}", error);
+}
+
+#[test]
+fn entry_without_colon_is_a_syntax_error() {
+    let (_, result) = run("var m = {\"a\": 1, \"b\"};", Engine::TreeWalker);
+    let error = result.unwrap_err();
+    assert!(error.diagnostic.message.contains("':'"), "{}", error);
+}
@@ -0,0 +1,124 @@
+//! 枚举与 match：成员负载的绑定，字面量、范围和通配符模式，分支中的控制流，执行前的穷尽性检查
+
+mod common;
+
+use ecl::{Engine, Interpreter, Value};
+use common::{run, run_both, str};
+
+const SHAPE: &str = "enum Shape { Circle(<double>r), Rect(w, h), Empty }\n";
+
+fn error_message(source: &str) -> String {
+    let (_, result) = run(source, Engine::TreeWalker);
+    result.unwrap_err().diagnostic.message
+}
+
+#[test]
+fn variant_patterns_bind_payload_values() {
+    let source = "
+        func area(s) {
+            return match (s) {
+                Shape::Circle(r) => r * r,
+                Shape::Rect(w, h) => w * h,
+                Shape::Empty => 0,
+            };
+        }
+        var circle = area(Shape::Circle(3));
+        var rect = area(Shape::Rect(2, 5));
+        var empty = area(Shape::Empty);
+        var shown = <str>Shape::Rect(2, 5);
+    ";
+    run_both(&format!("{}{}", SHAPE, source))
+        .assert_global("circle", Value::Double(9.0))
+        .assert_global("rect", Value::Int(10))
+        .assert_global("empty", Value::Int(0))
+        .assert_global("shown", str("Shape::Rect(2, 5)"));
+}
+
+#[test]
+fn literal_range_and_wildcard_patterns_pick_the_first_matching_arm() {
+    let source = "
+        func classify(n) {
+            return match (n) {
+                0 => \"zero\",
+                1..10 => \"small\",
+                -10..0 => \"negative\",
+                5 => \"unreachable\",
+                _ => \"large\",
+            };
+        }
+        var a = classify(0);
+        var b = classify(5);
+        var c = classify(-1);
+        var d = classify(10);
+    ";
+    run_both(source)
+        .assert_text("a", "zero")
+        .assert_text("b", "small")
+        .assert_text("c", "negative")
+        .assert_text("d", "large");
+}
+
+#[test]
+fn match_statement_passes_control_flow_to_the_enclosing_loop() {
+    let source = "
+        var total = 0;
+        for i in 0..10 {
+            match (i) {
+                2 => continue,
+                5 => { break; }
+                _ => total = total + i,
+            }
+        }
+    ";
+    // 0 + 1 + 3 + 4
+    run_both(source).assert_global("total", Value::Int(8));
+}
+
+#[test]
+fn pattern_bindings_are_local_to_their_arm() {
+    let (_, result) = run(&format!("{}match (Shape::Circle(1)) {{\n    Shape::Circle(r) => println(r),\n    _ => println(r),\n}}", SHAPE), Engine::TreeWalker);
+    let error = result.unwrap_err();
+    assert!(error.diagnostic.message.contains("\"r\""), "{}", error);
+    assert_eq!(error.diagnostic.line, 4);
+}
+
+#[test]
+fn missing_variants_are_reported_before_execution() {
+    let source = format!("{}println(\"before\");\nmatch (Shape::Empty) {{\n    Shape::Circle(r) => println(r),\n}}", SHAPE);
+    let (_, result) = run(&source, Engine::Vm);
+    let error = result.unwrap_err();
+    assert_eq!(error.diagnostic.message, "Non-exhaustive match: missing Shape::Rect, Shape::Empty");
+    assert_eq!(error.diagnostic.line, 3);
+    assert!(error.diagnostic.help.is_some());
+}
+
+#[test]
+fn literal_matches_need_a_wildcard_unless_they_cover_both_booleans() {
+    assert!(error_message("var s = match (3) { 1 => \"one\", 2..5 => \"few\" };").starts_with("Non-exhaustive match"));
+    assert_eq!(error_message("match (true) { true => println(1) }"), "Non-exhaustive match: missing false");
+    run("var s = match (1 > 2) { true => \"yes\", false => \"no\" };", Engine::TreeWalker).1.unwrap();
+}
+
+#[test]
+fn enum_usage_is_checked_against_the_declaration() {
+    assert_eq!(error_message(&format!("{}var s = Shape::Square(1);", SHAPE)), "Enum 'Shape' has no variant 'Square'");
+    assert_eq!(error_message(&format!("{}var s = Shape::Rect(1);", SHAPE)), "Variant 'Shape::Rect' expects 2 values, got 1");
+    assert_eq!(error_message("var c = Color::Red;"), "Enum 'Color' is not defined");
+    assert_eq!(
+        error_message(&format!("{}enum Other {{ A }}\nmatch (Shape::Empty) {{ Shape::Empty => println(1), Other::A => println(2), _ => println(3) }}", SHAPE)),
+        "Type error: match arms mix variants of enum 'Shape' and enum 'Other'"
+    );
+    assert!(error_message(&format!("{}var s = Shape::Circle(\"big\");", SHAPE)).starts_with("Type error"));
+}
+
+#[test]
+fn enums_declared_in_earlier_runs_stay_available() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = Interpreter::new().with_engine(engine);
+        interpreter.run(SHAPE).unwrap();
+        interpreter.run("var same = Shape::Rect(1, 2) == Shape::Rect(1, 2.0);").unwrap();
+        assert_eq!(interpreter.get_global("same"), Some(Value::Bool(true)), "{:?}", engine);
+        let error = interpreter.run("match (Shape::Empty) { Shape::Empty => println(1) }").unwrap_err();
+        assert!(error.diagnostic.message.starts_with("Non-exhaustive match"), "{:?}", engine);
+    }
+}
@@ -0,0 +1,92 @@
+//! 数学函数和常量：结果与标准库一致，整数参数保持整数，定义域和参数类型错误，常量不能赋值
+
+mod common;
+
+use ecl::{Engine, ErrorKind, Value};
+use common::{run, run_both, run_err_both};
+
+#[test]
+fn math_functions_match_the_standard_library() {
+    let source = "
+        var root = sqrt(2);
+        var power = pow(2, 0.5);
+        var natural = ln(E);
+        var thousand = log(1000, 10);
+        var angle = atan2(1, 1);
+        var side = hypot(3, 4);
+        var wave = sin(PI / 6);
+    ";
+    run_both(source)
+        .assert_global("root", Value::Double(2f64.sqrt()))
+        .assert_global("power", Value::Double(2f64.sqrt()))
+        .assert_global("natural", Value::Double(1.0))
+        .assert_global("thousand", Value::Double(3.0))
+        .assert_global("angle", Value::Double(std::f64::consts::FRAC_PI_4))
+        .assert_global("side", Value::Double(5.0))
+        .assert_global("wave", Value::Double((std::f64::consts::PI / 6.0).sin()));
+}
+
+#[test]
+fn integer_arguments_keep_integer_results() {
+    let source = "
+        var results = {abs(-7), min(3, 9), max(3, 9), pow(3, 4), floor(5), round(2.5), ceil(-2.5), floor(-2.5)};
+        var mixed = {abs(-7.5), max(3, 9.5), pow(2, -1)};
+    ";
+    let ints = [7, 3, 9, 81, 5, 3, -2, -3].iter().map(|n| Value::Int(*n)).collect();
+    let doubles = [7.5, 9.5, 0.5].iter().map(|n| Value::Double(*n)).collect();
+    run_both(source)
+        .assert_global("results", Value::List(ints))
+        .assert_global("mixed", Value::List(doubles));
+}
+
+#[test]
+fn domain_and_type_errors_are_diagnostics() {
+    let cases = [
+        ("var x = sqrt(-1);", "Math domain error: sqrt(-1) is not defined"),
+        ("var x = ln(0);", "Math domain error: ln(0) is not defined"),
+        ("var x = log(8, 1);", "Math domain error: log(8, 1) is not defined"),
+        ("var x = asin(2);", "Math domain error: asin(2) is not defined"),
+        ("var x = pow(-8, 0.5);", "Math domain error: pow(-8, 0.5) is not defined"),
+        ("var x = pow(10, 30);", "Runtime error: integer overflow in 10 ** 30"),
+        ("var x = round(-9223372036854775807.0 * 2);", "Runtime error: -18446744073709552000 is out of range for int"),
+        ("var x = cos(\"0\");", "Type error: 'cos' expects a number, got 'str'"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert!(error.diagnostic.help.is_some(), "{}", source);
+    }
+}
+
+#[test]
+fn pow_of_zero_to_a_negative_exponent_matches_the_operator() {
+    let operator = run_err_both("var x = 0 ** -1;");
+    for source in ["var x = pow(0, -1);", "var x = pow(0.0, -2);", "var x = pow(0, -0.5);"] {
+        let error = run_err_both(source);
+        assert_eq!(error.kind, operator.kind, "{}", source);
+        assert_eq!(error.diagnostic.message, operator.diagnostic.message, "{}", source);
+    }
+    assert_eq!(operator.kind, ErrorKind::Arithmetic);
+    assert_eq!(operator.diagnostic.message, "Runtime error: division by zero in '**' operation");
+}
+
+#[test]
+fn math_constants_cannot_be_assigned() {
+    run_both("var ratio = TAU / PI;").assert_global("ratio", Value::Double(2.0));
+
+    let error = run_err_both("println(1);\nfunc reset() { PI = 3; }");
+    assert_eq!(error.diagnostic.message, "Cannot assign to constant 'PI'");
+    assert_eq!(error.diagnostic.line, 2);
+
+    // 使用了 import 的程序同样不能给常量赋值
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let (interpreter, result) = run("import \"std\";\nE = 1;", engine);
+        assert_eq!(result.unwrap_err().diagnostic.message, "Cannot assign to constant 'E'", "{:?}", engine);
+        assert_eq!(interpreter.get_global("E"), Some(Value::Double(std::f64::consts::E)), "{:?}", engine);
+    }
+}
+
+#[test]
+fn programs_can_declare_their_own_variable_with_a_constant_name() {
+    run_both("var PI = 3.14;\nPI = 3;").assert_global("PI", Value::Int(3));
+}
@@ -0,0 +1,70 @@
+//! 宿主程序注册的原生函数：普通调用、表达式函数的中缀调用、参数个数检查和原生函数返回的错误
+
+use ecl::{Engine, ErrorKind, Interpreter, Value};
+
+/// 注册了 `discount`（一个参数）和 `add`（两个参数）的解释器
+fn interpreter(engine: Engine) -> Interpreter {
+    let mut interpreter = Interpreter::new().with_engine(engine);
+    interpreter.register_native("discount", 1, |args| match &args[0] {
+        Value::Int(price) => Ok(Value::Double(*price as f64 * 0.9)),
+        Value::Double(price) => Ok(Value::Double(price * 0.9)),
+        other => Err(format!("expected a number, got {}", other.get_type_name())),
+    });
+    interpreter.register_native("add", 2, |args| match (&args[0], &args[1]) {
+        (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+        _ => Err("expected two ints".to_string()),
+    });
+    interpreter
+}
+
+#[test]
+fn natives_are_called_with_normal_call_syntax() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = interpreter(engine);
+        interpreter.run("var a = discount(100);\nvar b = add(add(1, 2), 4);").unwrap();
+        assert_eq!(interpreter.get_global("a"), Some(Value::Double(90.0)), "{:?}", engine);
+        assert_eq!(interpreter.get_global("b"), Some(Value::Int(7)), "{:?}", engine);
+        assert_eq!(interpreter.call("add", &[Value::Int(2), Value::Int(3)]).unwrap(), Value::Int(5), "{:?}", engine);
+    }
+}
+
+#[test]
+fn natives_are_called_with_expr_infix_syntax() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = interpreter(engine);
+        interpreter.run("var a = 100 discount;\nvar b = (200)discount;\nvar c = 2 add 3;").unwrap();
+        assert_eq!(interpreter.get_global("a"), Some(Value::Double(90.0)), "{:?}", engine);
+        assert_eq!(interpreter.get_global("b"), Some(Value::Double(180.0)), "{:?}", engine);
+        assert_eq!(interpreter.get_global("c"), Some(Value::Int(5)), "{:?}", engine);
+    }
+}
+
+#[test]
+fn native_arity_mismatches_point_at_the_call() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let error = interpreter(engine).run("var x = 1;\nvar y = add(1, 2, 3);").unwrap_err();
+        assert_eq!(error.diagnostic.message, "Function 'add' expects 2 arguments, got 3", "{:?}", engine);
+        assert_eq!(error.diagnostic.line, 2, "{:?}", engine);
+        assert_eq!(error.kind, ErrorKind::Argument, "{:?}", engine);
+    }
+}
+
+#[test]
+fn native_errors_surface_as_diagnostics() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let error = interpreter(engine).run("var x = 1;\nvar y = discount(\"free\");").unwrap_err();
+        let diagnostic = &error.diagnostic;
+        assert_eq!(diagnostic.message, "Error in native function 'discount': expected a number, got str", "{:?}", engine);
+        assert_eq!((diagnostic.line, diagnostic.source_line.as_str()), (2, "var y = discount(\"free\");"), "{:?}", engine);
+        assert!(diagnostic.help.is_some(), "{:?}", engine);
+
+        // 原生函数返回的错误同样可以被 catch 捕获
+        let mut interpreter = interpreter(engine);
+        interpreter.run("var message = \"\";\ntry { add(1, \"2\"); } catch (e) { message = e.message; }").unwrap();
+        assert_eq!(
+            interpreter.get_global("message"),
+            Some(Value::Str("Error in native function 'add': expected two ints".to_string())),
+            "{:?}", engine
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! 名称解析的回归测试：变量绑定与执行前的未定义变量检查
+
+mod common;
+
+use ecl::{Interpreter, RuntimeError, Value};
+use common::run_both;
+
+fn run(source: &str) -> (Interpreter, Result<(), RuntimeError>) {
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.run(source);
+    (interpreter, result)
+}
+
+#[test]
+fn undefined_name_is_reported_before_execution() {
+    let (interpreter, result) = run("var before = 1;\nif (false) { println(missing); }");
+    let error = result.unwrap_err();
+    assert!(error.diagnostic.message.contains("\"missing\""), "{}", error);
+    assert_eq!(error.diagnostic.line, 2);
+    // 第一条语句没有执行
+    assert_eq!(interpreter.get_global("before"), None);
+}
+
+#[test]
+fn undefined_name_note_lists_static_scopes() {
+    let (_, result) = run("func area(r) {\n    if (r > 0) { return r * missing; }\n    return 0;\n}");
+    let error = result.unwrap_err();
+    let note = error.diagnostic.note.as_deref().unwrap_or_default();
+    assert_eq!(note, "searched block -> function 'area' -> global scope");
+}
+
+#[test]
+fn block_variables_are_not_visible_after_the_block() {
+    let (_, result) = run("if (true) { var inner = 1; }\nprintln(inner);");
+    assert_eq!(result.unwrap_err().diagnostic.line, 2);
+}
+
+#[test]
+fn functions_may_use_globals_declared_after_them() {
+    let (interpreter, result) = run("func total() { return base + 1; }\nvar base = 41;\nvar answer = total();");
+    result.unwrap();
+    assert_eq!(interpreter.get_global("answer"), Some(Value::Int(42)));
+}
+
+#[test]
+fn shadowing_and_assignment_bind_to_the_right_scope() {
+    let source = "
+        var x = 1;
+        var outer = 0;
+        for i in 0..3 {
+            var x = x + 10;   // 初始值中的 x 是全局变量
+            outer = outer + x;
+            fresh = i;        // 在循环体中声明
+        }
+        var after = x;
+    ";
+    run_both(source)
+        .assert_global("outer", Value::Int(33))
+        .assert_global("after", Value::Int(1))
+        .assert_undefined("fresh");
+}
+
+#[test]
+fn host_globals_are_known_to_the_resolver() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_global("price", Value::Double(2.5));
+    interpreter.run("var total = price * 4;").unwrap();
+    assert_eq!(interpreter.get_global("total"), Some(Value::Double(10.0)));
+}
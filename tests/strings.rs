@@ -0,0 +1,82 @@
+//! 字符串函数和字符串下标：按字符计数，split/join/replace/trim 与 format，下标和参数错误的位置
+
+mod common;
+
+use ecl::Value;
+use common::{run_both, run_err_both, str};
+
+#[test]
+fn string_functions_count_characters_not_bytes() {
+    let source = "
+        var s = \"héllo wörld\";
+        var first = s[1];
+        var part = substring(s, 6, 11);
+        var at = find(s, \"wö\");
+        var letters = len(chars(s));
+        var shout = upper(s);
+    ";
+    run_both(source)
+        .assert_global("first", str("é"))
+        .assert_global("part", str("wörld"))
+        .assert_global("at", Value::Int(6))
+        .assert_global("letters", Value::Int(11))
+        .assert_global("shout", str("HÉLLO WÖRLD"));
+}
+
+#[test]
+fn split_join_replace_and_trim_round_trip() {
+    let source = "
+        var words = split(\" a, b ,c \", \",\");
+        var cleaned = [];
+        for word in words { push(cleaned, trim(word)); }
+        var joined = join(cleaned, \"-\");
+        var replaced = replace(joined, \"-\", \"\");
+        var checks = {starts_with(joined, \"a-\"), ends_with(joined, \"-c\"), starts_with(joined, \"b\")};
+        var repeated = repeat(\"ab\", 3);
+        var none = repeat(\"ab\", 0);
+    ";
+    run_both(source)
+        .assert_text("words", "[ a,  b , c ]")
+        .assert_global("joined", str("a-b-c"))
+        .assert_global("replaced", str("abc"))
+        .assert_text("checks", "[true, true, false]")
+        .assert_global("repeated", str("ababab"))
+        .assert_global("none", str(""));
+}
+
+#[test]
+fn format_fills_placeholders_in_order() {
+    let source = "
+        var a = format(\"{} + {} = {}\", 1, 2.5, 3.5);
+        var b = format(\"{{literal}} {}\", true);
+        var c = format(\"no placeholders\");
+    ";
+    run_both(source)
+        .assert_global("a", str("1 + 2.5 = 3.5"))
+        .assert_global("b", str("{literal} true"))
+        .assert_global("c", str("no placeholders"));
+}
+
+#[test]
+fn string_errors_report_the_problem_at_the_call() {
+    let cases = [
+        ("var s = \"abc\";\nvar c = s[3];", "Index out of bounds: index 3 is out of range for str of length 3"),
+        ("var s = \"abc\";\nvar c = s[-1];", "Index out of bounds: index -1 is out of range for str of length 3"),
+        ("var s = \"abc\";\nvar c = s[1.5];", "Type error: index of str must be an int, got 'double'"),
+        ("var xs = {1, 2};\nvar x = xs[-1];", "Index out of bounds: index -1 is out of range for list of length 2"),
+        ("var n = 5;\nvar x = n[0];", "Type error: value of type 'int' cannot be indexed"),
+        ("var s = substring(\"héllo\", 2, 9);", "Index out of bounds: range 2..9 is out of range for str of length 5"),
+        ("var s = upper(42);", "Type error: 'upper' expects a str, got 'int'"),
+        ("var s = split(\"a,b\", \"\");", "Invalid argument: the str to search for in 'split' cannot be empty"),
+        ("var s = repeat(\"a\", -1);", "Invalid argument: 'repeat' expects a non-negative int, got -1"),
+        ("var s = join(\"abc\", \",\");", "Type error: 'join' expects a list or array, got 'str'"),
+        ("var s = format(\"{} {}\", 1);", "Invalid format string: 2 placeholders but 1 values"),
+        ("var s = format(\"{oops}\");", "Invalid format string: unmatched brace, write {{ or }} for a literal brace"),
+        ("var s = format();", "Function 'format' expects at least 1 arguments, got 0"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, source.lines().count(), "{}", source);
+    }
+}
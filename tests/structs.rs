@@ -0,0 +1,101 @@
+//! 结构体：构造时转换字段类型，按值修改嵌套结构体的字段，字段和下标混合的赋值目标，字段个数、未知字段和字段类型的诊断
+
+mod common;
+
+use ecl::{Engine, Value};
+use common::{run, run_both, run_err_both};
+
+const POINT: &str = "struct Point { <double>x, <double>y }\n";
+
+#[test]
+fn constructor_converts_arguments_to_field_types() {
+    run_both(&format!("{}var p = Point(1, 2.5);\nvar x = p.x;", POINT))
+        .assert_global("x", Value::Double(1.0))
+        .assert_text("p", "Point { x: 1, y: 2.5 }");
+}
+
+#[test]
+fn field_writes_update_nested_structs_by_value() {
+    let source = "
+        struct Line { start, end }
+        var a = Point(0, 0);
+        var line = Line(a, Point(3, 4));
+        line.end.y = 8;
+        line.start.x = 1;
+        var copy = line;
+        copy.end.x = 100;
+    ";
+    run_both(&format!("{}{}", POINT, source))
+        .assert_text("line", "Line { start: Point { x: 1, y: 0 }, end: Point { x: 3, y: 8 } }")
+        .assert_text("a", "Point { x: 0, y: 0 }");
+}
+
+#[test]
+fn field_type_mismatch_is_reported() {
+    let error = run_err_both(&format!("{}var p = Point(1, 2);\np.y = \"up\";", POINT));
+    assert!(error.diagnostic.message.contains("field 'y' of struct 'Point' expects 'double', got 'str'"), "{}", error);
+    assert_eq!(error.diagnostic.line, 3);
+}
+
+#[test]
+fn wrong_field_count_and_unknown_fields_are_reported() {
+    let error = run_err_both(&format!("{}var p = Point(1);", POINT));
+    assert!(error.diagnostic.message.contains("has 2 fields, got 1"), "{}", error);
+
+    let error = run_err_both(&format!("{}var z = Point(1, 2).z;", POINT));
+    assert_eq!(error.diagnostic.help.as_deref(), Some("help: the fields of 'Point' are: x, y"));
+}
+
+#[test]
+fn field_access_on_non_struct_is_a_type_error() {
+    let error = run_err_both("var n = 3;\nvar x = n.x;");
+    assert!(error.diagnostic.message.contains("value of type 'int'"), "{}", error);
+}
+
+#[test]
+fn duplicate_fields_are_a_syntax_error() {
+    let (_, result) = run("struct P { a, <int>a }", Engine::TreeWalker);
+    assert!(result.unwrap_err().diagnostic.message.contains("duplicate field 'a'"));
+}
+
+#[test]
+fn assignment_targets_mix_fields_and_indices() {
+    let source = "
+        struct Bag { items, tags }
+        var bag = Bag({1, 2, 3}, {\"a\": Point(0, 0)});
+        bag.items[0] = 9;
+        bag.tags[\"a\"].y = 4;
+        var points = {Point(1, 1), Point(2, 2)};
+        points[1].x = 5;
+        var grid = {{Point(0, 0)}};
+        grid[0][0].y = 7;
+    ";
+    run_both(&format!("{}{}", POINT, source))
+        .assert_text("bag", "Bag { items: [9, 2, 3], tags: {a: Point { x: 0, y: 4 }} }")
+        .assert_text("points", "[Point { x: 1, y: 1 }, Point { x: 5, y: 2 }]")
+        .assert_text("grid", "[[Point { x: 0, y: 7 }]]");
+}
+
+#[test]
+fn mixed_assignment_targets_report_errors_at_the_assignment() {
+    let cases = [
+        ("var ps = {Point(1, 1)};\nps[3].x = 1;", "Index out of bounds"),
+        ("var ps = {Point(1, 1)};\nps[0].z = 1;", "has no field 'z'"),
+        ("var ps = {Point(1, 1)};\nps[0].x = \"left\";", "field 'x' of struct 'Point' expects 'double', got 'str'"),
+        ("struct Bag { items }\nvar b = Bag(1);\nb.items[0] = 1;", "cannot assign to an index of value of type 'int'"),
+    ];
+    for (source, message) in cases {
+        let error = run_err_both(&format!("{}{}", POINT, source));
+        assert!(error.diagnostic.message.contains(message), "{}\n{}", source, error);
+        assert_eq!(error.diagnostic.line, source.lines().count() + 1, "{}", source);
+    }
+}
+
+#[test]
+fn assigning_through_a_call_is_a_syntax_error() {
+    for source in ["func origin() { return Point(0, 0); }\norigin().x = 1;", "var fs = {};\nfs[0](1).x = 2;"] {
+        let error = run_err_both(&format!("{}{}", POINT, source));
+        assert!(error.diagnostic.message.starts_with("Syntax error: cannot assign to the result of a function call"), "{}\n{}", source, error);
+        assert_eq!(error.diagnostic.line, 3, "{}", source);
+    }
+}
@@ -0,0 +1,113 @@
+//! 执行前的类型检查：一次报告所有类型错误，由声明推断表达式的类型，无类型的值留到运行时检查
+
+mod common;
+
+use ecl::{Engine, ErrorKind, Interpreter, RuntimeError, Value};
+use common::{run, run_both, run_err_both, str};
+
+/// 所有错误的（行号，信息），按报告的顺序排列
+fn errors(error: &RuntimeError) -> Vec<(usize, String)> {
+    std::iter::once(error.diagnostic.as_ref())
+        .chain(&error.additional)
+        .map(|diagnostic| (diagnostic.line, diagnostic.message.clone()))
+        .collect()
+}
+
+#[test]
+fn all_type_errors_are_reported_before_execution() {
+    let source = "var started = true;
+var <int>count = \"many\";
+var x = \"a\" - 1;
+func f() {
+    var <double>ratio = {\"r\": 1};
+}
+var b = true + 1;";
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let (interpreter, result) = run(source, engine);
+        let error = result.unwrap_err();
+        assert_eq!(errors(&error), vec![
+            (2, "Type error: cannot assign to 'count' of type 'int': Cannot convert string 'many' to int".to_string()),
+            (3, "Type error: cannot perform operation '-' between 'str' and 'int'".to_string()),
+            (5, "Type error: cannot assign to 'ratio' of type 'double': Cannot convert a value of type 'map' to double".to_string()),
+            (7, "Type error: cannot perform operation '+' between 'bool' and 'int'".to_string()),
+        ], "{:?}", engine);
+        assert_eq!(error.kind, ErrorKind::Type, "{:?}", engine);
+        assert!(error.to_string().ends_with("4 errors found before execution"), "{:?}: {}", engine, error);
+        // 程序中的语句都没有执行
+        assert_eq!(interpreter.get_global("started"), None, "{:?}", engine);
+    }
+}
+
+#[test]
+fn expression_types_are_inferred_from_declarations() {
+    let cases = [
+        ("var <int>a = 1;\nvar <str>s = \"x\";\nvar y = a + s;", "'+' between 'int' and 'str'"),
+        ("var <int>a = 1;\nvar <str>s = \"x\";\nvar y = (a * 2.0) - s;", "'-' between 'double' and 'str'"),
+        ("const NAME = \"ecl\";\nvar y = NAME * 2;", "'*' between 'str' and 'int'"),
+        ("var <str>names[2] = {\"a\", \"b\"};\nvar y = names[0] < 1;", "'<' between 'str' and 'int'"),
+        ("var <bool>ready = true;\nvar y = (ready && 1 < 2) == \"yes\";", "'==' between 'bool' and 'str'"),
+        ("var y = {1, 2} + 1;", "'+' between 'list' and 'int'"),
+    ];
+    for (source, operation) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, format!("Type error: cannot perform operation {}", operation), "{}", source);
+        assert_eq!(error.diagnostic.line, source.lines().count(), "{}", source);
+    }
+}
+
+#[test]
+fn untyped_values_are_checked_at_runtime() {
+    let source = "
+        var <int>n = \"42\";
+        var <double>d = n / 3;
+        var text = <str>{1, 2};
+        func twice(x) { return x + x; }
+        var joined = twice(\"ab\");
+        var s = \"a\";
+        var kind = \"\";
+        try { var bad = s - 1; } catch (e) { kind = e.kind; }
+        try { var number = <int>\"abc\"; } catch (e) { kind = kind + \",\" + e.kind; }
+    ";
+    run_both(source)
+        .assert_global("n", Value::Int(42))
+        .assert_global("text", str("[1, 2]"))
+        .assert_global("joined", str("abab"))
+        .assert_global("kind", str("TypeError,ConversionError"));
+}
+
+#[test]
+fn impossible_assignments_and_conversions_are_rejected() {
+    let cases = [
+        ("var <int>count = 0;\nfunc reset() {\n    count = {1};\n}", 3, "Type error: cannot assign to 'count' of type 'int': Cannot convert a value of type 'list' to int"),
+        ("const <bool>DEBUG = \"maybe\";", 1, "Type error: cannot assign to 'DEBUG' of type 'bool': Cannot convert string 'maybe' to bool"),
+        ("var <int>xs[3] = {1, 2, 3};\nxs[0] = {:};", 2, "Type error in array assignment: cannot assign value of type 'map' to array of type 'int'"),
+        ("var ratio = <double>{\"a\": 1};", 1, "Type conversion failed: a value of type 'map' cannot be converted to 'double'"),
+    ];
+    for (source, line, message) in cases {
+        let error = run_err_both(source);
+        assert_eq!(error.diagnostic.message, message, "{}", source);
+        assert_eq!(error.diagnostic.line, line, "{}", source);
+        assert!(error.diagnostic.help.is_some(), "{}", source);
+    }
+}
+
+#[test]
+fn redeclared_globals_lose_their_type_inside_functions() {
+    // 函数可能在任意一次声明之后被调用，类型不一致时只能在运行时检查
+    let source = "var <int>x = 1;\nfunc f() { x = {1}; }\nvar x = \"s\";\nf();";
+    run_both(source).assert_text("x", "[1]");
+}
+
+#[test]
+fn existing_globals_keep_their_declared_types() {
+    for engine in [Engine::TreeWalker, Engine::Vm] {
+        let mut interpreter = Interpreter::new().with_engine(engine);
+        interpreter.run("var <int>total = 1;").unwrap();
+        let error = interpreter.run("var before = 0;\ntotal = {1, 2};").unwrap_err();
+        assert_eq!(error.diagnostic.line, 2, "{:?}", engine);
+        assert_eq!(interpreter.get_global("before"), None, "{:?}", engine);
+
+        let error = interpreter.run("var y = PI + \"1\";").unwrap_err();
+        assert_eq!(error.diagnostic.message, "Type error: cannot perform operation '+' between 'double' and 'str'", "{:?}", engine);
+    }
+}
@@ -0,0 +1,129 @@
+//! 示例脚本的端到端测试：tests/ 中的每个示例脚本在字节码虚拟机和语法树解释器下
+//! 必须产生相同的标准输出、错误输出和退出状态，有快照的脚本还要与快照一致
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// 运行时间过长、不适合在测试中执行的脚本
+const SKIPPED: &[&str] = &[
+    "speed.ecl", // 一千万次循环，用于手动比较两种引擎的速度
+];
+
+/// 有快照的脚本和预期的退出状态。预期的标准输出保存在 tests/expected/<脚本名>.out，
+/// 错误输出保存在 <脚本名>.err，没有 .err 文件表示错误输出为空
+const SNAPSHOTS: &[(&str, i32)] = &[
+    ("test_scope.ecl", 0),
+    ("test_break_continue.ecl", 0),
+    ("test_logical.ecl", 0),
+    ("test_arithmetic_ops.ecl", 1),
+    ("test_numeric_types.ecl", 1),
+    ("test_return.ecl", 0),
+    ("test_map.ecl", 0),
+    ("test_struct.ecl", 0),
+    ("test_match.ecl", 0),
+    ("test_closure.ecl", 0),
+    ("test_builtins.ecl", 0),
+    ("test_strings.ecl", 0),
+    ("test_math.ecl", 0),
+    ("test_const.ecl", 0),
+    ("test_try.ecl", 0),
+    ("test_type_check.ecl", 1),
+    ("test_typed_functions.ecl", 0),
+    ("test_multidimensional_arrays.ecl", 0),
+];
+
+fn run_ecl(script: &Path, vm: bool) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_ecl"));
+    if vm {
+        command.arg("--vm");
+    }
+    command
+        .arg(script)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap_or_else(|error| panic!("failed to run {}: {}", script.display(), error))
+}
+
+#[test]
+fn vm_matches_tree_walker_on_example_scripts() {
+    let mut scripts: Vec<_> = fs::read_dir("tests")
+        .expect("tests directory should exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ecl"))
+        .filter(|path| !SKIPPED.iter().any(|skipped| path.ends_with(skipped)))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no example scripts found");
+
+    let mut mismatches = Vec::new();
+    for script in &scripts {
+        let tree_walker = run_ecl(script, false);
+        let vm = run_ecl(script, true);
+        if tree_walker.stdout != vm.stdout || tree_walker.stderr != vm.stderr || tree_walker.status.code() != vm.status.code() {
+            mismatches.push(format!(
+                "{}\n--- tree-walker ({:?}) ---\n{}{}\n--- vm ({:?}) ---\n{}{}",
+                script.display(),
+                tree_walker.status.code(),
+                String::from_utf8_lossy(&tree_walker.stdout),
+                String::from_utf8_lossy(&tree_walker.stderr),
+                vm.status.code(),
+                String::from_utf8_lossy(&vm.stdout),
+                String::from_utf8_lossy(&vm.stderr),
+            ));
+        }
+    }
+
+    assert!(mismatches.is_empty(), "engines disagree on {} script(s):\n\n{}", mismatches.len(), mismatches.join("\n\n"));
+}
+
+fn expected(script: &str, extension: &str) -> String {
+    let path = Path::new("tests/expected").join(script).with_extension(extension);
+    fs::read_to_string(path).unwrap_or_default()
+}
+
+#[test]
+fn example_scripts_match_their_snapshots() {
+    let mut mismatches = Vec::new();
+    for &(script, code) in SNAPSHOTS {
+        let (stdout, stderr) = (expected(script, "out"), expected(script, "err"));
+        assert!(!stdout.is_empty() || !stderr.is_empty(), "no snapshot for {}", script);
+        for vm in [false, true] {
+            let output = run_ecl(&Path::new("tests").join(script), vm);
+            let (actual_stdout, actual_stderr) = (String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+            if actual_stdout != stdout || actual_stderr != stderr || output.status.code() != Some(code) {
+                mismatches.push(format!(
+                    "{} ({})\n--- expected ({}) ---\n{}{}\n--- actual ({:?}) ---\n{}{}",
+                    script,
+                    if vm { "vm" } else { "tree-walker" },
+                    code,
+                    stdout,
+                    stderr,
+                    output.status.code(),
+                    actual_stdout,
+                    actual_stderr,
+                ));
+            }
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{} snapshot(s) differ:\n\n{}", mismatches.len(), mismatches.join("\n\n"));
+}
+
+#[cfg(unix)]
+#[test]
+fn input_read_failures_are_runtime_errors() {
+    for vm in [false, true] {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_ecl"));
+        if vm {
+            command.arg("--vm");
+        }
+        // 从目录读取一行输入会失败
+        let stdin = fs::File::open("tests").expect("tests directory should exist");
+        let output = command.arg("tests/test_input.ecl").stdin(stdin).output().expect("failed to run test_input.ecl");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "vm: {}\n{}", vm, stderr);
+        assert!(stderr.starts_with("Runtime error: cannot read input for 'name'"), "vm: {}\n{}", vm, stderr);
+        assert!(stderr.contains("tests/test_input.ecl:5:"), "vm: {}\n{}", vm, stderr);
+    }
+}